@@ -0,0 +1,135 @@
+use std::sync::atomic::AtomicBool;
+
+use crate::agents::limits::SearchLimits;
+use crate::agents::report::{Ranking, SearchReport};
+use crate::{GameState, Move, NinjaBelief, Player};
+
+/// A player that picks moves for one side of a game.
+///
+/// Agents are told about every move of the game through `observe_move`, their own included, so they may
+/// keep state between moves such as what they know about hidden Ninjas or a search tree.
+/// Scores are positive when they favour Red, like `GameState::basic_value`.
+/// Moves come with a `SearchReport`, agents that don't search can report just the move with `SearchReport::new`.
+pub trait Agent: Send {
+    /// Called before the first move of a game the agent plays as `player`
+    fn new_game(&mut self, _state: &GameState, _player: Player) {}
+
+    /// Called with the state before `game_move` for every move played
+    fn observe_move(&mut self, _state: &GameState, _game_move: Move) {}
+
+    fn choose_move(&mut self, state: &GameState, limits: SearchLimits) -> Option<SearchReport>;
+
+    /// Scores every legal move, `None` if the agent can't rank moves
+    fn rank_moves(&mut self, _state: &GameState, _limits: SearchLimits) -> Option<Ranking> {
+        None
+    }
+
+    /// Searches `state` while the opponent thinks about their move, until `stop` is set.
+    ///
+    /// Whatever the agent finds, such as a search tree or transposition table, is kept for its next search.
+    /// Agents without anything to keep don't need to ponder.
+    fn ponder(&mut self, _state: &GameState, _stop: &AtomicBool) {}
+}
+
+/// An agent that searches from what its player knows about the opponent's hidden Ninjas.
+///
+/// Wrap it in `Tracked` to have the knowledge kept up to date from the moves of the game.
+pub trait BeliefAgent: Send {
+    /// Forgets anything kept from a previous game
+    fn new_game(&mut self) {}
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport>;
+
+    fn rank_moves_with_belief(
+        &mut self,
+        _state: &GameState,
+        _belief: &NinjaBelief,
+        _limits: SearchLimits,
+    ) -> Option<Ranking> {
+        None
+    }
+
+    /// See `Agent::ponder`
+    fn ponder_with_belief(&mut self, _state: &GameState, _belief: &NinjaBelief, _stop: &AtomicBool) {}
+}
+
+impl<A: BeliefAgent + ?Sized> BeliefAgent for Box<A> {
+    fn new_game(&mut self) {
+        (**self).new_game()
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        (**self).choose_move_with_belief(state, belief, limits)
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        (**self).rank_moves_with_belief(state, belief, limits)
+    }
+
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        (**self).ponder_with_belief(state, belief, stop)
+    }
+}
+
+/// Keeps a `NinjaBelief` for a `BeliefAgent` from the moves it observes.
+///
+/// Before `new_game` nothing is known about the game, so hidden Ninjas could be on any empty square.
+pub struct Tracked<A> {
+    agent: A,
+    belief: Option<NinjaBelief>,
+}
+
+impl<A: BeliefAgent> Tracked<A> {
+    pub fn new(agent: A) -> Tracked<A> {
+        Tracked { agent, belief: None }
+    }
+
+    fn belief(&self, state: &GameState) -> NinjaBelief {
+        let board = state.board();
+        self.belief
+            .unwrap_or_else(|| NinjaBelief::unknown(&board, board.turn))
+    }
+}
+
+impl<A: BeliefAgent> Agent for Tracked<A> {
+    fn new_game(&mut self, state: &GameState, player: Player) {
+        self.belief = Some(NinjaBelief::new(&state.board(), player));
+        self.agent.new_game();
+    }
+
+    fn observe_move(&mut self, state: &GameState, game_move: Move) {
+        if let Some(belief) = &mut self.belief {
+            belief.observe(&state.board(), game_move);
+        }
+    }
+
+    fn choose_move(&mut self, state: &GameState, limits: SearchLimits) -> Option<SearchReport> {
+        let belief = self.belief(state);
+        self.agent.choose_move_with_belief(state, &belief, limits)
+    }
+
+    fn rank_moves(&mut self, state: &GameState, limits: SearchLimits) -> Option<Ranking> {
+        let belief = self.belief(state);
+        self.agent.rank_moves_with_belief(state, &belief, limits)
+    }
+
+    fn ponder(&mut self, state: &GameState, stop: &AtomicBool) {
+        let belief = self.belief(state);
+        self.agent.ponder_with_belief(state, &belief, stop)
+    }
+}
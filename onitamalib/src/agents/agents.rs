@@ -1,10 +1,19 @@
 use enum_iterator::IntoEnumIterator;
-use instant::Duration;
 use serde::{Deserialize, Serialize};
 
-use crate::agents::{alphabeta, greedy, minimax, montecarlo};
-use crate::{GameState, Move};
+use crate::agents::agent::{Agent, BeliefAgent, Tracked};
+use crate::agents::alphabeta::{AlphabetaAgent, AlphabetaConfig};
+use crate::agents::greedy::{GreedyAgent, GreedyConfig};
+use crate::agents::ismcts::IsmctsAgent;
+use crate::agents::mcts::MctsAgent;
+use crate::agents::minimax::{MinimaxAgent, MinimaxConfig};
+use crate::agents::montecarlo::{HybridMonteCarloAgent, HybridMonteCarloConfig, PureMonteCarloAgent};
+use crate::agents::skill::{SkillLevel, Skilled};
+use crate::{EvalWeights, GameState, NinjaBelief, Ranking, SearchLimits, SearchReport};
 
+/// The agents built into the library, each with its default configuration.
+///
+/// Construct an agent's type directly to configure it, or implement `Agent` for one of your own.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, Hash, IntoEnumIterator)]
 pub enum AiAgent {
     Greedy,
@@ -12,40 +21,160 @@ pub enum AiAgent {
     HybridMonteCarlo,
     Minimax,
     Alphabeta,
+    InformationSetMonteCarlo,
+    MonteCarloTreeSearch,
 }
 
 impl AiAgent {
-    pub fn play_move(&self, state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+    /// Builds the agent to play a game, it learns about hidden Ninjas from the moves it observes
+    pub fn build(&self) -> Box<dyn Agent> {
+        self.build_with_weights(EvalWeights::default())
+    }
+
+    /// Like `build`, with agents that evaluate positions using `weights`, such as ones tuned by `tuneweights`
+    pub fn build_with_weights(&self, weights: EvalWeights) -> Box<dyn Agent> {
+        Box::new(Tracked::new(self.build_searcher(weights)))
+    }
+
+    /// Like `build`, weakened to play at `skill`
+    pub fn build_at_skill(&self, skill: SkillLevel) -> Box<dyn Agent> {
+        Box::new(Tracked::new(Skilled::new(self.build_searcher(EvalWeights::default()), skill)))
+    }
+
+    /// Like `build`, with alphabeta, MCTS and the alphabeta half of hybrid Monte Carlo searching on `threads` threads
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel(&self, threads: usize) -> Box<dyn Agent> {
+        Box::new(Tracked::new(self.build_parallel_searcher(threads, EvalWeights::default())))
+    }
+
+    /// Like `build_parallel`, weakened to play at `skill`
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel_at_skill(&self, threads: usize, skill: SkillLevel) -> Box<dyn Agent> {
+        self.build_parallel_at_skill_with_weights(threads, skill, EvalWeights::default())
+    }
+
+    /// Like `build_parallel_at_skill`, with agents that evaluate positions using `weights`
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel_at_skill_with_weights(
+        &self,
+        threads: usize,
+        skill: SkillLevel,
+        weights: EvalWeights,
+    ) -> Box<dyn Agent> {
+        Box::new(Tracked::new(Skilled::new(self.build_parallel_searcher(threads, weights), skill)))
+    }
+
+    #[cfg(feature = "parallel")]
+    fn build_parallel_searcher(&self, threads: usize, weights: EvalWeights) -> Box<dyn BeliefAgent> {
+        use crate::agents::mcts::MctsConfig;
+        use crate::agents::parallel::{LazySmpAgent, LazySmpHybridAgent, RootParallelMctsAgent};
+
         match self {
-            AiAgent::Greedy => greedy::greedy_agent(state),
-            AiAgent::PureMonteCarlo => montecarlo::pure_montecarlo_agent(state, duration),
-            AiAgent::HybridMonteCarlo => montecarlo::hybrid_hard_montecarlo_agent(state, duration),
-            AiAgent::Minimax => minimax::iterative_deepening(state, duration),
-            AiAgent::Alphabeta => alphabeta::iterative_deepening(state, duration),
+            AiAgent::Alphabeta => Box::new(LazySmpAgent::new(
+                AlphabetaConfig {
+                    weights,
+                    ..AlphabetaConfig::default()
+                },
+                threads,
+            )),
+            AiAgent::HybridMonteCarlo => Box::new(LazySmpHybridAgent::new(
+                HybridMonteCarloConfig {
+                    weights,
+                    ..HybridMonteCarloConfig::default()
+                },
+                threads,
+            )),
+            AiAgent::MonteCarloTreeSearch => Box::new(RootParallelMctsAgent::new(MctsConfig::default(), threads)),
+            _ => self.build_searcher(weights),
         }
     }
+
+    fn build_searcher(&self, weights: EvalWeights) -> Box<dyn BeliefAgent> {
+        match self {
+            AiAgent::Greedy => Box::new(GreedyAgent::new(GreedyConfig {
+                weights,
+                ..GreedyConfig::default()
+            })),
+            AiAgent::PureMonteCarlo => Box::new(PureMonteCarloAgent),
+            AiAgent::HybridMonteCarlo => Box::new(HybridMonteCarloAgent::new(HybridMonteCarloConfig {
+                weights,
+                ..HybridMonteCarloConfig::default()
+            })),
+            AiAgent::Minimax => Box::new(MinimaxAgent::new(MinimaxConfig {
+                weights,
+                ..MinimaxConfig::default()
+            })),
+            AiAgent::Alphabeta => Box::new(AlphabetaAgent::new(AlphabetaConfig {
+                weights,
+                ..AlphabetaConfig::default()
+            })),
+            AiAgent::InformationSetMonteCarlo => Box::new(IsmctsAgent::default()),
+            AiAgent::MonteCarloTreeSearch => Box::new(MctsAgent::default()),
+        }
+    }
+
+    /// Plays without knowing the history of the game, so hidden Ninjas could be on any empty square
+    pub fn play_move(&self, state: &GameState, limits: SearchLimits) -> Option<SearchReport> {
+        let board = state.board();
+        self.play_move_with_belief(state, &NinjaBelief::unknown(&board, board.turn), limits)
+    }
+
+    /// Plays a single move from what the player knows about hidden Ninjas, without following a game
+    pub fn play_move_with_belief(
+        &self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        self.build_searcher(EvalWeights::default()).choose_move_with_belief(state, belief, limits)
+    }
+
+    /// Like `play_move_with_belief`, weakened to play at `skill`
+    pub fn play_move_at_skill(
+        &self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        skill: SkillLevel,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        Skilled::new(self.build_searcher(EvalWeights::default()), skill).choose_move_with_belief(state, belief, limits)
+    }
+
+    pub fn rank_moves_with_belief(
+        &self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        self.build_searcher(EvalWeights::default()).rank_moves_with_belief(state, belief, limits)
+    }
 }
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "web")] {
+        use instant::Duration;
         use wasm_bindgen::prelude::*;
         use crate::MoveRequest;
 
         #[wasm_bindgen(js_name = agentMove)]
         pub fn agent_move(request: &JsValue) -> JsValue {
-            let MoveRequest { state, agent } = request.into_serde().unwrap();
-            let duration = Duration::from_millis(1000);
-            let (game_move, _) = agent.play_move(&state, duration).unwrap();
-            JsValue::from_serde(&game_move).unwrap()
+            let MoveRequest { state, agent, belief, skill } = request.into_serde().unwrap();
+            let limits = SearchLimits::time(Duration::from_millis(1000));
+            let board = state.board();
+            let belief = belief.unwrap_or_else(|| NinjaBelief::unknown(&board, board.turn));
+            let report = agent.play_move_at_skill(&state, &belief, skill.unwrap_or_default(), limits).unwrap();
+            JsValue::from_serde(&report).unwrap()
         }
 
         #[wasm_bindgen(js_name = rankMoves)]
         pub fn rank_moves(request: &JsValue) -> JsValue {
             // Used by training mode to request scoring of all possible moves
             let state: GameState = request.into_serde().unwrap();
-            let duration = Duration::from_millis(1000);
-            let ranked_moves = montecarlo::hybrid_hard_montecarlo_rank_moves(&state, duration);
-            JsValue::from_serde(&ranked_moves).unwrap()
+            let limits = SearchLimits::time(Duration::from_millis(1000));
+            let board = state.board();
+            let belief = NinjaBelief::unknown(&board, board.turn);
+            let ranking = AiAgent::HybridMonteCarlo.rank_moves_with_belief(&state, &belief, limits);
+            JsValue::from_serde(&ranking).unwrap()
         }
    }
 }
@@ -1,23 +1,165 @@
 use std::cmp;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
-use instant::{Duration, Instant};
+use instant::Instant;
 
-use crate::models::{GameState, Move, Player, Point};
-use crate::agents::ninja_logic;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{GameState, Move, Player};
+use crate::agents::agent::BeliefAgent;
+use crate::agents::belief::NinjaBelief;
+use crate::agents::limits::{timed_out, SearchLimits};
+use crate::agents::heuristics::{is_decisive, ply_earlier, ply_later, win_value};
+use crate::agents::report::best_scored;
+use crate::agents::evaluation::EVAL_SCALE;
+use crate::bitboard::{bit, side, square, SQUARES};
+use crate::{BitBoard, EvalWeights, Evaluator, Ranking, SearchReport};
+use crate::agents::transposition::{bound_for, Bound, Table, TableEntry, TranspositionTable};
+#[cfg(feature = "parallel")]
+use crate::agents::transposition::SharedTable;
 
 const MAX_DEPTH: u16 = 50;
+/// Half the width of the window searched around the previous iteration's score
+const ASPIRATION_WINDOW: i64 = 8 * EVAL_SCALE;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct AlphabetaConfig {
+    /// Deepest search started by iterative deepening
+    pub max_depth: u16,
+    /// Keep the transposition table between moves of a game
+    pub keep_table: bool,
+    pub weights: EvalWeights,
+}
+
+impl Default for AlphabetaConfig {
+    fn default() -> Self {
+        AlphabetaConfig {
+            max_depth: MAX_DEPTH,
+            keep_table: true,
+            weights: EvalWeights::default(),
+        }
+    }
+}
+
+pub struct AlphabetaAgent {
+    pub config: AlphabetaConfig,
+    evaluator: Evaluator,
+    table: TranspositionTable,
+}
+
+impl AlphabetaAgent {
+    pub fn new(config: AlphabetaConfig) -> AlphabetaAgent {
+        AlphabetaAgent {
+            config,
+            evaluator: Evaluator::new(config.weights),
+            table: TranspositionTable::default(),
+        }
+    }
+}
+
+impl Default for AlphabetaAgent {
+    fn default() -> Self {
+        AlphabetaAgent::new(AlphabetaConfig::default())
+    }
+}
+
+impl BeliefAgent for AlphabetaAgent {
+    fn new_game(&mut self) {
+        self.table.clear();
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        if !self.config.keep_table {
+            self.table.clear();
+        }
+        iterative_deepening_to_depth(
+            state,
+            belief,
+            limits,
+            self.config.max_depth,
+            &mut self.table,
+            &self.evaluator,
+        )
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        moves_scored_deepening(state, belief, limits, &self.evaluator)
+    }
+
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        if self.config.keep_table {
+            ponder(state, belief, self.config.max_depth, &mut self.table, &self.evaluator, stop);
+        }
+    }
+}
+
+pub fn iterative_deepening(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    evaluator: &Evaluator,
+) -> Option<SearchReport> {
+    let mut table = TranspositionTable::default();
+    iterative_deepening_with_table(state, belief, limits, &mut table, evaluator)
+}
+
+/// Like `iterative_deepening`, but keeps results in `table` so they can be reused by later searches
+pub fn iterative_deepening_with_table(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    table: &mut TranspositionTable,
+    evaluator: &Evaluator,
+) -> Option<SearchReport> {
+    iterative_deepening_to_depth(state, belief, limits, MAX_DEPTH, table, evaluator)
+}
+
+/// Searches one ply deeper at a time until `max_depth` or the depth in `limits`, whichever is shallower,
+/// playing the best move of the deepest search that finished within the limits
+pub fn iterative_deepening_to_depth(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    max_depth: u16,
+    table: &mut TranspositionTable,
+    evaluator: &Evaluator,
+) -> Option<SearchReport> {
+    let board = determinize(state, belief, limits)?;
+    let mut search = Search::new(table, evaluator, limits.nodes);
+    deepen(&board, limits, max_depth, 0, &mut search)
+}
 
-pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+/// Iterative deepening of `board` with `search`, each iteration searching `extra_depth` plies deeper
+fn deepen<T: Table>(
+    board: &BitBoard,
+    limits: SearchLimits,
+    max_depth: u16,
+    extra_depth: u16,
+    search: &mut Search<T>,
+) -> Option<SearchReport> {
     let start = Instant::now();
-    let deadline = start + duration;
-    let mut result: Option<(Move, i64)> = None;
-    for depth in 1..MAX_DEPTH {
-        if let Some((_, val)) = result {
-            if val == i64::MAX || val == i64::MIN {
+    let deadline = limits.deadline();
+    let max_depth = max_depth.min(limits.depth.unwrap_or(max_depth));
+    let mut result: Option<(Move, i64, u16)> = None;
+    for depth in 1..=max_depth {
+        if let Some((_, val, _)) = result {
+            if is_decisive(val) {
                 break;
             }
         }
-        match optimal_move_deadline(state, depth, deadline) {
+        let depth = cmp::min(depth + extra_depth, max_depth);
+        let previous = result.map(|(_, score, _)| score);
+        match optimal_move_deadline(board, depth, previous, deadline, search) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -26,26 +168,121 @@ pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Mov
                 );
                 break;
             }
-            Some(val) => {
-                result = Some(val);
+            Some((game_move, score)) => {
+                search.pv = search.table.principal_variation(board, game_move, depth as usize);
+                result = Some((game_move, score, depth));
             }
         };
     }
-    return result;
+    let (game_move, score, depth) = result?;
+    Some(search.report(board, game_move, score, depth))
+}
+
+/// Lazy SMP: `threads` iterative deepening searches share `table`, the others filling it for the first.
+///
+/// The threads share the node budget in `limits`, and searches finish in a different order every time, so
+/// parallel searches aren't reproducible. All of them give up once `stop` is set.
+#[cfg(feature = "parallel")]
+pub fn lazy_smp(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    table: &SharedTable,
+    evaluator: &Evaluator,
+    threads: usize,
+    stop: Option<&AtomicBool>,
+) -> Option<SearchReport> {
+    let board = &determinize(state, belief, limits)?;
+    with_helpers(board, limits, table, evaluator, threads, stop, |search| {
+        deepen(board, limits, MAX_DEPTH, 0, search)
+    })
+}
+
+/// Like `moves_scored_deepening`, with Lazy SMP helpers filling `table` for it as in `lazy_smp`
+#[cfg(feature = "parallel")]
+pub fn lazy_smp_moves_scored(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    table: &SharedTable,
+    evaluator: &Evaluator,
+    threads: usize,
+) -> Option<Ranking> {
+    let board = &determinize(state, belief, limits)?;
+    with_helpers(board, limits, table, evaluator, threads, None, |search| {
+        scored_deepening(board, limits, search)
+    })
+}
+
+/// Runs `main` with a search of `table`, while `threads - 1` helpers deepen `board` into the same table
+#[cfg(feature = "parallel")]
+fn with_helpers<R>(
+    board: &BitBoard,
+    limits: SearchLimits,
+    table: &SharedTable,
+    evaluator: &Evaluator,
+    threads: usize,
+    stop: Option<&AtomicBool>,
+    main: impl FnOnce(&mut Search<&SharedTable>) -> R,
+) -> R {
+    let done = AtomicBool::new(false);
+    let nodes = AtomicU64::new(0);
+    std::thread::scope(|scope| {
+        for helper in 1..threads {
+            let (done, nodes) = (&done, &nodes);
+            scope.spawn(move || {
+                let mut search = Search::new(table, evaluator, limits.nodes);
+                search.stop = Some(done);
+                search.shared_nodes = Some(nodes);
+                // Half the helpers run ahead, so threads don't all search the same depth at once
+                deepen(board, limits, MAX_DEPTH, (helper % 2) as u16, &mut search);
+            });
+        }
+        let mut search = Search::new(table, evaluator, limits.nodes);
+        search.stop = stop;
+        search.shared_nodes = Some(&nodes);
+        let result = main(&mut search);
+        done.store(true, Ordering::Relaxed);
+        result
+    })
+}
+
+/// Searches deeper and deeper until `stop` is set or `PONDER_TIME` runs out, leaving what it finds in `table`
+/// for the next search
+pub fn ponder(
+    state: &GameState,
+    belief: &NinjaBelief,
+    max_depth: u16,
+    table: &mut TranspositionTable,
+    evaluator: &Evaluator,
+    stop: &AtomicBool,
+) {
+    let limits = SearchLimits::ponder();
+    if let Some(board) = determinize(state, belief, limits) {
+        let mut search = Search::new(table, evaluator, None);
+        search.stop = Some(stop);
+        deepen(&board, limits, max_depth, 0, &mut search);
+    }
 }
 
 #[cfg(test)]
-pub fn iterative_deepening_just_depth(state: &GameState, duration: Duration) -> Option<u16> {
+pub fn iterative_deepening_just_depth(state: &GameState, duration: instant::Duration) -> Option<u16> {
+    let limits = SearchLimits::time(duration);
+    let board = determinize(state, &NinjaBelief::unknown(&state.board(), state.board().turn), limits)?;
+    let mut table = TranspositionTable::default();
+    let evaluator = Evaluator::default();
+    let mut search = Search::new(&mut table, &evaluator, None);
     let start = Instant::now();
-    let deadline = start + duration;
+    let deadline = limits.deadline();
     let mut result: Option<(Move, i64)> = None;
     for depth in 1..MAX_DEPTH {
         if let Some((_, val)) = result {
-            if val == i64::MAX || val == i64::MIN {
+            if is_decisive(val) {
                 break;
             }
         }
-        match optimal_move_deadline(state, depth, deadline) {
+        let previous = result.map(|(_, score)| score);
+        match optimal_move_deadline(&board, depth, previous, deadline, &mut search) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -54,61 +291,77 @@ pub fn iterative_deepening_just_depth(state: &GameState, duration: Duration) ->
                 );
                 return Some(depth);
             }
-            Some(val) => {
-                result = Some(val);
+            Some((game_move, score)) => {
+                search.pv = search.table.principal_variation(&board, game_move, depth as usize);
+                result = Some((game_move, score));
             }
         };
     }
-    return None;
+    None
 }
 
-fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Option<(Move, i64)> {
-    let timedout = || Instant::now() > deadline;
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
-            return None;
-        }
-    };
+/// Searches one placement of the opponent's hidden Ninjas, drawn from what the player knows about them
+fn determinize(state: &GameState, belief: &NinjaBelief, limits: SearchLimits) -> Option<BitBoard> {
+    match state {
+        GameState::Playing { board } => Some(belief.determinize(board, &mut limits.rng())),
+        GameState::Setup { .. } | GameState::Finished { .. } => None,
+    }
+}
+
+/// Searches the root within a window around the score of the previous iteration, widening it when the
+/// score falls outside
+fn optimal_move_deadline<T: Table>(
+    board: &BitBoard,
+    depth: u16,
+    previous: Option<i64>,
+    deadline: Option<Instant>,
+    search: &mut Search<T>,
+) -> Option<(Move, i64)> {
+    let mut board = *board;
     if depth == 0 {
         return None;
     }
-    if timedout() {
+    if timed_out(deadline) {
         return None;
     }
-    let mut game_moves = board.legal_moves().into_iter();
-    let mut best_move = game_moves.next().expect("No legal moves");
-    let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
-    for game_move in game_moves {
-        if timedout() {
-            return None;
-        }
-        let state = board
-            .try_move(game_move)
-            .expect("generated illegal move in loop");
-        let expected_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
-        match board.turn {
-            Player::Red if expected_score > best_score => {
-                best_move = game_move;
-                best_score = expected_score;
-            }
-            Player::Blue if expected_score < best_score => {
-                best_move = game_move;
-                best_score = expected_score;
-            }
-            _ => {}
-        };
+    let window = match previous {
+        Some(score) if !is_decisive(score) => (score - ASPIRATION_WINDOW, score + ASPIRATION_WINDOW),
+        _ => (i64::MIN, i64::MAX),
+    };
+    let (mut best_move, mut best_score) = search.search_root(&mut board, depth, window, deadline)?;
+    if best_score <= window.0 || best_score >= window.1 {
+        log::debug!("Score {} outside the aspiration window at depth {}", best_score, depth);
+        (best_move, best_score) = search.search_root(&mut board, depth, (i64::MIN, i64::MAX), deadline)?;
     }
-    return Some((best_move, best_score));
+    search.table.store(TableEntry {
+        hash: board.hash,
+        depth,
+        value: best_score,
+        bound: Bound::Exact,
+        best_move: Some(best_move),
+    });
+    Some((best_move, best_score))
+}
+
+pub fn moves_scored_deepening(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    evaluator: &Evaluator,
+) -> Option<Ranking> {
+    let board = determinize(state, belief, limits)?;
+    let mut table = TranspositionTable::default();
+    let mut search = Search::new(&mut table, evaluator, limits.nodes);
+    scored_deepening(&board, limits, &mut search)
 }
 
-pub fn moves_scored_deepening(state: &GameState, duration: Duration) -> Option<Vec<(Move, i64)>> {
+/// Scores every move of `board` one ply deeper at a time, ranking them by the deepest scores found in time
+fn scored_deepening<T: Table>(board: &BitBoard, limits: SearchLimits, search: &mut Search<T>) -> Option<Ranking> {
     let start = Instant::now();
-    let deadline = start + duration;
-    let mut result: Option<Vec<(Move, i64)>> = None;
-    for depth in 1..MAX_DEPTH {
-        match moves_scored_deadline(state, depth, deadline) {
+    let deadline = limits.deadline();
+    let mut result: Option<(Vec<(Move, i64)>, u16)> = None;
+    for depth in 1..limits.depth.map_or(MAX_DEPTH, |depth| depth.saturating_add(1).min(MAX_DEPTH)) {
+        match moves_scored_deadline(board, depth, deadline, search) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -118,25 +371,27 @@ pub fn moves_scored_deepening(state: &GameState, duration: Duration) -> Option<V
                 break;
             }
             Some(val) => {
-                result = Some(val);
+                if let Some((game_move, _)) = best_scored(&val, board.turn) {
+                    search.pv = search.table.principal_variation(board, game_move, depth as usize);
+                }
+                result = Some((val, depth));
             }
         };
     }
-    return result;
+    let (ranked_moves, depth) = result?;
+    let (game_move, score) = best_scored(&ranked_moves, board.turn)?;
+    let report = search.report(board, game_move, score, depth);
+    Some(Ranking { ranked_moves, report })
 }
 
-fn moves_scored_deadline(
-    state: &GameState,
+fn moves_scored_deadline<T: Table>(
+    board: &BitBoard,
     depth: u16,
-    deadline: Instant,
+    deadline: Option<Instant>,
+    search: &mut Search<T>,
 ) -> Option<Vec<(Move, i64)>> {
-    let timedout = || Instant::now() > deadline;
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
-            return None;
-        }
-    };
+    let timedout = || timed_out(deadline);
+    let mut board = *board;
     if depth == 0 {
         return None;
     }
@@ -145,102 +400,313 @@ fn moves_scored_deadline(
     }
     let mut scored_moves: Vec<(Move, i64)> = vec![];
     let game_moves = board.legal_moves().into_iter();
+    let limited = depth > 1;
     for game_move in game_moves {
-        if timedout() {
+        if timedout() || (limited && search.stopped()) {
             return None;
         }
-        let state = board
-            .try_move(game_move)
-            .expect("generated illegal move in loop");
-        let expected_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+        let expected_score = search.score_move(&mut board, game_move, depth - 1);
         scored_moves.push((game_move, expected_score));
     }
-    return Some(scored_moves);
+    if limited && search.stopped() {
+        return None;
+    }
+    Some(scored_moves)
 }
 
-fn minimax(state: &GameState, depth: u16, mut alpha: i64, mut beta: i64) -> i64 {
-    if depth == 0 {
-        return state.basic_value();
-    }
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
-            return state.basic_value();
-        }
-    };
-    // Clone and randomize hidden Ninja positions
-    let mut randomized_state = ninja_logic::randomize_hidden_ninjas(state.clone());
-
-    // Generate possible hidden Ninja positions for the opponent
-    let opponent_hidden_ninjas: Vec<Point> = board
-        .opponent_ninjas()
-        .iter()
-        .filter_map(|ninja| ninja.as_ref().filter(|(_, revealed)| !*revealed).map(|(pos, _)| *pos))
-        .collect();
-
-    let potential_positions = ninja_logic::possible_ninja_positions(&board, opponent_hidden_ninjas);
-
-    // Replace Ninja positions in the randomized state
-    ninja_logic::randomize_ninjas_with_positions(&mut randomized_state, &potential_positions);
-
-    // Extract the updated board from the randomized state
-    let updated_board = match &randomized_state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
-            return randomized_state.basic_value();
+/// Searches sharing one transposition table, counting the positions they visit
+struct Search<'a, T> {
+    table: T,
+    evaluator: &'a Evaluator,
+    nodes: u64,
+    max_nodes: Option<u64>,
+    /// Positions visited by every thread of a parallel search, which `max_nodes` limits instead of `nodes`
+    shared_nodes: Option<&'a AtomicU64>,
+    /// Set by another thread when the search is no longer needed
+    stop: Option<&'a AtomicBool>,
+    /// Line found by the previous iteration, searched first
+    pv: Vec<Move>,
+    /// Moves played from the root to the position being searched
+    line: Vec<Move>,
+    /// Quiet moves that last caused a cutoff at each ply
+    killers: Vec<[Option<Move>; 2]>,
+    /// How much quiet moves caused cutoffs, by player, source and destination
+    history: [[[u32; SQUARES]; SQUARES]; 2],
+}
+
+impl<'a, T: Table> Search<'a, T> {
+    fn new(table: T, evaluator: &'a Evaluator, max_nodes: Option<u64>) -> Search<'a, T> {
+        Search {
+            table,
+            evaluator,
+            nodes: 0,
+            max_nodes,
+            shared_nodes: None,
+            stop: None,
+            pv: vec![],
+            line: vec![],
+            killers: vec![],
+            history: [[[0; SQUARES]; SQUARES]; 2],
         }
-    };
+    }
 
-    let mut value = match updated_board.turn {
-        Player::Red => i64::MIN,
-        Player::Blue => i64::MAX,
-    };
+    /// Positions visited so far, by every thread when the search is shared
+    fn total_nodes(&self) -> u64 {
+        self.shared_nodes.map_or(self.nodes, |nodes| nodes.load(Ordering::Relaxed))
+    }
 
-    let legal_moves = updated_board.legal_moves().into_iter();
-    for game_move in legal_moves {
-        let next_state = updated_board.try_move(game_move).expect("illegal move generated");
-        let next_val = minimax(&next_state, depth - 1, alpha, beta);
-        value = match updated_board.turn {
-            Player::Red => cmp::max(value, next_val),
-            Player::Blue => cmp::min(value, next_val),
-        };
-        match updated_board.turn {
-            Player::Red if value >= beta => {
-                break;
+    /// Once out of nodes or stopped, scores are meaningless until the search is started again
+    fn stopped(&self) -> bool {
+        self.max_nodes.is_some_and(|max_nodes| self.total_nodes() >= max_nodes)
+            || self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    /// Plays `game_move` and searches the resulting position with a full window, then takes the move back
+    fn score_move(&mut self, board: &mut BitBoard, game_move: Move, depth: u16) -> i64 {
+        self.score_child(board, game_move, depth, (i64::MIN, i64::MAX), false)
+    }
+
+    /// Plays `game_move` and searches the resulting position, then takes the move back.
+    ///
+    /// Scouting first checks with a null window whether the move beats the best so far, only searching
+    /// it again with the full window when it does.
+    fn score_child(
+        &mut self,
+        board: &mut BitBoard,
+        game_move: Move,
+        depth: u16,
+        (alpha, beta): (i64, i64),
+        scout: bool,
+    ) -> i64 {
+        self.nodes += 1;
+        if let Some(nodes) = self.shared_nodes {
+            nodes.fetch_add(1, Ordering::Relaxed);
+        }
+        let turn = board.turn;
+        let undo = board.apply_move(game_move);
+        let score = match undo.winner() {
+            Some(winner) => ply_earlier(win_value(winner)),
+            None => {
+                self.line.push(game_move);
+                let mut search = |alpha: i64, beta: i64| ply_earlier(self.minimax(board, depth, ply_later(alpha), ply_later(beta)));
+                let score = match scout {
+                    true => {
+                        let score = match turn {
+                            Player::Red => search(alpha, alpha.saturating_add(1)),
+                            Player::Blue => search(beta.saturating_sub(1), beta),
+                        };
+                        match alpha < score && score < beta {
+                            true => search(alpha, beta),
+                            false => score,
+                        }
+                    }
+                    false => search(alpha, beta),
+                };
+                self.line.pop();
+                score
             }
-            Player::Blue if value <= alpha => {
+        };
+        board.undo(undo);
+        score
+    }
+
+    /// Searches the root's moves within `window`, `None` when out of time or nodes
+    fn search_root(
+        &mut self,
+        board: &mut BitBoard,
+        depth: u16,
+        (mut alpha, mut beta): (i64, i64),
+        deadline: Option<Instant>,
+    ) -> Option<(Move, i64)> {
+        let turn = board.turn;
+        let mut game_moves = self.ordered_moves(board).into_iter();
+        let mut best_move = game_moves.next().expect("No legal moves");
+        let mut best_score = self.score_child(board, best_move, depth - 1, (alpha, beta), false);
+        // The first ply only evaluates each move, so it always finishes and there is a move to play
+        let limited = depth > 1;
+        for game_move in game_moves {
+            match turn {
+                Player::Red => alpha = cmp::max(alpha, best_score),
+                Player::Blue => beta = cmp::min(beta, best_score),
+            };
+            if alpha >= beta {
                 break;
             }
-            Player::Red => {
-                alpha = cmp::max(alpha, value);
+            if timed_out(deadline) || (limited && self.stopped()) {
+                return None;
             }
-            Player::Blue => {
-                beta = cmp::min(beta, value);
+            let expected_score = self.score_child(board, game_move, depth - 1, (alpha, beta), true);
+            match turn {
+                Player::Red if expected_score > best_score => {
+                    best_move = game_move;
+                    best_score = expected_score;
+                }
+                Player::Blue if expected_score < best_score => {
+                    best_move = game_move;
+                    best_score = expected_score;
+                }
+                _ => {}
+            };
+        }
+        // Positions cut short by the node budget or another thread don't have their real scores
+        if limited && self.stopped() {
+            return None;
+        }
+        Some((best_move, best_score))
+    }
+
+    fn minimax(&mut self, board: &mut BitBoard, depth: u16, mut alpha: i64, mut beta: i64) -> i64 {
+        if depth == 0 {
+            return self.evaluator.evaluate(board);
+        }
+        if self.stopped() {
+            return 0;
+        }
+        let hash = board.hash;
+        if let Some(value) = self.table.probe(hash).and_then(|entry| entry.cutoff(depth, alpha, beta)) {
+            return value;
+        }
+        let (original_alpha, original_beta) = (alpha, beta);
+
+        let turn = board.turn;
+        let mut value = match turn {
+            Player::Red => i64::MIN,
+            Player::Blue => i64::MAX,
+        };
+        let mut best_move = None;
+
+        for game_move in self.ordered_moves(board) {
+            let scout = best_move.is_some();
+            let next_val = self.score_child(board, game_move, depth - 1, (alpha, beta), scout);
+            let improved = match turn {
+                Player::Red => next_val > value,
+                Player::Blue => next_val < value,
+            };
+            if improved || best_move.is_none() {
+                value = next_val;
+                best_move = Some(game_move);
             }
+            match turn {
+                Player::Red if value >= beta => {
+                    self.record_cutoff(board, game_move, depth);
+                    break;
+                }
+                Player::Blue if value <= alpha => {
+                    self.record_cutoff(board, game_move, depth);
+                    break;
+                }
+                Player::Red => {
+                    alpha = cmp::max(alpha, value);
+                }
+                Player::Blue => {
+                    beta = cmp::min(beta, value);
+                }
+            };
+        }
+        if self.stopped() {
+            return value;
+        }
+        self.table.store(TableEntry {
+            hash,
+            depth,
+            value,
+            bound: bound_for(value, original_alpha, original_beta),
+            best_move,
+        });
+        value
+    }
+
+    /// Legal moves from `board`, most promising first: the previous iteration's line, the best move stored
+    /// for the position, captures, killers, then quiet moves by history
+    fn ordered_moves(&self, board: &BitBoard) -> Vec<Move> {
+        let ply = self.line.len();
+        let pv_move = match self.pv.starts_with(&self.line) {
+            true => self.pv.get(ply).copied(),
+            false => None,
         };
+        // Hidden Ninjas may have been placed differently, so the stored move isn't always legal
+        let table_move = self.table.probe(board.hash).and_then(|entry| entry.best_move);
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        let mut game_moves = board.legal_moves();
+        game_moves.sort_by_key(|&game_move| {
+            if Some(game_move) == pv_move {
+                (0, 0)
+            } else if Some(game_move) == table_move {
+                (1, 0)
+            } else if !is_quiet(board, game_move) {
+                (2, 0)
+            } else if killers.contains(&Some(game_move)) {
+                (3, 0)
+            } else {
+                (4, u32::MAX - self.history_score(board.turn, game_move))
+            }
+        });
+        game_moves
+    }
+
+    /// Remembers a quiet move that refuted the position, to try it early in similar positions
+    fn record_cutoff(&mut self, board: &BitBoard, game_move: Move, depth: u16) {
+        if !is_quiet(board, game_move) {
+            return;
+        }
+        let ply = self.line.len();
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(game_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(game_move);
+        }
+        if let Move::Move { src, dst, .. } = game_move {
+            let entry = &mut self.history[side(board.turn)][square(src)][square(dst)];
+            *entry = entry.saturating_add(depth as u32 * depth as u32);
+        }
+    }
+
+    fn history_score(&self, turn: Player, game_move: Move) -> u32 {
+        match game_move {
+            Move::Move { src, dst, .. } => self.history[side(turn)][square(src)][square(dst)],
+            Move::Discard { .. } | Move::PlaceNinja { .. } => 0,
+        }
+    }
+
+    /// Report for playing `game_move` from `board` after searching `depth` plies
+    fn report(&self, board: &BitBoard, game_move: Move, score: i64, depth: u16) -> SearchReport {
+        SearchReport {
+            depth: Some(depth),
+            nodes: self.total_nodes(),
+            principal_variation: self.table.principal_variation(board, game_move, depth as usize),
+            ..SearchReport::new(game_move, score)
+        }
+    }
+}
+
+/// Moves that capture nothing
+fn is_quiet(board: &BitBoard, game_move: Move) -> bool {
+    match game_move {
+        Move::Move { dst, .. } => board.opponent_pieces() & bit(dst) == 0,
+        Move::Discard { .. } | Move::PlaceNinja { .. } => true,
     }
-    return value;
 }
 
-pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
+pub fn optimal_move(state: &GameState, depth: u16, evaluator: &Evaluator) -> Option<(Move, i64)> {
+    let mut board = match state {
+        GameState::Playing { board } => BitBoard::from(board),
+        GameState::Setup { .. } | GameState::Finished { .. } => {
             return None;
         }
     };
     if depth == 0 {
         return None;
     }
+    let mut table = TranspositionTable::default();
+    let mut search = Search::new(&mut table, evaluator, None);
     let mut game_moves = board.legal_moves().into_iter();
     let mut best_move = game_moves.next().expect("No legal moves");
-    let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+    let mut best_score = search.score_move(&mut board, best_move, depth - 1);
     for game_move in game_moves {
-        let state = board
-            .try_move(game_move)
-            .expect("generated illegal move in loop");
-        let expected_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+        let expected_score = search.score_move(&mut board, game_move, depth - 1);
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -253,5 +719,5 @@ pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
             _ => {}
         };
     }
-    return Some((best_move, best_score));
+    Some((best_move, best_score))
 }
@@ -0,0 +1,231 @@
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::{bit, move_targets, points, side, BitBoard, FULL_BOARD, SQUARES};
+use crate::models::{Board, Move, Player, Point};
+use crate::zobrist;
+
+/// Where the opponent's hidden Ninjas could be, as far as `observer` can tell.
+///
+/// Every placement of all hidden Ninjas together is tracked, so a Ninja seen to move doesn't take the
+/// others with it. Placements are only added when a hidden Ninja may have moved, and dropped as they stop
+/// fitting what the observer sees, so the real Ninjas are always one of them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NinjaBelief {
+    pub observer: Player,
+    // A player has at most two Ninjas, bit `b` of `placements[a]` with `b >= a` puts them on squares `a` and
+    // `b`, or a lone Ninja on `a` when they are the same
+    placements: [u32; SQUARES],
+}
+
+fn home_row(player: Player) -> u32 {
+    let y = match player {
+        Player::Red => 4,
+        Player::Blue => 0,
+    };
+    (0..5).fold(0, |row, x| row | bit(Point { x, y }))
+}
+
+// Hidden Ninjas never share a square with anything either player can see
+fn visible_pieces(board: &BitBoard) -> u32 {
+    board.kings[0]
+        | board.kings[1]
+        | board.pawns[0]
+        | board.pawns[1]
+        | board.revealed_ninjas[0]
+        | board.revealed_ninjas[1]
+        | board.wind_spirit
+}
+
+fn hidden_ninjas(board: &BitBoard, player: usize) -> u32 {
+    board.ninjas[player] & !board.revealed_ninjas[player]
+}
+
+// Every way of putting `count` Ninjas on `squares`
+fn spread(squares: u32, count: u32) -> Vec<u32> {
+    match count {
+        0 => vec![0],
+        _ => points(squares)
+            .flat_map(|pos| {
+                let above = squares & !((bit(pos) << 1) - 1);
+                spread(above, count - 1).into_iter().map(move |ninjas| ninjas | bit(pos))
+            })
+            .collect(),
+    }
+}
+
+impl NinjaBelief {
+    fn from_placements(observer: Player, placements: impl IntoIterator<Item = u32>) -> NinjaBelief {
+        let mut belief = NinjaBelief {
+            observer,
+            placements: [0; SQUARES],
+        };
+        for ninjas in placements.into_iter().filter(|ninjas| *ninjas != 0) {
+            let first = ninjas.trailing_zeros() as usize;
+            belief.placements[first] |= match ninjas & (ninjas - 1) {
+                0 => ninjas,
+                others => others,
+            };
+        }
+        belief
+    }
+
+    /// At the start of a game the opponent's Ninjas are somewhere on their home row
+    pub fn new(board: &Board, observer: Player) -> NinjaBelief {
+        let bits = BitBoard::from(board);
+        let opponent = side(observer.invert());
+        let squares = home_row(observer.invert()) & !visible_pieces(&bits);
+        NinjaBelief::from_placements(observer, spread(squares, hidden_ninjas(&bits, opponent).count_ones()))
+    }
+
+    /// Without the history of the game the hidden Ninjas could be on any empty square
+    pub fn unknown(board: &Board, observer: Player) -> NinjaBelief {
+        let bits = BitBoard::from(board);
+        let opponent = side(observer.invert());
+        let squares = FULL_BOARD & !visible_pieces(&bits);
+        NinjaBelief::from_placements(observer, spread(squares, hidden_ninjas(&bits, opponent).count_ones()))
+    }
+
+    /// Each placement the hidden Ninjas could be in as a mask of their squares, `[0]` when none are hidden
+    pub fn placements(&self) -> Vec<u32> {
+        let placements: Vec<u32> = (0..SQUARES)
+            .flat_map(|first| points(self.placements[first]).map(move |pos| (1 << first) | bit(pos)))
+            .collect();
+        match placements.is_empty() {
+            true => vec![0],
+            false => placements,
+        }
+    }
+
+    /// Squares any hidden Ninja could be on
+    pub fn squares(&self) -> Vec<Point> {
+        points(self.placements().into_iter().fold(0, |squares, ninjas| squares | ninjas)).collect()
+    }
+
+    pub fn contains(&self, pos: Point) -> bool {
+        self.placements().iter().any(|ninjas| ninjas & bit(pos) != 0)
+    }
+
+    /// Updates the belief with what the observer sees of `game_move` being played on `board`
+    pub fn observe(&mut self, board: &Board, game_move: Move) {
+        let before = BitBoard::from(board);
+        let opponent = side(self.observer.invert());
+        let opponent_moved = board.turn != self.observer;
+        let mut placements = vec![];
+        if let Move::PlaceNinja { player, .. } = game_move {
+            // Ninjas are placed out of sight, on any empty square of the home row
+            let squares = home_row(player) & !visible_pieces(&before);
+            for ninjas in self.placements() {
+                match player == self.observer {
+                    true => placements.push(ninjas),
+                    false => placements.extend(points(squares & !ninjas).map(|pos| ninjas | bit(pos))),
+                }
+            }
+            let count = hidden_ninjas(&before, opponent).count_ones() + (player != self.observer) as u32;
+            self.keep(placements, count, visible_pieces(&before));
+            return;
+        }
+        let after = match before.try_move(game_move) {
+            Ok((after, _)) => after,
+            Err(_) => return,
+        };
+        // Ninjas that were in sight may go back into hiding where they stand
+        let hiding = before.revealed_ninjas[opponent] & after.ninjas[opponent] & !after.revealed_ninjas[opponent];
+        for ninjas in self.placements() {
+            let (card, src, dst) = match game_move {
+                Move::Move { card, src, dst, .. } => (card, src, bit(dst)),
+                _ => {
+                    placements.push(ninjas | hiding);
+                    continue;
+                }
+            };
+            let targets = |pos| move_targets(card, false, false, board.turn, pos);
+            let moved_ninja = opponent_moved && before.ninjas[opponent] & bit(src) != 0;
+            let was_hidden = before.revealed_ninjas[opponent] & bit(src) == 0;
+            let shows = after.revealed_ninjas[opponent] & dst != 0;
+            match (moved_ninja, was_hidden, shows) {
+                // Any of the hidden Ninjas could have moved anywhere the card takes it
+                (true, true, false) => {
+                    for pos in points(ninjas) {
+                        let others = ninjas & !bit(pos);
+                        placements.extend(points(targets(pos)).map(|target| others | bit(target) | hiding));
+                    }
+                }
+                // The Ninja that shows itself at `dst` came from one of the squares that reach it
+                (true, true, true) => {
+                    for pos in points(ninjas).filter(|pos| targets(*pos) & dst != 0) {
+                        placements.push((ninjas & !bit(pos)) | hiding);
+                    }
+                }
+                // A Ninja seen at `src` hides again wherever it lands
+                (true, false, false) => {
+                    placements.extend(points(targets(src)).map(|target| ninjas | bit(target) | hiding));
+                }
+                // Only a Ninja on `dst` can go, captured or swapped out by the Wind Spirit
+                _ => {
+                    placements.push(ninjas | hiding);
+                    placements.push((ninjas & !dst) | hiding);
+                }
+            }
+        }
+        self.keep(placements, hidden_ninjas(&after, opponent).count_ones(), visible_pieces(&after));
+    }
+
+    // Keeps the placements of `count` Ninjas that are out of sight
+    fn keep(&mut self, placements: Vec<u32>, count: u32, visible: u32) {
+        let fitting = placements
+            .into_iter()
+            .filter(|ninjas| ninjas.count_ones() == count && ninjas & visible == 0);
+        *self = NinjaBelief::from_placements(self.observer, fitting);
+    }
+
+    /// Puts the opponent's hidden Ninjas in a placement drawn from the belief, leaving everything else as it is
+    pub fn sample<R: Rng>(&self, board: &mut BitBoard, rng: &mut R) {
+        let opponent = side(self.observer.invert());
+        let count = hidden_ninjas(board, opponent).count_ones();
+        if count == 0 {
+            return;
+        }
+        let free = FULL_BOARD & !visible_pieces(board);
+        let total: u32 = self.placements.iter().map(|row| row.count_ones()).sum();
+        let placement = match total {
+            0 => None,
+            _ => Some(self.nth_placement(rng.gen_range(0..total))),
+        };
+        let ninjas = match placement {
+            Some(ninjas) if ninjas.count_ones() == count && ninjas & !free == 0 => ninjas,
+            // Only happens when the belief wasn't kept up to date with the game
+            _ => {
+                let candidates: Vec<Point> = points(free).collect();
+                candidates
+                    .choose_multiple(rng, count as usize)
+                    .fold(0, |ninjas, pos| ninjas | bit(*pos))
+            }
+        };
+        board.ninjas[opponent] = board.revealed_ninjas[opponent] | ninjas;
+        board.hash = zobrist::hash(board);
+    }
+
+    // The placement `n` places into the order of `placements`, without collecting them
+    fn nth_placement(&self, mut n: u32) -> u32 {
+        for (first, row) in self.placements.iter().enumerate() {
+            let mut row = *row;
+            if n >= row.count_ones() {
+                n -= row.count_ones();
+                continue;
+            }
+            for _ in 0..n {
+                row &= row - 1;
+            }
+            return (1 << first) | (1 << row.trailing_zeros());
+        }
+        unreachable!("n is below the number of placements")
+    }
+
+    /// `board` with the opponent's hidden Ninjas sampled from the belief
+    pub fn determinize<R: Rng>(&self, board: &Board, rng: &mut R) -> BitBoard {
+        let mut bits = BitBoard::from(board);
+        self.sample(&mut bits, rng);
+        bits
+    }
+}
@@ -0,0 +1,272 @@
+// Red is maximizing player, Blue is minimizing
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::{move_targets, points, side, BitBoard};
+use crate::models::{Card, Player, Point};
+
+/// Number of terms in the evaluation, see `Features`
+pub const FEATURES: usize = 14;
+/// Evaluations count hundredths of a weight's unit, so fractional weights aren't lost to rounding
+pub const EVAL_SCALE: i64 = 100;
+
+/// Weights of each term of the evaluation, in the same units as `GameState::basic_value`.
+/// `Evaluator` scores come out `EVAL_SCALE` times larger.
+///
+/// Fields missing from JSON keep their default.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EvalWeights {
+    /// Value of the first, second, third and fourth pawn a player has left
+    pub pawns: [f64; 4],
+    pub ninja: f64,
+    /// Extra value of a Ninja the opponent hasn't seen
+    pub hidden_ninja: f64,
+    /// Per square closer a Master is to the opponent's Temple Arch
+    pub temple_distance: f64,
+    /// Per square the player's pieces can move to with their cards
+    pub mobility: f64,
+    /// Per square the Wind Spirit can move to with the player's cards
+    pub wind_spirit_mobility: f64,
+    /// Per opponent piece other than the Master that the player's cards could capture
+    pub threats: f64,
+    /// For the player's cards being able to capture the opponent's Master
+    pub master_threats: f64,
+    /// Per move offered by each card in the player's hand
+    pub cards: f64,
+    /// Per move offered by the spare card, which the player to move receives next
+    pub spare_card: f64,
+    /// Per square the player's Master can move to with their cards, a cornered Master is easier to capture
+    pub master_mobility: f64,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            pawns: [8.0, 6.0, 4.0, 2.0],
+            ninja: 6.0,
+            hidden_ninja: 1.0,
+            temple_distance: 1.0,
+            mobility: 0.3,
+            wind_spirit_mobility: 0.1,
+            threats: 1.0,
+            master_threats: 2.0,
+            cards: 0.3,
+            spare_card: 0.2,
+            master_mobility: 0.2,
+        }
+    }
+}
+
+impl EvalWeights {
+    /// Only counts pawns, scoring positions exactly like `basic_value`
+    pub fn material() -> EvalWeights {
+        EvalWeights {
+            pawns: [8.0, 6.0, 4.0, 2.0],
+            ninja: 0.0,
+            hidden_ninja: 0.0,
+            temple_distance: 0.0,
+            mobility: 0.0,
+            wind_spirit_mobility: 0.0,
+            threats: 0.0,
+            master_threats: 0.0,
+            cards: 0.0,
+            spare_card: 0.0,
+            master_mobility: 0.0,
+        }
+    }
+
+    /// The weights in the same order as `Features::values`
+    pub fn to_array(&self) -> [f64; FEATURES] {
+        let [pawn1, pawn2, pawn3, pawn4] = self.pawns;
+        [
+            pawn1,
+            pawn2,
+            pawn3,
+            pawn4,
+            self.ninja,
+            self.hidden_ninja,
+            self.temple_distance,
+            self.mobility,
+            self.wind_spirit_mobility,
+            self.threats,
+            self.master_threats,
+            self.cards,
+            self.spare_card,
+            self.master_mobility,
+        ]
+    }
+
+    pub fn from_array(weights: [f64; FEATURES]) -> EvalWeights {
+        EvalWeights {
+            pawns: [weights[0], weights[1], weights[2], weights[3]],
+            ninja: weights[4],
+            hidden_ninja: weights[5],
+            temple_distance: weights[6],
+            mobility: weights[7],
+            wind_spirit_mobility: weights[8],
+            threats: weights[9],
+            master_threats: weights[10],
+            cards: weights[11],
+            spare_card: weights[12],
+            master_mobility: weights[13],
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<EvalWeights, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Weights always serialize")
+    }
+}
+
+/// Each term of the evaluation measured for Red minus the same for Blue, so the evaluation is linear in the weights
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Features {
+    pub values: [f64; FEATURES],
+}
+
+// Squares a player's pieces other than the Wind Spirit can reach with their cards, and those the Spirit can
+fn reach(board: &BitBoard, player: Player) -> (u32, u32, u32) {
+    let own = side(player);
+    let own_pieces = board.kings[own] | board.pawns[own] | board.ninjas[own] | board.wind_spirit;
+    let mut squares = 0u32;
+    let mut moves = 0u32;
+    let mut spirit = 0u32;
+    for &card in board.hands[own].iter() {
+        for src in points(board.kings[own]) {
+            let targets = move_targets(card, true, false, player, src) & !own_pieces;
+            squares |= targets;
+            moves += targets.count_ones();
+        }
+        for src in points(board.pawns[own] | board.ninjas[own]) {
+            let targets = move_targets(card, false, false, player, src) & !own_pieces;
+            squares |= targets;
+            moves += targets.count_ones();
+        }
+        for src in points(board.wind_spirit) {
+            spirit += (move_targets(card, false, true, player, src) & !(board.kings[0] | board.kings[1])).count_ones();
+        }
+    }
+    (squares, moves, spirit)
+}
+
+// Squares a player's Master can move to with their cards
+fn master_squares(board: &BitBoard, player: Player) -> u32 {
+    let own = side(player);
+    let own_pieces = board.kings[own] | board.pawns[own] | board.ninjas[own] | board.wind_spirit;
+    let mut squares = 0u32;
+    for &card in board.hands[own].iter() {
+        for src in points(board.kings[own]) {
+            squares |= move_targets(card, true, false, player, src) & !own_pieces;
+        }
+    }
+    squares
+}
+
+fn temple_arch(player: Player) -> Point {
+    match player {
+        Player::Red => Point { x: 2, y: 0 },
+        Player::Blue => Point { x: 2, y: 4 },
+    }
+}
+
+// How far a player's Master is from the opponent's Temple Arch, taking as many king steps as needed
+fn temple_distance(board: &BitBoard, player: Player) -> f64 {
+    let goal = temple_arch(player);
+    points(board.kings[side(player)])
+        .map(|pos| (pos.x - goal.x).abs().max((pos.y - goal.y).abs()) as f64)
+        .next()
+        .unwrap_or(0.0)
+}
+
+/// Number of moves `card` offers a piece in the middle of the board
+fn card_moves(card: Card) -> f64 {
+    move_targets(card, false, false, Player::Red, Point { x: 2, y: 2 }).count_ones() as f64
+}
+
+impl Features {
+    pub fn new(board: &BitBoard) -> Features {
+        let mut values = [0.0; FEATURES];
+        let pawns = [board.pawns[0].count_ones(), board.pawns[1].count_ones()];
+        for (idx, value) in values.iter_mut().take(4).enumerate() {
+            let has = |count: u32| (count as usize > idx) as i32 as f64;
+            *value = has(pawns[0]) - has(pawns[1]);
+        }
+        let count = |mask: [u32; 2]| mask[0].count_ones() as f64 - mask[1].count_ones() as f64;
+        values[4] = count(board.ninjas);
+        values[5] = count([
+            board.ninjas[0] & !board.revealed_ninjas[0],
+            board.ninjas[1] & !board.revealed_ninjas[1],
+        ]);
+        values[6] = temple_distance(board, Player::Blue) - temple_distance(board, Player::Red);
+        let (red_squares, red_moves, red_spirit) = reach(board, Player::Red);
+        let (blue_squares, blue_moves, blue_spirit) = reach(board, Player::Blue);
+        values[7] = red_moves as f64 - blue_moves as f64;
+        values[8] = red_spirit as f64 - blue_spirit as f64;
+        let pieces = |player: usize| board.pawns[player] | board.ninjas[player];
+        values[9] = (pieces(1) & red_squares).count_ones() as f64 - (pieces(0) & blue_squares).count_ones() as f64;
+        values[10] =
+            (board.kings[1] & red_squares).count_ones() as f64 - (board.kings[0] & blue_squares).count_ones() as f64;
+        let hand = |player: usize| board.hands[player].iter().map(|&card| card_moves(card)).sum::<f64>();
+        values[11] = hand(0) - hand(1);
+        values[12] = match board.turn {
+            Player::Red => card_moves(board.spare_card),
+            Player::Blue => -card_moves(board.spare_card),
+        };
+        values[13] = master_squares(board, Player::Red).count_ones() as f64
+            - master_squares(board, Player::Blue).count_ones() as f64;
+        Features { values }
+    }
+}
+
+/// Scores positions of games still being played, positive when they favour Red
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Evaluator {
+    weights: EvalWeights,
+    // Weights that only count pawns can be scored much faster
+    material_only: bool,
+}
+
+impl Evaluator {
+    pub fn new(weights: EvalWeights) -> Evaluator {
+        let material_only = EvalWeights {
+            pawns: weights.pawns,
+            ..EvalWeights::material()
+        } == weights;
+        Evaluator { weights, material_only }
+    }
+
+    pub fn from_json(json: &str) -> Result<Evaluator, serde_json::Error> {
+        Ok(Evaluator::new(EvalWeights::from_json(json)?))
+    }
+
+    pub fn weights(&self) -> &EvalWeights {
+        &self.weights
+    }
+
+    pub fn evaluate(&self, board: &BitBoard) -> i64 {
+        let value = match self.material_only {
+            true => self.material(board),
+            false => {
+                let features = Features::new(board);
+                let weights = self.weights.to_array();
+                (0..FEATURES).map(|idx| weights[idx] * features.values[idx]).sum()
+            }
+        };
+        (value * EVAL_SCALE as f64).round() as i64
+    }
+
+    // `basic_value` generalized to any pawn weights
+    fn material(&self, board: &BitBoard) -> f64 {
+        let value = |count: u32| self.weights.pawns.iter().take(count as usize).sum::<f64>();
+        value(board.pawns[0].count_ones()) - value(board.pawns[1].count_ones())
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Evaluator::new(EvalWeights::default())
+    }
+}
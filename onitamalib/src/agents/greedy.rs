@@ -1,26 +1,82 @@
-use crate::minimax;
-use crate::models::{GameState, Move, Player};
+use serde::{Deserialize, Serialize};
+
+use crate::agents::minimax::Search;
+use crate::agents::agent::BeliefAgent;
+use crate::agents::limits::SearchLimits;
+use crate::agents::transposition::TranspositionTable;
+use crate::agents::report::best_scored;
+use crate::models::{GameState, Move};
+use crate::{BitBoard, EvalWeights, Evaluator, NinjaBelief, Ranking, SearchReport};
 use rand::prelude::*;
 
-pub fn greedy_agent(state: &GameState) -> Option<(Move, i64)> {
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct GreedyConfig {
+    /// Plies searched after each move
+    pub depth: u16,
+    pub weights: EvalWeights,
+}
+
+impl Default for GreedyConfig {
+    fn default() -> Self {
+        GreedyConfig {
+            depth: 3,
+            weights: EvalWeights::default(),
+        }
+    }
+}
+
+pub struct GreedyAgent {
+    pub config: GreedyConfig,
+    evaluator: Evaluator,
+}
+
+impl GreedyAgent {
+    pub fn new(config: GreedyConfig) -> GreedyAgent {
+        GreedyAgent {
+            config,
+            evaluator: Evaluator::new(config.weights),
+        }
+    }
+}
+
+impl Default for GreedyAgent {
+    fn default() -> Self {
+        GreedyAgent::new(GreedyConfig::default())
+    }
+}
+
+impl BeliefAgent for GreedyAgent {
+    fn choose_move_with_belief(&mut self, state: &GameState, _: &NinjaBelief, limits: SearchLimits) -> Option<SearchReport> {
+        greedy_agent(state, self.config.depth, limits, &self.evaluator)
+    }
+
+    fn rank_moves_with_belief(&mut self, state: &GameState, _: &NinjaBelief, limits: SearchLimits) -> Option<Ranking> {
+        greedy_rank_moves(state, self.config.depth, limits, &self.evaluator)
+    }
+}
+
+pub fn greedy_agent(state: &GameState, depth: u16, limits: SearchLimits, evaluator: &Evaluator) -> Option<SearchReport> {
+    Some(greedy_rank_moves(state, depth, limits, evaluator)?.report)
+}
+
+/// Scores each legal move by a fixed depth search, in random order so ties are broken at random.
+///
+/// Only the seed is taken from `limits`, the search always goes to `depth`.
+pub fn greedy_rank_moves(state: &GameState, depth: u16, limits: SearchLimits, evaluator: &Evaluator) -> Option<Ranking> {
     let board = match state {
         GameState::Playing { board, .. } => Some(board),
-        GameState::Finished { .. } => None,
+        GameState::Setup { .. } | GameState::Finished { .. } => None,
     }?;
-    let mut rng = thread_rng();
     let mut legal_moves = board.legal_moves();
-    legal_moves.shuffle(&mut rng);
-    let mut legal_moves: Vec<(Move, i64)> = legal_moves
+    legal_moves.shuffle(&mut limits.rng());
+    let mut table = TranspositionTable::default();
+    let mut search = Search::new(&mut table, evaluator, None);
+    let mut board = BitBoard::from(board);
+    let ranked_moves: Vec<(Move, i64)> = legal_moves
         .into_iter()
-        .map(|game_move| {
-            let state = board.try_move(game_move).unwrap();
-            (game_move, minimax::minimax(&state, 3))
-        })
+        .map(|game_move| (game_move, search.score_move(&mut board, game_move, depth)))
         .collect();
-    let key = |(_, score): &(Move, i64)| *score;
-    legal_moves.sort_by_key(key);
-    match board.turn {
-        Player::Red => legal_moves.pop(),
-        Player::Blue => Some(*legal_moves.get(0)?),
-    }
+    let (game_move, score) = best_scored(&ranked_moves, board.turn)?;
+    let report = search.report(&board, game_move, score, depth + 1);
+    Some(Ranking { ranked_moves, report })
 }
@@ -1,5 +1,6 @@
 // Red is maximizing player, Blue is minimizing
-use crate::{GameState, Player, Point};
+use crate::agents::evaluation::EVAL_SCALE;
+use crate::{BitBoard, GameState, Player, Point};
 
 fn value_from_pawn_count(count: usize) -> i64 {
     // Reserve one point for possibly distinguishing current player
@@ -16,18 +17,18 @@ fn value_from_pawn_count(count: usize) -> i64 {
 impl GameState {
     pub fn basic_value(&self) -> i64 {
         let board = match self {
-            GameState::Playing { board } => board,
+            GameState::Setup { board, .. } | GameState::Playing { board } => board,
             GameState::Finished {
                 winner: Player::Blue,
                 ..
             } => {
-                return i64::MIN;
+                return win_value(Player::Blue);
             }
             GameState::Finished {
                 winner: Player::Red,
                 ..
             } => {
-                return i64::MAX;
+                return win_value(Player::Red);
             }
         };
         let count_pieces = |acc, piece: &Option<Point>| match piece {
@@ -39,3 +40,50 @@ impl GameState {
         return value_from_pawn_count(red_count) - value_from_pawn_count(blue_count);
     }
 }
+
+/// Magnitude of the score of a finished game, in the same hundredths as `Evaluator` scores and far beyond
+/// anything an evaluation gives
+pub const WIN_SCORE: i64 = 10_000_000 * EVAL_SCALE;
+/// Longest forced win a score can describe
+const MAX_WIN_PLIES: i64 = 1000;
+
+/// Value of a finished game.
+///
+/// Searches move the value one step towards zero for every ply it is backed up, so a win `n` plies away
+/// scores `WIN_SCORE - n` and faster wins and slower losses score better.
+pub fn win_value(winner: Player) -> i64 {
+    match winner {
+        Player::Red => WIN_SCORE,
+        Player::Blue => -WIN_SCORE,
+    }
+}
+
+/// Whether `score` says how the game ends with best play, rather than estimating who is ahead
+pub fn is_decisive(score: i64) -> bool {
+    (WIN_SCORE - MAX_WIN_PLIES + 1..=WIN_SCORE).contains(&score.saturating_abs())
+}
+
+/// Score of a position from the score of the position one ply after it
+pub(crate) fn ply_earlier(score: i64) -> i64 {
+    match is_decisive(score) {
+        true => score - score.signum(),
+        false => score,
+    }
+}
+
+/// Inverse of `ply_earlier`, for passing search bounds on to the next ply
+pub(crate) fn ply_later(score: i64) -> i64 {
+    match is_decisive(score) {
+        true => score + score.signum(),
+        false => score,
+    }
+}
+
+impl BitBoard {
+    /// Same as `GameState::basic_value` for a game that is still being played
+    pub fn basic_value(&self) -> i64 {
+        let red_count = self.pawns[0].count_ones() as usize;
+        let blue_count = self.pawns[1].count_ones() as usize;
+        value_from_pawn_count(red_count) - value_from_pawn_count(blue_count)
+    }
+}
@@ -0,0 +1,248 @@
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::agent::BeliefAgent;
+use crate::agents::limits::{timed_out, SearchLimits};
+use crate::agents::montecarlo::simulate;
+use crate::{BitBoard, GameState, Move, NinjaBelief, Player, Ranking, SearchReport};
+
+const ITERATIONS_PER_TIME_CHECK: u32 = 50;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct IsmctsConfig {
+    /// UCB exploration constant, for rewards between 0 and 1
+    pub exploration: f64,
+}
+
+impl Default for IsmctsConfig {
+    fn default() -> Self {
+        IsmctsConfig { exploration: 0.7 }
+    }
+}
+
+#[derive(Default)]
+pub struct IsmctsAgent {
+    pub config: IsmctsConfig,
+}
+
+impl IsmctsAgent {
+    pub fn new(config: IsmctsConfig) -> IsmctsAgent {
+        IsmctsAgent { config }
+    }
+}
+
+impl BeliefAgent for IsmctsAgent {
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        ismcts_agent(state, belief, self.config, limits)
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        ismcts_rank_moves(state, belief, self.config, limits)
+    }
+}
+
+// Nodes are shared by every determinization that reaches them with the same moves
+struct Node {
+    /// `None` for the root
+    game_move: Option<Move>,
+    /// Player who played `game_move`, rewards are counted for them
+    mover: Player,
+    children: Vec<usize>,
+    visits: u32,
+    /// How often the node's move was legal when its parent was selected from
+    availability: u32,
+    reward: f64,
+}
+
+impl Node {
+    fn new(game_move: Option<Move>, mover: Player) -> Node {
+        Node {
+            game_move,
+            mover,
+            children: vec![],
+            visits: 0,
+            availability: 1,
+            reward: 0.0,
+        }
+    }
+
+    fn ucb(&self, exploration: f64) -> f64 {
+        let visits = self.visits as f64;
+        self.reward / visits + exploration * ((self.availability as f64).ln() / visits).sqrt()
+    }
+
+    /// Score of the node's move, positive when it favours Red like the other agents
+    fn score(&self) -> i64 {
+        let score = (2.0 * self.reward - self.visits as f64) as i64;
+        match self.mover {
+            Player::Red => score,
+            Player::Blue => -score,
+        }
+    }
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    exploration: f64,
+    iterations: u64,
+}
+
+impl Tree {
+    fn new(root_player: Player, exploration: f64) -> Tree {
+        let root = Node::new(None, root_player.invert());
+        Tree {
+            nodes: vec![root],
+            exploration,
+            iterations: 0,
+        }
+    }
+
+    // Our own moves are legal in every determinization, so the root's children are the legal moves
+    fn root_moves(&self) -> impl Iterator<Item = &Node> {
+        self.nodes[0].children.iter().map(move |&child| &self.nodes[child])
+    }
+
+    /// Follows the most visited moves down from the root
+    fn principal_variation(&self) -> Vec<Move> {
+        let mut line = vec![];
+        let mut node = 0;
+        while let Some(&child) = self.nodes[node].children.iter().max_by_key(|&&child| self.nodes[child].visits) {
+            line.extend(self.nodes[child].game_move);
+            node = child;
+        }
+        line
+    }
+
+    fn report(&self) -> Option<SearchReport> {
+        let best = self.root_moves().max_by_key(|child| child.visits)?;
+        Some(SearchReport {
+            nodes: self.nodes.len() as u64,
+            principal_variation: self.principal_variation(),
+            simulations: Some(self.iterations),
+            ..SearchReport::new(best.game_move?, best.score())
+        })
+    }
+
+    /// Plays one determinization down the tree, adding a node and finishing the game with random moves
+    fn iterate<R: Rng>(&mut self, mut board: BitBoard, rng: &mut R) {
+        let mut path = vec![0];
+        let mut node = 0;
+        let mut moves = Vec::with_capacity(32);
+        let winner = loop {
+            board.legal_moves_into(&mut moves);
+            let mover = board.turn;
+            let children = self.nodes[node].children.clone();
+            for &child in children.iter() {
+                if self.nodes[child].game_move.is_some_and(|game_move| moves.contains(&game_move)) {
+                    self.nodes[child].availability += 1;
+                }
+            }
+            let untried: Vec<Move> = moves
+                .iter()
+                .copied()
+                .filter(|game_move| children.iter().all(|&child| self.nodes[child].game_move != Some(*game_move)))
+                .collect();
+            let child = match untried.choose(rng) {
+                Some(&game_move) => {
+                    self.nodes.push(Node::new(Some(game_move), mover));
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push(child);
+                    child
+                }
+                None => {
+                    let nodes = &self.nodes;
+                    let exploration = self.exploration;
+                    let best = nodes[node]
+                        .children
+                        .iter()
+                        .copied()
+                        .filter(|&child| nodes[child].game_move.is_some_and(|game_move| moves.contains(&game_move)))
+                        .max_by(|&a, &b| nodes[a].ucb(exploration).total_cmp(&nodes[b].ucb(exploration)));
+                    match best {
+                        Some(best) => best,
+                        None => break None,
+                    }
+                }
+            };
+            let expanded = self.nodes[child].visits == 0;
+            let game_move = self.nodes[child].game_move.unwrap();
+            path.push(child);
+            node = child;
+            if let Some(winner) = board.apply_move(game_move).winner() {
+                break Some(winner);
+            }
+            if expanded {
+                break simulate(board, rng);
+            }
+        };
+        for node in path {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.reward += match winner {
+                Some(winner) if winner == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+    }
+}
+
+/// Information Set Monte Carlo Tree Search, drawing a fresh placement of the opponent's hidden Ninjas
+/// from `belief` on every iteration while sharing one tree between all of them
+pub fn ismcts_agent(
+    state: &GameState,
+    belief: &NinjaBelief,
+    config: IsmctsConfig,
+    limits: SearchLimits,
+) -> Option<SearchReport> {
+    search(state, belief, config, limits)?.report()
+}
+
+pub fn ismcts_rank_moves(
+    state: &GameState,
+    belief: &NinjaBelief,
+    config: IsmctsConfig,
+    limits: SearchLimits,
+) -> Option<Ranking> {
+    let tree = search(state, belief, config, limits)?;
+    let ranked_moves = tree
+        .root_moves()
+        .map(|child| Some((child.game_move?, child.score())))
+        .collect::<Option<Vec<(Move, i64)>>>()?;
+    Some(Ranking {
+        ranked_moves,
+        report: tree.report()?,
+    })
+}
+
+fn search(state: &GameState, belief: &NinjaBelief, config: IsmctsConfig, limits: SearchLimits) -> Option<Tree> {
+    let board = match state {
+        GameState::Playing { board } => *board,
+        GameState::Setup { .. } | GameState::Finished { .. } => return None,
+    };
+    let deadline = limits.deadline();
+    let max_iterations = limits.simulations.unwrap_or(u64::MAX);
+    let mut rng = limits.rng();
+    let mut tree = Tree::new(board.turn, config.exploration);
+    'search: while !timed_out(deadline) {
+        for _ in 0..ITERATIONS_PER_TIME_CHECK {
+            if tree.iterations >= max_iterations {
+                break 'search;
+            }
+            tree.iterate(belief.determinize(&board, &mut rng), &mut rng);
+            tree.iterations += 1;
+        }
+    }
+    log::info!("ISMCTS stopped after {} iterations", tree.iterations);
+    Some(tree)
+}
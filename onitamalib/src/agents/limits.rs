@@ -0,0 +1,79 @@
+use instant::{Duration, Instant};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "web")] {
+        pub type SearchRng = rand_mt::Mt;
+    } else {
+        pub type SearchRng = SmallRng;
+    }
+}
+
+/// Longest an agent ponders, so a player who walks away doesn't leave it searching
+pub const PONDER_TIME: Duration = Duration::from_secs(30);
+
+/// Bounds on a search, which stops at whichever it reaches first.
+///
+/// Time limits depend on how busy the machine is, so give a node or simulation budget with a `seed`
+/// for searches that play the same move every time.
+/// A search without any limit only stops once it has nothing left to search, Monte Carlo agents never do.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchLimits {
+    pub time: Option<Duration>,
+    /// Positions searched by tree searches
+    pub nodes: Option<u64>,
+    /// Plies searched by iterative deepening
+    pub depth: Option<u16>,
+    /// Games played out by Monte Carlo searches
+    pub simulations: Option<u64>,
+    /// Seeds every random choice of the search, including where hidden Ninjas are guessed to be
+    pub seed: Option<u64>,
+}
+
+impl SearchLimits {
+    /// Searches for `time` and nothing else
+    pub fn time(time: Duration) -> SearchLimits {
+        SearchLimits {
+            time: Some(time),
+            ..SearchLimits::default()
+        }
+    }
+
+    /// Pondering searches until it is stopped or `PONDER_TIME` runs out
+    pub fn ponder() -> SearchLimits {
+        SearchLimits::time(PONDER_TIME)
+    }
+
+    /// The same limits with the time shared between `parts` searches run one after another
+    pub fn split(&self, parts: u32) -> SearchLimits {
+        SearchLimits {
+            time: self.time.map(|time| time / parts),
+            ..*self
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.time.map(|time| Instant::now() + time)
+    }
+
+    /// Seeded by `seed`, from entropy otherwise, which `getrandom` takes from the browser's crypto on the web
+    pub fn rng(&self) -> SearchRng {
+        match self.seed {
+            Some(seed) => SearchRng::seed_from_u64(seed),
+            None => SearchRng::from_entropy(),
+        }
+    }
+}
+
+impl From<Duration> for SearchLimits {
+    fn from(time: Duration) -> Self {
+        SearchLimits::time(time)
+    }
+}
+
+/// Whether `deadline` has passed, never for a search without one
+pub fn timed_out(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() > deadline)
+}
@@ -0,0 +1,610 @@
+use instant::Duration;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::agents::agent::BeliefAgent;
+use crate::agents::heuristics::win_value;
+use crate::agents::limits::{timed_out, SearchLimits, SearchRng};
+use crate::agents::montecarlo::simulate;
+use crate::bitboard::{bit, side};
+use crate::{BitBoard, GameState, Move, NinjaBelief, Player, Point, Ranking, SearchReport};
+#[cfg(feature = "parallel")]
+use crate::ForcedWin;
+
+const ITERATIONS_PER_TIME_CHECK: u64 = 50;
+/// Past this the tree stops growing and iterations only add playouts
+const MAX_NODES: usize = 1 << 20;
+const MAX_ROLLOUT_PLIES: usize = 1000;
+/// How often heuristic rollouts pick a capture over any other move
+const CAPTURE_PROBABILITY: f64 = 0.8;
+
+/// How a child is picked while walking down the tree
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Selection {
+    /// UCB1, every child is tried once before any is revisited
+    Uct,
+    /// AlphaZero style, exploration is weighted by a prior favouring captures and wins
+    Puct,
+}
+
+/// How games are finished from a new leaf
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rollout {
+    Random,
+    /// Takes Masters whenever possible and prefers captures
+    Heuristic,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Budget {
+    Time(Duration),
+    Iterations(u64),
+}
+
+impl From<Budget> for SearchLimits {
+    fn from(budget: Budget) -> Self {
+        match budget {
+            Budget::Time(time) => SearchLimits::time(time),
+            Budget::Iterations(iterations) => SearchLimits {
+                simulations: Some(iterations),
+                ..SearchLimits::default()
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct MctsConfig {
+    pub selection: Selection,
+    pub rollout: Rollout,
+    /// Exploration constant, rewards are between 0 and 1
+    pub exploration: f64,
+    /// Keep the part of the tree below the previous search when it reaches the new position
+    pub reuse_tree: bool,
+    /// Run this many iterations for each move as an agent, rather than searching within the limits given
+    pub iterations: Option<u64>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            selection: Selection::Puct,
+            rollout: Rollout::Heuristic,
+            exploration: 1.4,
+            reuse_tree: true,
+            iterations: None,
+        }
+    }
+}
+
+struct Node {
+    /// `None` for the root
+    game_move: Option<Move>,
+    /// Player who played `game_move`, a turn can take them more than one move
+    mover: Player,
+    /// Position after `game_move`
+    board: BitBoard,
+    /// Winner with best play from here on, once the search has proven it.
+    /// Known straight away when `game_move` finished the game.
+    winner: Option<Player>,
+    prior: f64,
+    children: Vec<usize>,
+    visits: u32,
+    /// Sum of rewards for the player who played `game_move`
+    reward: f64,
+}
+
+impl Node {
+    fn new(game_move: Option<Move>, mover: Player, board: BitBoard, winner: Option<Player>, prior: f64) -> Node {
+        Node {
+            game_move,
+            mover,
+            board,
+            winner,
+            prior,
+            children: vec![],
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    /// Score of the node's move from its playouts, positive when it favours Red like the other agents
+    fn score(&self) -> i64 {
+        let score = (2.0 * self.reward - self.visits as f64) as i64;
+        match self.mover {
+            Player::Red => score,
+            Player::Blue => -score,
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        match self.visits {
+            0 => 0.5,
+            visits => self.reward / visits as f64,
+        }
+    }
+}
+
+/// Monte Carlo Tree Search over a single, fully known position.
+///
+/// The tree is kept between calls to `search`, so following a game with one `Mcts` reuses the work done
+/// on positions it already explored.
+pub struct Mcts {
+    config: MctsConfig,
+    nodes: Vec<Node>,
+}
+
+impl Mcts {
+    pub fn new(config: MctsConfig) -> Mcts {
+        Mcts { config, nodes: vec![] }
+    }
+
+    /// Searches `board` within `budget`, reporting the most visited move and its score with Red positive
+    pub fn search(&mut self, board: &BitBoard, budget: Budget) -> Option<SearchReport> {
+        let limits = SearchLimits::from(budget);
+        self.search_within(board, limits, &mut limits.rng(), None)
+    }
+
+    /// Searches until out of time or simulations, until the root is proven or until `stop` is set
+    fn search_within(
+        &mut self,
+        board: &BitBoard,
+        limits: SearchLimits,
+        rng: &mut SearchRng,
+        stop: Option<&AtomicBool>,
+    ) -> Option<SearchReport> {
+        self.set_root(board);
+        let deadline = limits.deadline();
+        let max_iterations = limits.simulations.unwrap_or(u64::MAX);
+        let mut iterations = 0u64;
+        let stopped = || stop.is_some_and(|stop| stop.load(Ordering::Relaxed));
+        'search: while !timed_out(deadline) && !stopped() {
+            for _ in 0..ITERATIONS_PER_TIME_CHECK {
+                if iterations >= max_iterations || self.nodes[0].winner.is_some() {
+                    break 'search;
+                }
+                self.iterate(rng);
+                iterations += 1;
+            }
+        }
+        log::info!("MCTS finished after {} iterations with {} nodes", iterations, self.nodes.len());
+        let best = self.best_child(0)?;
+        Some(SearchReport {
+            nodes: self.nodes.len() as u64,
+            principal_variation: self.line_from(0),
+            simulations: Some(iterations),
+            ..SearchReport::new(self.nodes[best].game_move?, self.score(best))
+        })
+    }
+
+    /// Playouts through the current root, including any kept from earlier searches
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.first().map_or(0, |root| root.visits)
+    }
+
+    /// A proven win if there is one, otherwise the most visited child that isn't a proven loss
+    fn best_child(&self, node: usize) -> Option<usize> {
+        let turn = self.nodes.get(node)?.board.turn;
+        self.candidates(node).into_iter().max_by_key(|&child| {
+            let child = &self.nodes[child];
+            (child.winner == Some(turn), child.visits)
+        })
+    }
+
+    /// Score of the node's move, proven moves score like the forced win alphabeta would find along their line
+    fn score(&self, node: usize) -> i64 {
+        match self.nodes[node].winner {
+            Some(winner) => {
+                let plies = 1 + self.line_from(node).len() as i64;
+                win_value(winner) - win_value(winner).signum() * plies
+            }
+            None => self.nodes[node].score(),
+        }
+    }
+
+    /// Follows the best children down from `node`
+    fn line_from(&self, mut node: usize) -> Vec<Move> {
+        let mut line = vec![];
+        while let Some(child) = self.best_child(node) {
+            line.extend(self.nodes[child].game_move);
+            node = child;
+        }
+        line
+    }
+
+    /// Every move from the root scored by the last search
+    pub fn ranked_moves(&self) -> Option<Vec<(Move, i64)>> {
+        let root = self.nodes.first()?;
+        root.children
+            .iter()
+            .map(|&child| Some((self.nodes[child].game_move?, self.score(child))))
+            .collect()
+    }
+
+    /// Makes `board` the root, keeping its subtree if the previous search reached it within two moves
+    fn set_root(&mut self, board: &BitBoard) {
+        let reused = match self.config.reuse_tree && !self.nodes.is_empty() {
+            true => self.nodes[0]
+                .children
+                .iter()
+                .flat_map(|&child| std::iter::once(child).chain(self.nodes[child].children.iter().copied()))
+                .find(|&node| self.nodes[node].board == *board),
+            false => None,
+        };
+        match reused {
+            Some(node) => {
+                let mut nodes = Vec::with_capacity(self.nodes.len());
+                self.copy_subtree(node, &mut nodes);
+                nodes[0].game_move = None;
+                self.nodes = nodes;
+            }
+            _ => {
+                self.nodes.clear();
+                self.nodes.push(Node::new(None, board.turn.invert(), *board, None, 1.0));
+            }
+        }
+    }
+
+    // Copies `node` and its descendants into `nodes`, returning the new index of `node`
+    fn copy_subtree(&mut self, node: usize, nodes: &mut Vec<Node>) -> usize {
+        let index = nodes.len();
+        let source = &mut self.nodes[node];
+        let children = std::mem::take(&mut source.children);
+        nodes.push(Node::new(source.game_move, source.mover, source.board, source.winner, source.prior));
+        nodes[index].visits = source.visits;
+        nodes[index].reward = source.reward;
+        for child in children {
+            let child = self.copy_subtree(child, nodes);
+            nodes[index].children.push(child);
+        }
+        index
+    }
+
+    fn iterate<R: Rng>(&mut self, rng: &mut R) {
+        let mut path = vec![0];
+        let mut node = 0;
+        let winner = loop {
+            if let Some(winner) = self.nodes[node].winner {
+                break Some(winner);
+            }
+            if self.nodes[node].children.is_empty() {
+                if self.nodes[node].visits == 0 || self.nodes.len() >= MAX_NODES {
+                    break self.rollout(self.nodes[node].board, rng);
+                }
+                self.expand(node);
+            }
+            node = self.select(node, rng);
+            path.push(node);
+        };
+        for &node in path.iter().rev() {
+            self.prove(node);
+        }
+        for node in path {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.reward += match winner {
+                Some(winner) if winner == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+    }
+
+    fn expand(&mut self, node: usize) {
+        let board = self.nodes[node].board;
+        let mut moves = Vec::with_capacity(32);
+        board.legal_moves_into(&mut moves);
+        let opponent_pieces = board.opponent_pieces();
+        let mut children: Vec<Node> = moves
+            .into_iter()
+            .map(|game_move| {
+                let mut child = board;
+                let winner = child.apply_move(game_move).winner();
+                let prior = match (winner, game_move) {
+                    (Some(_), _) => 8.0,
+                    (None, Move::Move { src, dst, .. })
+                        if opponent_pieces & bit(dst) != 0 && board.wind_spirit & bit(src) == 0 =>
+                    {
+                        3.0
+                    }
+                    _ => 1.0,
+                };
+                Node::new(Some(game_move), board.turn, child, winner, prior)
+            })
+            .collect();
+        let total: f64 = children.iter().map(|child| child.prior).sum();
+        for child in children.iter_mut() {
+            child.prior /= total;
+        }
+        let first = self.nodes.len();
+        self.nodes.extend(children);
+        self.nodes[node].children = (first..self.nodes.len()).collect();
+    }
+
+    /// Marks `node` as won once one of the moves from it wins for the player to move, or lost once they all lose
+    fn prove(&mut self, node: usize) {
+        let player = self.nodes[node].board.turn;
+        let children = &self.nodes[node].children;
+        if self.nodes[node].winner.is_some() || children.is_empty() {
+            return;
+        }
+        let winners = || children.iter().map(|&child| self.nodes[child].winner);
+        if winners().any(|winner| winner == Some(player)) {
+            self.nodes[node].winner = Some(player);
+        } else if winners().all(|winner| winner == Some(player.invert())) {
+            self.nodes[node].winner = Some(player.invert());
+        }
+    }
+
+    /// Children worth searching, a proven win on its own, otherwise all but the proven losses
+    fn candidates(&self, node: usize) -> Vec<usize> {
+        let player = self.nodes[node].board.turn;
+        let children = &self.nodes[node].children;
+        if let Some(&win) = children.iter().find(|&&child| self.nodes[child].winner == Some(player)) {
+            return vec![win];
+        }
+        let open: Vec<usize> = children
+            .iter()
+            .copied()
+            .filter(|&child| self.nodes[child].winner != Some(player.invert()))
+            .collect();
+        match open.is_empty() {
+            true => children.clone(),
+            false => open,
+        }
+    }
+
+    fn select<R: Rng>(&self, node: usize, rng: &mut R) -> usize {
+        let parent = &self.nodes[node];
+        let children = &self.candidates(node);
+        let exploration = self.config.exploration;
+        match self.config.selection {
+            Selection::Uct => {
+                let unvisited: Vec<usize> = children
+                    .iter()
+                    .copied()
+                    .filter(|&child| self.nodes[child].visits == 0)
+                    .collect();
+                if let Some(&child) = unvisited.choose(rng) {
+                    return child;
+                }
+                let log_visits = (parent.visits as f64).ln();
+                let ucb = |child: &Node| child.mean() + exploration * (log_visits / child.visits as f64).sqrt();
+                children
+                    .iter()
+                    .copied()
+                    .max_by(|&a, &b| ucb(&self.nodes[a]).total_cmp(&ucb(&self.nodes[b])))
+                    .unwrap()
+            }
+            Selection::Puct => {
+                let sqrt_visits = (parent.visits as f64).sqrt();
+                let puct = |child: &Node| {
+                    child.mean() + exploration * child.prior * sqrt_visits / (1.0 + child.visits as f64)
+                };
+                children
+                    .iter()
+                    .copied()
+                    .max_by(|&a, &b| puct(&self.nodes[a]).total_cmp(&puct(&self.nodes[b])))
+                    .unwrap()
+            }
+        }
+    }
+
+    fn rollout<R: Rng>(&self, board: BitBoard, rng: &mut R) -> Option<Player> {
+        match self.config.rollout {
+            Rollout::Random => simulate(board, rng),
+            Rollout::Heuristic => heuristic_simulate(board, rng),
+        }
+    }
+}
+
+/// Like `simulate`, but always wins when a single move can and usually prefers captures
+fn heuristic_simulate<R: Rng>(mut board: BitBoard, rng: &mut R) -> Option<Player> {
+    let mut moves = Vec::with_capacity(32);
+    let mut captures = Vec::with_capacity(8);
+    for _ in 0..MAX_ROLLOUT_PLIES {
+        board.legal_moves_into(&mut moves);
+        let player = side(board.turn);
+        let opponent_pieces = board.opponent_pieces();
+        let temple_arch = match board.turn {
+            Player::Red => bit(Point { x: 2, y: 0 }),
+            Player::Blue => bit(Point { x: 2, y: 4 }),
+        };
+        captures.clear();
+        let mut winning_move = None;
+        for &game_move in moves.iter() {
+            if let Move::Move { src, dst, .. } = game_move {
+                // The Wind Spirit swaps places instead of capturing
+                if board.wind_spirit & bit(src) != 0 {
+                    continue;
+                }
+                let reaches_temple = board.kings[player] & bit(src) != 0 && bit(dst) == temple_arch;
+                if board.kings[1 - player] & bit(dst) != 0 || reaches_temple {
+                    winning_move = Some(game_move);
+                    break;
+                }
+                if opponent_pieces & bit(dst) != 0 {
+                    captures.push(game_move);
+                }
+            }
+        }
+        let game_move = match winning_move {
+            Some(game_move) => game_move,
+            None if !captures.is_empty() && rng.gen_bool(CAPTURE_PROBABILITY) => *captures.choose(rng).unwrap(),
+            None => match moves.choose(rng) {
+                Some(game_move) => *game_move,
+                None => return None,
+            },
+        };
+        if let Some(winner) = board.apply_move(game_move).winner() {
+            return Some(winner);
+        }
+    }
+    None
+}
+
+/// Root parallel search: every tree searches `board` on its own thread within `limits`, then their
+/// statistics for each move from the root are added together.
+///
+/// Trees keep their own subtrees between searches like a single `Mcts` does.
+#[cfg(feature = "parallel")]
+pub fn root_parallel(
+    trees: &mut [Mcts],
+    board: &BitBoard,
+    limits: SearchLimits,
+    stop: Option<&AtomicBool>,
+) -> Option<Ranking> {
+    let reports: Vec<Option<SearchReport>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = trees
+            .iter_mut()
+            .zip(0u64..)
+            .map(|(mcts, index)| {
+                // Seeded trees still need to play out different games
+                let limits = SearchLimits {
+                    seed: limits.seed.map(|seed| seed.wrapping_add(index)),
+                    ..limits
+                };
+                scope.spawn(move || mcts.search_within(board, limits, &mut limits.rng(), stop))
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    // Visits, rewards and proven score of each move from the root, added up over every tree
+    let mut merged: Vec<(Move, u32, f64, Option<i64>)> = vec![];
+    for mcts in trees.iter() {
+        for &index in mcts.nodes[0].children.iter() {
+            let child = &mcts.nodes[index];
+            let game_move = child.game_move?;
+            let proven = child.winner.map(|_| mcts.score(index));
+            match merged.iter_mut().find(|(merged_move, ..)| *merged_move == game_move) {
+                Some((_, visits, reward, merged_proven)) => {
+                    *visits += child.visits;
+                    *reward += child.reward;
+                    *merged_proven = merged_proven.or(proven);
+                }
+                None => merged.push((game_move, child.visits, child.reward, proven)),
+            }
+        }
+    }
+    let turn = board.turn;
+    let score = |visits: u32, reward: f64, proven: Option<i64>| {
+        let score = (2.0 * reward - visits as f64) as i64;
+        match (proven, turn) {
+            (Some(proven), _) => proven,
+            (None, Player::Red) => score,
+            (None, Player::Blue) => -score,
+        }
+    };
+    let winner = |proven: Option<i64>| proven.and_then(ForcedWin::from_score).map(|win| win.winner);
+    let ranked_moves: Vec<(Move, i64)> = merged
+        .iter()
+        .map(|&(game_move, visits, reward, proven)| (game_move, score(visits, reward, proven)))
+        .collect();
+    // Same preferences as `Mcts::best_child`, a proven loss is only played when every move loses
+    let all_lose = merged.iter().all(|&(.., proven)| winner(proven) == Some(turn.invert()));
+    let &(game_move, visits, reward, proven) = merged
+        .iter()
+        .filter(|&&(.., proven)| all_lose || winner(proven) != Some(turn.invert()))
+        .max_by_key(|&&(_, visits, _, proven)| (winner(proven) == Some(turn), visits))?;
+    let reports: Vec<SearchReport> = reports.into_iter().flatten().collect();
+    let principal_variation = reports
+        .iter()
+        .find(|report| report.game_move == game_move)
+        .map_or(vec![game_move], |report| report.principal_variation.clone());
+    let report = SearchReport {
+        nodes: reports.iter().map(|report| report.nodes).sum(),
+        principal_variation,
+        simulations: Some(reports.iter().filter_map(|report| report.simulations).sum()),
+        ..SearchReport::new(game_move, score(visits, reward, proven))
+    };
+    Some(Ranking { ranked_moves, report })
+}
+
+/// Keeps one tree for the whole game, so searches start from what was found on earlier moves
+pub struct MctsAgent {
+    mcts: Mcts,
+}
+
+impl MctsAgent {
+    pub fn new(config: MctsConfig) -> MctsAgent {
+        MctsAgent { mcts: Mcts::new(config) }
+    }
+
+    fn search(&mut self, state: &GameState, belief: &NinjaBelief, limits: SearchLimits) -> Option<SearchReport> {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Setup { .. } | GameState::Finished { .. } => return None,
+        };
+        let limits = match self.mcts.config.iterations {
+            Some(iterations) => SearchLimits::from(Budget::Iterations(iterations)),
+            None => limits,
+        };
+        let mut rng = limits.rng();
+        let board = belief.determinize(board, &mut rng);
+        self.mcts.search_within(&board, limits, &mut rng, None)
+    }
+}
+
+impl Default for MctsAgent {
+    fn default() -> Self {
+        MctsAgent::new(MctsConfig::default())
+    }
+}
+
+impl BeliefAgent for MctsAgent {
+    fn new_game(&mut self) {
+        self.mcts = Mcts::new(self.mcts.config);
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        self.search(state, belief, limits)
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        let report = self.search(state, belief, limits)?;
+        let ranked_moves = self.mcts.ranked_moves()?;
+        Some(Ranking { ranked_moves, report })
+    }
+
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        // Pondering would change what a fixed number of iterations finds
+        if self.mcts.config.iterations.is_some() {
+            return;
+        }
+        if let GameState::Playing { board } = state {
+            let limits = SearchLimits::ponder();
+            let mut rng = limits.rng();
+            let board = belief.determinize(board, &mut rng);
+            self.mcts.search_within(&board, limits, &mut rng, Some(stop));
+        }
+    }
+}
+
+/// Searches a fresh tree, with the opponent's hidden Ninjas placed from `belief` once for the whole search
+pub fn mcts_agent(
+    state: &GameState,
+    belief: &NinjaBelief,
+    config: MctsConfig,
+    budget: Budget,
+) -> Option<SearchReport> {
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Setup { .. } | GameState::Finished { .. } => return None,
+    };
+    let limits = SearchLimits::from(budget);
+    let mut rng = limits.rng();
+    let board = belief.determinize(board, &mut rng);
+    Mcts::new(config).search_within(&board, limits, &mut rng, None)
+}
@@ -1,39 +1,117 @@
-use instant::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 
-use crate::models::{GameState, Move, Player, Point};
-use crate::agents::ninja_logic;
+use crate::models::{GameState, Move, Player};
+use crate::agents::heuristics::{is_decisive, ply_earlier, win_value};
+use crate::agents::agent::BeliefAgent;
+use crate::agents::limits::{timed_out, SearchLimits};
+use crate::{BitBoard, EvalWeights, Evaluator, NinjaBelief, SearchReport};
+use instant::Instant;
+use crate::agents::transposition::{Bound, Table, TableEntry, TranspositionTable};
 
 const MAX_DEPTH: u16 = 50;
-pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct MinimaxConfig {
+    /// Deepest search started by iterative deepening
+    pub max_depth: u16,
+    pub weights: EvalWeights,
+}
+
+impl Default for MinimaxConfig {
+    fn default() -> Self {
+        MinimaxConfig {
+            max_depth: MAX_DEPTH,
+            weights: EvalWeights::default(),
+        }
+    }
+}
+
+/// Searches the position as if it was fully known, hidden Ninjas are only seen where they really are
+pub struct MinimaxAgent {
+    pub config: MinimaxConfig,
+    evaluator: Evaluator,
+}
+
+impl MinimaxAgent {
+    pub fn new(config: MinimaxConfig) -> MinimaxAgent {
+        MinimaxAgent {
+            config,
+            evaluator: Evaluator::new(config.weights),
+        }
+    }
+}
+
+impl Default for MinimaxAgent {
+    fn default() -> Self {
+        MinimaxAgent::new(MinimaxConfig::default())
+    }
+}
+
+impl BeliefAgent for MinimaxAgent {
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        _: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        iterative_deepening_to_depth(state, limits, self.config.max_depth, &self.evaluator)
+    }
+}
+
+pub fn iterative_deepening(state: &GameState, limits: SearchLimits, evaluator: &Evaluator) -> Option<SearchReport> {
+    iterative_deepening_to_depth(state, limits, MAX_DEPTH, evaluator)
+}
+
+/// Searches one ply deeper at a time until `max_depth` or the depth in `limits`, whichever is shallower,
+/// playing the best move of the deepest search that finished within the limits
+pub fn iterative_deepening_to_depth(
+    state: &GameState,
+    limits: SearchLimits,
+    max_depth: u16,
+    evaluator: &Evaluator,
+) -> Option<SearchReport> {
+    let board = match state {
+        GameState::Playing { board } => BitBoard::from(board),
+        GameState::Setup { .. } | GameState::Finished { .. } => {
+            return None;
+        }
+    };
     let start = Instant::now();
-    let deadline = start + duration;
-    let mut result: Option<(Move, i64)> = None;
-    for depth in 1..MAX_DEPTH {
-        match optimal_move_deadline(state, depth, deadline) {
+    let deadline = limits.deadline();
+    let mut table = TranspositionTable::default();
+    let mut search = Search::new(&mut table, evaluator, limits.nodes);
+    let mut result: Option<(Move, i64, u16)> = None;
+    for depth in 1..=max_depth.min(limits.depth.unwrap_or(max_depth)) {
+        // Deeper searches can't find a faster win or a slower loss than a proven one
+        if result.is_some_and(|(_, score, _)| is_decisive(score)) {
+            break;
+        }
+        match optimal_move_deadline(&board, depth, deadline, &mut search) {
             None => {
                 log::info!(
-                    "Timeout at depth {}, took {}ms",
+                    "Out of time or nodes at depth {}, took {}ms",
                     depth,
                     start.elapsed().as_millis()
                 );
                 break;
             }
-            Some(val) => {
-                result = Some(val);
+            Some((game_move, score)) => {
+                result = Some((game_move, score, depth));
             }
         };
     }
-    return result;
+    let (game_move, score, depth) = result?;
+    Some(search.report(&board, game_move, score, depth))
 }
 
-fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Option<(Move, i64)> {
-    let timedout = || Instant::now() > deadline;
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
-            return None;
-        }
-    };
+fn optimal_move_deadline(
+    board: &BitBoard,
+    depth: u16,
+    deadline: Option<Instant>,
+    search: &mut Search,
+) -> Option<(Move, i64)> {
+    let timedout = || timed_out(deadline);
+    let mut board = *board;
     if depth == 0 {
         return None;
     }
@@ -42,14 +120,14 @@ fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Op
     }
     let mut game_moves = board.legal_moves().into_iter();
     let mut best_move = game_moves.next().expect("No legal moves");
-    let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1);
+    let mut best_score = search.score_move(&mut board, best_move, depth - 1);
+    // The first ply only evaluates each move, so it always finishes and there is a move to play
+    let limited = depth > 1;
     for game_move in game_moves {
-        if timedout() {
+        if timedout() || (limited && search.out_of_nodes()) {
             return None;
         }
-        let state = board.try_move(game_move).expect("generated illegal move");
-        let expected_score = minimax(&state, depth - 1);
+        let expected_score = search.score_move(&mut board, game_move, depth - 1);
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -62,13 +140,17 @@ fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Op
             _ => {}
         };
     }
-    return Some((best_move, best_score));
+    // Positions cut short by the node budget don't have their real scores
+    if limited && search.out_of_nodes() {
+        return None;
+    }
+    Some((best_move, best_score))
 }
 
-pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
+pub fn optimal_move(state: &GameState, depth: u16, evaluator: &Evaluator) -> Option<(Move, i64)> {
+    let mut board = match state {
+        GameState::Playing { board } => BitBoard::from(board),
+        GameState::Setup { .. } | GameState::Finished { .. } => {
             return None;
         }
     };
@@ -76,12 +158,12 @@ pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
         return None;
     }
     let mut game_moves = board.legal_moves().into_iter();
+    let mut table = TranspositionTable::default();
+    let mut search = Search::new(&mut table, evaluator, None);
     let mut best_move = game_moves.next()?;
-    let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1);
+    let mut best_score = search.score_move(&mut board, best_move, depth - 1);
     for game_move in game_moves {
-        let state = board.try_move(game_move).expect("generated illegal move");
-        let expected_score = minimax(&state, depth - 1);
+        let expected_score = search.score_move(&mut board, game_move, depth - 1);
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -94,42 +176,100 @@ pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
             _ => {}
         };
     }
-    return Some((best_move, best_score));
+    Some((best_move, best_score))
 }
 
-pub fn minimax(state: &GameState, depth: u16) -> i64 {
-    if depth == 0 {
-        return state.basic_value();
-    }
-    let board = match state {
-        GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
-            return state.basic_value();
-        }
-    };
+/// Plays `game_move` and scores the resulting position, then takes the move back
+pub fn score_move(
+    board: &mut BitBoard,
+    game_move: Move,
+    depth: u16,
+    table: &mut TranspositionTable,
+    evaluator: &Evaluator,
+) -> i64 {
+    Search::new(table, evaluator, None).score_move(board, game_move, depth)
+}
 
-    // Randomize hidden Ninja positions before evaluating moves
-    let mut randomized_state = ninja_logic::randomize_hidden_ninjas(state.clone());
+pub fn minimax(board: &mut BitBoard, depth: u16, table: &mut TranspositionTable, evaluator: &Evaluator) -> i64 {
+    Search::new(table, evaluator, None).minimax(board, depth)
+}
 
-    // Generate possible hidden Ninja positions for the opponent
-    let opponent_hidden_ninjas: Vec<Point> = board
-        .opponent_ninjas()
-        .iter()
-        .filter_map(|ninja| ninja.as_ref().filter(|(_, revealed)| !*revealed).map(|(pos, _)| *pos))
-        .collect();
+/// Searches sharing one transposition table, counting the positions they visit
+pub(crate) struct Search<'a> {
+    table: &'a mut TranspositionTable,
+    evaluator: &'a Evaluator,
+    pub nodes: u64,
+    max_nodes: Option<u64>,
+}
 
-    let potential_positions = ninja_logic::possible_ninja_positions(&board, opponent_hidden_ninjas);
+impl<'a> Search<'a> {
+    pub fn new(table: &'a mut TranspositionTable, evaluator: &'a Evaluator, max_nodes: Option<u64>) -> Search<'a> {
+        Search {
+            table,
+            evaluator,
+            nodes: 0,
+            max_nodes,
+        }
+    }
 
-    // Replace Ninja positions in the randomized state
-    ninja_logic::randomize_ninjas_with_positions(&mut randomized_state, &potential_positions);
+    /// Once out of nodes, scores are meaningless until the search is started again
+    pub fn out_of_nodes(&self) -> bool {
+        self.max_nodes.is_some_and(|max_nodes| self.nodes >= max_nodes)
+    }
 
+    pub fn score_move(&mut self, board: &mut BitBoard, game_move: Move, depth: u16) -> i64 {
+        self.nodes += 1;
+        let undo = board.apply_move(game_move);
+        let score = match undo.winner() {
+            Some(winner) => win_value(winner),
+            None => self.minimax(board, depth),
+        };
+        board.undo(undo);
+        ply_earlier(score)
+    }
 
-    let expected_scores = board.legal_moves().into_iter().map(|game_move| {
-        let state = board.try_move(game_move).expect("illegal move generated");
-        minimax(&state, depth - 1)
-    });
-    match board.turn {
-        Player::Red => expected_scores.max().expect("no expected_scores"),
-        Player::Blue => expected_scores.min().expect("no expected_scores"),
+    fn minimax(&mut self, board: &mut BitBoard, depth: u16) -> i64 {
+        if depth == 0 {
+            return self.evaluator.evaluate(board);
+        }
+        if self.out_of_nodes() {
+            return 0;
+        }
+        let hash = board.hash;
+        if let Some(value) = self.table.probe(hash).and_then(|entry| entry.cutoff(depth, i64::MIN, i64::MAX)) {
+            return value;
+        }
+
+        let turn = board.turn;
+        let legal_moves = board.legal_moves();
+        let expected_scores = legal_moves
+            .into_iter()
+            .map(|game_move| (self.score_move(board, game_move, depth - 1), game_move));
+        let (value, best_move) = match turn {
+            Player::Red => expected_scores.max_by_key(|(score, _)| *score),
+            Player::Blue => expected_scores.min_by_key(|(score, _)| *score),
+        }
+        .expect("no expected_scores");
+        if self.out_of_nodes() {
+            return value;
+        }
+        self.table.store(TableEntry {
+            hash,
+            depth,
+            value,
+            bound: Bound::Exact,
+            best_move: Some(best_move),
+        });
+        value
+    }
+
+    /// Report for playing `game_move` from `board` after searching `depth` plies
+    pub fn report(&self, board: &BitBoard, game_move: Move, score: i64, depth: u16) -> SearchReport {
+        SearchReport {
+            depth: Some(depth),
+            nodes: self.nodes,
+            principal_variation: self.table.principal_variation(board, game_move, depth as usize),
+            ..SearchReport::new(game_move, score)
+        }
     }
 }
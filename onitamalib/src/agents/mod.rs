@@ -1,10 +1,27 @@
+pub mod agent;
 pub mod agents;
 pub mod alphabeta;
+pub mod belief;
+pub mod evaluation;
 pub mod greedy;
 pub mod heuristics;
+pub mod limits;
+pub mod ismcts;
+pub mod mcts;
 pub mod minimax;
 pub mod montecarlo;
 pub mod move_gen;
-pub mod ninja_logic;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod report;
+pub mod skill;
+pub mod transposition;
+pub mod tuning;
 
+pub use agent::{Agent, BeliefAgent, Tracked};
 pub use agents::AiAgent;
+pub use belief::NinjaBelief;
+pub use evaluation::{EvalWeights, Evaluator};
+pub use limits::SearchLimits;
+pub use report::{ForcedWin, Ranking, SearchReport};
+pub use skill::{SkillLevel, SkillLevelError, Skilled};
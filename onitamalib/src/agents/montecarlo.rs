@@ -1,45 +1,134 @@
 use std::cell::Cell;
 
-use instant::{Duration, Instant};
 use rand::prelude::*;
 
-use crate::{alphabeta, Board, GameState, Move, Player};
-use crate::agents::ninja_logic;
+use serde::{Deserialize, Serialize};
 
-pub fn hybrid_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
-    log::debug!("Game State: {:?}", state);
-    let alphabeta_duration = duration / 2;
-    let moves_scored = match alphabeta::moves_scored_deepening(state, alphabeta_duration) {
-        None => {
-            return None;
+use crate::agents::agent::BeliefAgent;
+use crate::agents::heuristics::is_decisive;
+use crate::agents::limits::{timed_out, SearchLimits};
+use crate::agents::report::best_scored;
+use crate::{alphabeta, BitBoard, Board, EvalWeights, Evaluator, ForcedWin, GameState, Move, NinjaBelief, Player, Ranking, SearchReport};
+
+#[derive(Default)]
+pub struct PureMonteCarloAgent;
+
+impl BeliefAgent for PureMonteCarloAgent {
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        pure_montecarlo_agent(state, belief, limits)
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        pure_montecarlo_rank_moves(state, belief, limits)
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct HybridMonteCarloConfig {
+    /// Only play out the moves alphabeta scores best, rather than all but the ones it finds losing
+    pub only_best_moves: bool,
+    /// Evaluation weights of the alphabeta search
+    pub weights: EvalWeights,
+}
+
+impl Default for HybridMonteCarloConfig {
+    fn default() -> Self {
+        HybridMonteCarloConfig {
+            only_best_moves: true,
+            weights: EvalWeights::default(),
         }
-        Some(val) => val,
-    };
+    }
+}
+
+pub struct HybridMonteCarloAgent {
+    pub config: HybridMonteCarloConfig,
+    evaluator: Evaluator,
+}
+
+impl HybridMonteCarloAgent {
+    pub fn new(config: HybridMonteCarloConfig) -> HybridMonteCarloAgent {
+        HybridMonteCarloAgent {
+            config,
+            evaluator: Evaluator::new(config.weights),
+        }
+    }
+}
+
+impl Default for HybridMonteCarloAgent {
+    fn default() -> Self {
+        HybridMonteCarloAgent::new(HybridMonteCarloConfig::default())
+    }
+}
+
+impl BeliefAgent for HybridMonteCarloAgent {
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        match self.config.only_best_moves {
+            true => hybrid_hard_montecarlo_agent(state, belief, limits, &self.evaluator),
+            false => hybrid_montecarlo_agent(state, belief, limits, &self.evaluator),
+        }
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        hybrid_hard_montecarlo_rank_moves(state, belief, limits, &self.evaluator)
+    }
+}
+
+pub fn hybrid_montecarlo_agent(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    evaluator: &Evaluator,
+) -> Option<SearchReport> {
+    log::debug!("Game State: {:?}", state);
+    let limits = limits.split(2);
+    let ranking = alphabeta::moves_scored_deepening(state, belief, limits, evaluator)?;
+    hybrid_montecarlo_playouts(state, belief, limits, ranking)
+}
+
+/// Plays out the moves `ranking` doesn't find losing, the second half of `hybrid_montecarlo_agent`
+pub(crate) fn hybrid_montecarlo_playouts(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    ranking: Ranking,
+) -> Option<SearchReport> {
     let board = match state {
         GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
+        GameState::Setup { .. } | GameState::Finished { .. } => {
             return None;
         }
     };
-    let guaranteed_win_score = match board.turn {
-        Player::Red => i64::MAX,
-        Player::Blue => i64::MIN,
-    };
-    for (game_move, expected_score) in moves_scored.iter() {
-        if *expected_score == guaranteed_win_score {
-            log::info!("Guaranteed to win");
-            return Some((*game_move, *expected_score));
-        }
+    if ranking.report.forced_win.is_some_and(|win| win.winner == board.turn) {
+        log::info!("Guaranteed to win");
+        return Some(ranking.report);
     }
+    let moves_scored = ranking.ranked_moves;
     log::info!("Using monte-carlo");
-    let guaranteed_lose_score = match board.turn.invert() {
-        Player::Red => i64::MAX,
-        Player::Blue => i64::MIN,
-    };
+    let loses = |score: i64| ForcedWin::from_score(score).is_some_and(|win| win.winner != board.turn);
     let moves: Vec<Move> = moves_scored
         .iter()
         .filter_map(
-            |(game_move, expected_score)| match *expected_score == guaranteed_lose_score {
+            |(game_move, expected_score)| match loses(*expected_score) {
                 true => {
                     log::debug!("Ruling out move: {:?}", game_move);
                     None
@@ -51,61 +140,60 @@ pub fn hybrid_montecarlo_agent(state: &GameState, duration: Duration) -> Option<
             },
         )
         .collect();
-    // If all moves lead to loss, still choose a move
-    let moves = match moves.len() > 0 {
-        true => moves,
-        false => {
-            log::debug!("Opponent can force a win");
-            moves_scored
-                .iter()
-                .map(|(game_move, _)| *game_move)
-                .collect()
-        }
-    };
+    // If all moves lead to loss, hold out for as long as possible
+    if moves.is_empty() {
+        log::debug!("Opponent can force a win");
+        return Some(ranking.report);
+    }
     if moves.len() == 1 {
         log::debug!("One legal move");
-        return Some((moves[0], 0));
+        return Some(hybrid_report(&ranking.report, moves[0], 0, 0));
     }
-    let monte_carlo_duration = duration / 2;
-    let scored_moves = montecarlo(board, moves, monte_carlo_duration);
+    let (scored_moves, simulations) = montecarlo(board, moves, belief, limits);
     let compare = match board.turn {
         Player::Red => |a, b| a > b,
         Player::Blue => |a, b| a < b,
     };
-    scored_moves
+    let (game_move, score) = scored_moves
         .into_iter()
         .reduce(
             |(move_a, score_a), (move_b, score_b)| match compare(score_a, score_b) {
                 true => (move_a, score_a),
                 false => (move_b, score_b),
             },
-        )
+        )?;
+    Some(hybrid_report(&ranking.report, game_move, score, simulations))
 }
-pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+pub fn hybrid_hard_montecarlo_agent(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    evaluator: &Evaluator,
+) -> Option<SearchReport> {
     log::debug!("Game State: {:?}", state);
-    let alphabeta_duration = duration / 2;
-    let moves_scored = match alphabeta::moves_scored_deepening(state, alphabeta_duration) {
-        None => {
-            return None;
-        }
-        Some(val) => val,
-    };
+    let limits = limits.split(2);
+    let ranking = alphabeta::moves_scored_deepening(state, belief, limits, evaluator)?;
+    hybrid_hard_montecarlo_playouts(state, belief, limits, ranking)
+}
+
+/// Plays out the moves `ranking` scores best, the second half of `hybrid_hard_montecarlo_agent`
+pub(crate) fn hybrid_hard_montecarlo_playouts(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    ranking: Ranking,
+) -> Option<SearchReport> {
     let board = match state {
         GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
+        GameState::Setup { .. } | GameState::Finished { .. } => {
             return None;
         }
     };
-    let guaranteed_win_score = match board.turn {
-        Player::Red => i64::MAX,
-        Player::Blue => i64::MIN,
-    };
-    for (game_move, expected_score) in moves_scored.iter() {
-        if *expected_score == guaranteed_win_score {
-            log::info!("Guaranteed to win");
-            return Some((*game_move, *expected_score));
-        }
+    if ranking.report.forced_win.is_some_and(|win| win.winner == board.turn) {
+        log::info!("Guaranteed to win");
+        return Some(ranking.report);
     }
+    let moves_scored = ranking.ranked_moves;
     log::info!("Using monte-carlo");
     let compare = match board.turn {
         Player::Red => i64::max,
@@ -132,7 +220,7 @@ pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Op
         )
         .collect();
     // If all moves lead to loss, still choose a move
-    let moves = match moves.len() > 0 {
+    let moves = match !moves.is_empty() {
         true => moves,
         false => {
             log::debug!("Opponent can force a win");
@@ -144,10 +232,9 @@ pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Op
     };
     if moves.len() == 1 {
         log::debug!("One legal move");
-        return Some((moves[0], 0));
+        return Some(hybrid_report(&ranking.report, moves[0], 0, 0));
     }
-    let monte_carlo_duration = duration / 2;
-    let scored_moves = montecarlo(board, moves, monte_carlo_duration);
+    let (scored_moves, simulations) = montecarlo(board, moves, belief, limits);
     let compare = match board.turn {
         Player::Red => |a, b| a > b,
         Player::Blue => |a, b| a < b,
@@ -160,82 +247,97 @@ pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Op
                 false => (move_b, score_b),
             },
         );
-    if let Some((_, expected_score)) = &result {
-        log::debug!("Expected result: {}", expected_score);
-    }
-    result
+    let (game_move, score) = result?;
+    log::debug!("Expected result: {}", score);
+    Some(hybrid_report(&ranking.report, game_move, score, simulations))
 }
 
 pub fn hybrid_hard_montecarlo_rank_moves(
     state: &GameState,
-    duration: Duration,
-) -> Option<Vec<(Move, i64)>> {
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    evaluator: &Evaluator,
+) -> Option<Ranking> {
     log::debug!("Game State: {:?}", state);
-    let alphabeta_duration = duration / 2;
-    let alphabeta_scored_moves = match alphabeta::moves_scored_deepening(state, alphabeta_duration)
-    {
-        None => {
-            return None;
-        }
-        Some(val) => val,
-    };
+    let limits = limits.split(2);
+    let ranking = alphabeta::moves_scored_deepening(state, belief, limits, evaluator)?;
+    hybrid_rank_playouts(state, belief, limits, ranking)
+}
+
+/// Averages `ranking` with the playout score of every move, the second half of `hybrid_hard_montecarlo_rank_moves`
+pub(crate) fn hybrid_rank_playouts(
+    state: &GameState,
+    belief: &NinjaBelief,
+    limits: SearchLimits,
+    ranking: Ranking,
+) -> Option<Ranking> {
     let board = match state {
         GameState::Playing { board } => board,
-        GameState::Finished { .. } => {
+        GameState::Setup { .. } | GameState::Finished { .. } => {
             return None;
         }
     };
-    let moves: Vec<Move> = alphabeta_scored_moves
+    let moves: Vec<Move> = ranking
+        .ranked_moves
         .iter()
         .map(|(game_move, _expected_score)| *game_move)
         .collect();
     if moves.len() == 1 {
         log::debug!("One legal move");
-        return Some(alphabeta_scored_moves);
+        return Some(ranking);
     }
-    let monte_carlo_duration = duration / 2;
-    let monte_carlo_scored_moves = montecarlo(board, moves, monte_carlo_duration);
-    let result: Vec<(Move, i64)> = alphabeta_scored_moves
+    let (monte_carlo_scored_moves, simulations) = montecarlo(board, moves, belief, limits);
+    let ranked_moves: Vec<(Move, i64)> = ranking
+        .ranked_moves
         .into_iter()
-        .zip(monte_carlo_scored_moves.into_iter())
+        .zip(monte_carlo_scored_moves)
         .map(|((alpha_move, alpha_score), (monte_move, monte_score))| {
             if alpha_move != monte_move {
                 panic!("monte move does not match alpha move");
             }
-            if alpha_score == i64::MIN || alpha_score == i64::MAX {
+            if is_decisive(alpha_score) {
                 return (alpha_move, alpha_score);
             }
-            return (alpha_move, (alpha_score / 2) + (monte_score / 2));
+            (alpha_move, (alpha_score / 2) + (monte_score / 2))
         })
         .collect();
-    Some(result)
+    let (game_move, score) = best_scored(&ranked_moves, board.turn)?;
+    let report = hybrid_report(&ranking.report, game_move, score, simulations);
+    Some(Ranking { ranked_moves, report })
+}
+
+/// Report for a move chosen by playouts among the moves alphabeta left
+fn hybrid_report(alphabeta: &SearchReport, game_move: Move, score: i64, simulations: u64) -> SearchReport {
+    SearchReport {
+        depth: alphabeta.depth,
+        nodes: alphabeta.nodes,
+        simulations: Some(simulations),
+        ..SearchReport::new(game_move, score)
+    }
 }
 
 const ITERATIONS_PER_TIME_CHECK: u8 = 50;
 
-fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move, i64)> {
-    let start = Instant::now();
-    let deadline = start + duration;
-    let timedout = || Instant::now() > deadline;
+/// Scores each move by playouts until out of time or simulations, also returning how many games were played out
+fn montecarlo(board: &Board, moves: Vec<Move>, belief: &NinjaBelief, limits: SearchLimits) -> (Vec<(Move, i64)>, u64) {
+    let deadline = limits.deadline();
+    let max_simulations = limits.simulations.unwrap_or(u64::MAX);
     let results: Vec<(Move, Cell<i64>)> = moves
         .into_iter()
         .map(|game_move| (game_move, Cell::new(0i64)))
         .collect();
+    let root = BitBoard::from(board);
     let mut simulations = 0u64;
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "web")] {
-            let mut rng = rand_mt::Mt::default();
-        } else {
-            let mut rng = thread_rng();
-        }
-    }
-    while !timedout() {
+    let mut rng = limits.rng();
+    'search: while !timed_out(deadline) {
         for _ in 0..ITERATIONS_PER_TIME_CHECK {
             for (game_move, score) in results.iter() {
+                if simulations >= max_simulations {
+                    break 'search;
+                }
                 simulations += 1;
-                let state = board.try_move(*game_move).expect("illegal move");
                 let new_score = score.get()
-                    + match simulate(state, &mut rng) {
+                    + match playout(&root, belief, *game_move, &mut rng) {
                         Some(Player::Red) => 1,
                         Some(Player::Blue) => -1,
                         None => 0,
@@ -244,89 +346,66 @@ fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move,
             }
         }
     }
-    log::info!("Monte-carlo timed out after {} simulations", simulations);
-    return results
+    log::info!("Monte-carlo stopped after {} simulations", simulations);
+    let results = results
         .into_iter()
         .map(|(game_move, score)| (game_move, score.get()))
         .collect();
+    (results, simulations)
 }
 
 #[cfg(test)]
-pub fn montecarlo_count_simulations(board: &Board, moves: Vec<Move>, duration: Duration) -> u64 {
-    let start = Instant::now();
-    let deadline = start + duration;
-    let timedout = || Instant::now() > deadline;
-    let results: Vec<(Move, Cell<i64>)> = moves
-        .into_iter()
-        .map(|game_move| (game_move, Cell::new(0i64)))
-        .collect();
-    let mut simulations = 0u64;
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "web")] {
-            let mut rng = rand_mt::Mt::default();
-        } else {
-            let mut rng = thread_rng();
-        }
-    }
-    while !timedout() {
-        for _ in 0..ITERATIONS_PER_TIME_CHECK {
-            for (game_move, score) in results.iter() {
-                simulations += 1;
-                let state = board.try_move(*game_move).expect("illegal move");
-                let new_score = score.get()
-                    + match simulate(state, &mut rng) {
-                        Some(Player::Red) => 1,
-                        Some(Player::Blue) => -1,
-                        None => 0,
-                    };
-                score.set(new_score);
-            }
-        }
-    }
-    log::info!("Monte-carlo timed out after {} simulations", simulations);
-    return simulations;
+pub fn montecarlo_count_simulations(board: &Board, moves: Vec<Move>, duration: instant::Duration) -> u64 {
+    let belief = &NinjaBelief::unknown(board, board.turn);
+    montecarlo(board, moves, belief, SearchLimits::time(duration)).1
+}
+
+pub fn pure_montecarlo_agent(state: &GameState, belief: &NinjaBelief, limits: SearchLimits) -> Option<SearchReport> {
+    Some(pure_montecarlo_rank_moves(state, belief, limits)?.report)
 }
 
-pub fn pure_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+/// Scores every legal move by how many more random games Red won than Blue after it
+pub fn pure_montecarlo_rank_moves(state: &GameState, belief: &NinjaBelief, limits: SearchLimits) -> Option<Ranking> {
     let board = match state {
         GameState::Playing { board, .. } => Some(*board),
-        GameState::Finished { .. } => None,
+        GameState::Setup { .. } | GameState::Finished { .. } => None,
     }?;
     let moves = board.legal_moves();
-    let scored_moves = montecarlo(&board, moves, duration);
-    let compare = match board.turn {
-        Player::Red => |a, b| a > b,
-        Player::Blue => |a, b| a < b,
+    let (ranked_moves, simulations) = montecarlo(&board, moves, belief, limits);
+    let (game_move, score) = best_scored(&ranked_moves, board.turn)?;
+    let report = SearchReport {
+        simulations: Some(simulations),
+        ..SearchReport::new(game_move, score)
     };
-    scored_moves
-        .into_iter()
-        .reduce(
-            |(move_a, score_a), (move_b, score_b)| match compare(score_a, score_b) {
-                true => (move_a, score_a),
-                false => (move_b, score_b),
-            },
-        )
+    Some(Ranking { ranked_moves, report })
 }
 
-fn simulate<R: Rng>(state: GameState, rng: &mut R) -> Option<Player> {
-    let mut state = ninja_logic::randomize_hidden_ninjas(state);
+/// Plays `game_move` from `root` followed by a random game, returning the winner if there is one.
+///
+/// Each playout draws its own placement of the opponent's hidden Ninjas from `belief`.
+fn playout<R: Rng>(root: &BitBoard, belief: &NinjaBelief, game_move: Move, rng: &mut R) -> Option<Player> {
+    let mut board = *root;
+    belief.sample(&mut board, rng);
+    match board.apply_move(game_move).winner() {
+        Some(winner) => Some(winner),
+        None => simulate(board, rng),
+    }
+}
+
+/// Plays random moves from `board` until the game ends, giving up after a while
+pub(crate) fn simulate<R: Rng>(mut board: BitBoard, rng: &mut R) -> Option<Player> {
+    let mut moves = Vec::with_capacity(32);
     for _ in 0..1000 {
-        let board = match state {
-            GameState::Playing { board } => board,
-            GameState::Finished { winner, .. } => {
-                return Some(winner);
-            }
-        };
-        let game_move = match board.random_legal_move(rng) {
-            Some(mv) => mv,
+        board.legal_moves_into(&mut moves);
+        let game_move = match moves.choose(rng) {
+            Some(mv) => *mv,
             None => {
                 return None;
             }
         };
-        state = state
-            .try_move(game_move)
-            .expect("montecarlo played illegal move");
+        if let Some(winner) = board.apply_move(game_move).winner() {
+            return Some(winner);
+        }
     }
     None
 }
-
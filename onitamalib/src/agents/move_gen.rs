@@ -1,159 +1,168 @@
-use crate::models::{Board, Move, Player, Point, CardSet};
+use crate::bitboard::{bit, is_wind_card, move_targets, points, side, BitBoard};
+use crate::models::{Board, Card, Move, Point};
 use rand::prelude::*;
 
-impl Board {
-pub fn legal_moves(&self) -> Vec<Move> {
-        let mut moves = vec![];
-        let pieces = self.player_pieces();
-        let wind_spirit_pos = self.wind_spirit();
-        let kings: Vec<Point> = [self.red_king, self.blue_king]
-            .iter()
-            .filter_map(|&king| king)
-            .collect();
-
-        // Handle Ninja Move Pending
-        if self.ninja_move_pending {
-            if let Some(ninja_card) = self.ninja_move_card {
-                for src in pieces.iter().filter_map(|p| *p) {
-                    if !self.is_ninja(src) {
-                        continue; // Only Ninja pieces can move
-                    }
-                    for offset in ninja_card.moves(false, false) {
-                        let offset = match self.turn {
-                            Player::Red => offset,
-                            Player::Blue => -offset,
-                        };
-                        let dst = src + offset;
-
-                        if dst.in_bounds() && !self.player_pieces().contains(&Some(dst)) {
-                            moves.push(Move::Move {
-                                card: ninja_card,
-                                src,
-                                dst,
-                                reveal_ninja: false,
-                            });
-                        }
-                    }
-                }
+impl BitBoard {
+    /// Squares the piece on `src` can reach with `card`, outside of any pending follow up move
+    fn destinations(&self, card: Card, src: Point) -> u32 {
+        let player = side(self.turn);
+        let src_bit = bit(src);
+        let is_wind_spirit = self.wind_spirit & src_bit != 0;
+        let is_king = self.kings[player] & src_bit != 0;
+
+        if is_wind_spirit {
+            // Wind Spirit cannot use WayOfTheWind card for normal moves, nor move onto a Master
+            if is_wind_card(card) {
+                return 0;
             }
-            return moves; // Only Ninja moves are valid during `ninja_move_pending`
+            return move_targets(card, false, false, self.turn, src) & !(self.kings[0] | self.kings[1]);
+        }
+
+        // Can't move onto your own pieces or the Wind Spirit
+        move_targets(card, is_king, false, self.turn, src) & !self.player_pieces()
+    }
+
+    /// Pieces that may move in the normal phase of the turn
+    fn movable_pieces(&self) -> u32 {
+        let player = side(self.turn);
+        let ninjas = match self.shadow_mode {
+            // Shadow mode: Ninjas only move as a follow up
+            true => 0,
+            false => self.ninjas[player],
+        };
+        self.kings[player] | self.pawns[player] | ninjas | self.wind_spirit
+    }
+
+    /// Destinations from `src` where moving with `reveal_ninja` set plays out differently than without it
+    fn reveal_targets(&self, src: Point) -> u32 {
+        let player = side(self.turn);
+        let opponent = 1 - player;
+        let src_bit = bit(src);
+        // In Way of the Light the flag is ignored
+        if self.light_mode || self.ninjas[player] & src_bit == 0 {
+            return 0;
+        }
+        // These captures reveal the Ninja anyway, a revealed Ninja also captures hidden ones
+        let mut captures = self.pawns[opponent] | self.revealed_ninjas[opponent];
+        if self.revealed_ninjas[player] & src_bit != 0 {
+            captures |= self.ninjas[opponent];
         }
+        !captures
+    }
+
+    pub(crate) fn can_ninja_move(&self, card: Card) -> bool {
+        let player = side(self.turn);
+        points(self.ninjas[player])
+            .any(|src| move_targets(card, false, false, self.turn, src) & !self.player_pieces() != 0)
+    }
+
+    pub(crate) fn can_wind_move(&self, card: Card) -> bool {
+        points(self.wind_spirit)
+            .any(|src| move_targets(card, false, true, self.turn, src) & !(self.kings[0] | self.kings[1]) != 0)
+    }
 
-        // Handle Wind Spirit Moves
+    pub fn can_move(&self) -> bool {
+        // If a Ninja move is pending, only the Ninja can move
+        if self.ninja_move_pending {
+            return self.ninja_move_card.is_some_and(|card| self.can_ninja_move(card));
+        }
+
+        // If a wind move is pending, only the Wind Spirit can move using the wind_move_card
         if self.wind_move_pending {
-            if let Some(wind_spirit_pos) = wind_spirit_pos {
-                if let Some(wind_card) = self.wind_move_card {
-                    for offset in wind_card.moves(false, true) {
-                        let offset = match self.turn {
-                            Player::Red => offset,
-                            Player::Blue => -offset,
-                        };
-                        let dst = wind_spirit_pos + offset;
-
-                        if dst.in_bounds()
-                            && (!self.player_pieces().contains(&Some(dst)) || kings.contains(&dst))
-                        {
-                            // Prevent Wind Spirit from moving onto a King
-                            if kings.contains(&dst) {
-                                continue;
-                            }
-
-                            moves.push(Move::Move {
-                                card: wind_card,
-                                src: wind_spirit_pos,
-                                dst,
-                                reveal_ninja: false,
-                            });
-                        }
-                    }
-                }
-            }
-            return moves; // Only Wind Spirit moves are valid during `wind_move_pending`
+            return self.wind_move_card.is_some_and(|card| self.can_wind_move(card));
         }
 
-        // Generate Normal Moves
-        for card in self.player_hand() {
-            for src in pieces.iter().filter_map(|p| *p) {
-                let is_wind_spirit = Some(src) == wind_spirit_pos;
-                let is_king = self.player_king() == Some(src);
-                let is_ninja = self.is_ninja(src);
+        let hand = self.hands[side(self.turn)];
+        points(self.movable_pieces())
+            .any(|src| hand.iter().any(|&card| self.destinations(card, src) != 0))
+    }
 
-                // Skip Ninja moves in shadow mode
-                if self.shadow_mode && is_ninja {
-                    continue; // Shadow mode: disallow Ninja moves
+    /// Writes every legal move into `moves`, in no particular order
+    pub fn legal_moves_into(&self, moves: &mut Vec<Move>) {
+        moves.clear();
+        let player = side(self.turn);
+        let push_moves = |moves: &mut Vec<Move>, card: Card, src: Point, dsts: u32| {
+            moves.extend(points(dsts).map(|dst| Move::Move {
+                card,
+                src,
+                dst,
+                reveal_ninja: false,
+            }));
+            moves.extend(points(dsts & self.reveal_targets(src)).map(|dst| Move::Move {
+                card,
+                src,
+                dst,
+                reveal_ninja: true,
+            }));
+        };
+
+        // Only Ninja moves are valid during `ninja_move_pending`, the follow up is optional so it can be skipped
+        if self.ninja_move_pending {
+            if let Some(card) = self.ninja_move_card {
+                for src in points(self.ninjas[player]) {
+                    let dsts = move_targets(card, false, false, self.turn, src) & !self.player_pieces();
+                    push_moves(moves, card, src, dsts);
                 }
+                moves.push(Move::Discard { card });
+            }
+            return;
+        }
 
-                let cached_moves: Vec<_> = card.moves(is_king, false);
-                for offset in cached_moves {
-                    if is_wind_spirit && CardSet::WayOfTheWind.cards().contains(&card) {
-                        continue; // Skip illegal moves for the Wind Spirit
-                    }
-
-                    let offset = match self.turn {
-                        Player::Red => offset,
-                        Player::Blue => -offset,
-                    };
-                    let dst = src + offset;
-
-                    if dst.in_bounds() && (!pieces.contains(&Some(dst)) || is_wind_spirit) {
-                        
-                        if is_wind_spirit && kings.contains(&dst) {
-                            continue;
-                        }
-
-                        // Prevent pieces from moving onto Wind Spirit
-                        if let Some(wind_spirit_pos) = wind_spirit_pos {
-                            if dst == wind_spirit_pos {
-                                continue;
-                            }
-                        }
-
-                        moves.push(Move::Move {
-                            card: *card,
-                            src,
-                            dst,
-                            reveal_ninja: false,
-                        });
-                    }
+        // Only Wind Spirit moves are valid during `wind_move_pending`, it swaps with anything but a Master
+        if self.wind_move_pending {
+            if let Some(card) = self.wind_move_card {
+                for src in points(self.wind_spirit) {
+                    let dsts = move_targets(card, false, true, self.turn, src) & !(self.kings[0] | self.kings[1]);
+                    push_moves(moves, card, src, dsts);
                 }
             }
+            // The player may discard instead, as long as they couldn't have made a normal move
+            let mut normal = *self;
+            normal.wind_move_pending = false;
+            if !moves.is_empty() && !normal.can_move() {
+                moves.extend(self.hands[player].iter().map(|&card| Move::Discard { card }));
+            }
+            return;
         }
 
-        if !moves.is_empty() {
-            let opponent_pieces = self.opponent_pieces();
-            let key = |game_move: &Move| match game_move {
-                Move::Move { dst, .. } => match opponent_pieces.contains(&Some(*dst)) {
-                    true => 0, // Prioritize capturing moves
-                    false => 1,
-                },
-                Move::Discard { .. } => 0,
-            };
-            moves.sort_by_cached_key(key);
-            return moves;
+        let hand = self.hands[player];
+        for src in points(self.movable_pieces()) {
+            for &card in hand.iter() {
+                push_moves(moves, card, src, self.destinations(card, src));
+            }
         }
 
         // If no moves are possible, force a discard
-        self.player_hand()
-            .iter()
-            .map(|&card| Move::Discard { card })
-            .collect()
+        if moves.is_empty() {
+            moves.extend(hand.iter().map(|&card| Move::Discard { card }));
+        }
+    }
+
+    /// Every legal move, with captures first
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(32);
+        self.legal_moves_into(&mut moves);
+        let opponent_pieces = self.opponent_pieces();
+        moves.sort_by_key(|game_move| match game_move {
+            Move::Move { dst, .. } => opponent_pieces & bit(*dst) == 0,
+            Move::Discard { .. } | Move::PlaceNinja { .. } => false,
+        });
+        moves
     }
 
     pub fn random_legal_move<R: Rng>(&self, rng: &mut R) -> Option<Move> {
-        let mut moves = self.legal_moves();
-    
-        // Shuffle moves to randomize selection
-        moves.shuffle(rng);
-    
-        // Validate each move using `try_move` before selecting
-        for game_move in moves {
-            if self.try_move(game_move).is_ok() {
-                return Some(game_move); // Return the first valid move
-            }
-        }
-    
-        // Return None if no valid moves remain
-        None
+        let mut moves = Vec::with_capacity(32);
+        self.legal_moves_into(&mut moves);
+        moves.choose(rng).copied()
+    }
+}
+
+impl Board {
+    pub fn legal_moves(&self) -> Vec<Move> {
+        BitBoard::from(self).legal_moves()
+    }
+
+    pub fn random_legal_move<R: Rng>(&self, rng: &mut R) -> Option<Move> {
+        BitBoard::from(self).random_legal_move(rng)
     }
-    
 }
@@ -0,0 +1,262 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::agents::agent::{Agent, BeliefAgent};
+use crate::agents::alphabeta::{self, AlphabetaConfig};
+use crate::agents::mcts::{self, Budget, Mcts, MctsConfig};
+use crate::agents::montecarlo::{self, HybridMonteCarloConfig};
+use crate::agents::transposition::SharedTable;
+use crate::{Evaluator, GameState, NinjaBelief, Ranking, SearchLimits, SearchReport};
+
+/// One search thread for each core, or a single thread if the number of cores isn't known
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Alphabeta searching on `threads` threads at once with Lazy SMP, see `alphabeta::lazy_smp`
+pub struct LazySmpAgent {
+    pub config: AlphabetaConfig,
+    pub threads: usize,
+    evaluator: Evaluator,
+    table: SharedTable,
+}
+
+impl LazySmpAgent {
+    pub fn new(config: AlphabetaConfig, threads: usize) -> LazySmpAgent {
+        LazySmpAgent {
+            config,
+            threads: threads.max(1),
+            evaluator: Evaluator::new(config.weights),
+            table: SharedTable::default(),
+        }
+    }
+
+    /// `limits` searching no deeper than the configured depth
+    fn limits(&self, limits: SearchLimits) -> SearchLimits {
+        let max_depth = self.config.max_depth;
+        SearchLimits {
+            depth: Some(limits.depth.map_or(max_depth, |depth| depth.min(max_depth))),
+            ..limits
+        }
+    }
+}
+
+impl Default for LazySmpAgent {
+    fn default() -> Self {
+        LazySmpAgent::new(AlphabetaConfig::default(), default_threads())
+    }
+}
+
+impl BeliefAgent for LazySmpAgent {
+    fn new_game(&mut self) {
+        self.table.clear();
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        if !self.config.keep_table {
+            self.table.clear();
+        }
+        let limits = self.limits(limits);
+        alphabeta::lazy_smp(state, belief, limits, &self.table, &self.evaluator, self.threads, None)
+    }
+
+    /// Scores every move on a single thread, like `AlphabetaAgent`
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        alphabeta::moves_scored_deepening(state, belief, limits, &self.evaluator)
+    }
+
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        if self.config.keep_table {
+            let limits = self.limits(SearchLimits::ponder());
+            alphabeta::lazy_smp(state, belief, limits, &self.table, &self.evaluator, self.threads, Some(stop));
+        }
+    }
+}
+
+/// `HybridMonteCarloAgent` with its alphabeta search run by Lazy SMP on `threads` threads, the playouts that
+/// follow still run on one
+pub struct LazySmpHybridAgent {
+    pub config: HybridMonteCarloConfig,
+    pub threads: usize,
+    evaluator: Evaluator,
+    table: SharedTable,
+}
+
+impl LazySmpHybridAgent {
+    pub fn new(config: HybridMonteCarloConfig, threads: usize) -> LazySmpHybridAgent {
+        LazySmpHybridAgent {
+            config,
+            threads: threads.max(1),
+            evaluator: Evaluator::new(config.weights),
+            table: SharedTable::default(),
+        }
+    }
+
+    fn ranking(&self, state: &GameState, belief: &NinjaBelief, limits: SearchLimits) -> Option<Ranking> {
+        alphabeta::lazy_smp_moves_scored(state, belief, limits, &self.table, &self.evaluator, self.threads)
+    }
+}
+
+impl Default for LazySmpHybridAgent {
+    fn default() -> Self {
+        LazySmpHybridAgent::new(HybridMonteCarloConfig::default(), default_threads())
+    }
+}
+
+impl BeliefAgent for LazySmpHybridAgent {
+    fn new_game(&mut self) {
+        self.table.clear();
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        let limits = limits.split(2);
+        let ranking = self.ranking(state, belief, limits)?;
+        match self.config.only_best_moves {
+            true => montecarlo::hybrid_hard_montecarlo_playouts(state, belief, limits, ranking),
+            false => montecarlo::hybrid_montecarlo_playouts(state, belief, limits, ranking),
+        }
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        let limits = limits.split(2);
+        let ranking = self.ranking(state, belief, limits)?;
+        montecarlo::hybrid_rank_playouts(state, belief, limits, ranking)
+    }
+
+    /// Fills the table for the alphabeta half of the next move, the playouts start over every move anyway
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        let limits = SearchLimits::ponder();
+        alphabeta::lazy_smp(state, belief, limits, &self.table, &self.evaluator, self.threads, Some(stop));
+    }
+}
+
+/// MCTS growing a tree on each of `threads` threads, see `mcts::root_parallel`
+pub struct RootParallelMctsAgent {
+    config: MctsConfig,
+    trees: Vec<Mcts>,
+}
+
+impl RootParallelMctsAgent {
+    pub fn new(config: MctsConfig, threads: usize) -> RootParallelMctsAgent {
+        RootParallelMctsAgent {
+            config,
+            trees: (0..threads.max(1)).map(|_| Mcts::new(config)).collect(),
+        }
+    }
+
+    fn search(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+        stop: Option<&AtomicBool>,
+    ) -> Option<Ranking> {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Setup { .. } | GameState::Finished { .. } => return None,
+        };
+        // Every thread runs the configured iterations
+        let limits = match self.config.iterations {
+            Some(iterations) => SearchLimits::from(Budget::Iterations(iterations)),
+            None => limits,
+        };
+        let board = belief.determinize(board, &mut limits.rng());
+        mcts::root_parallel(&mut self.trees, &board, limits, stop)
+    }
+}
+
+impl Default for RootParallelMctsAgent {
+    fn default() -> Self {
+        RootParallelMctsAgent::new(MctsConfig::default(), default_threads())
+    }
+}
+
+impl BeliefAgent for RootParallelMctsAgent {
+    fn new_game(&mut self) {
+        let threads = self.trees.len();
+        *self = RootParallelMctsAgent::new(self.config, threads);
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        Some(self.search(state, belief, limits, None)?.report)
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        self.search(state, belief, limits, None)
+    }
+
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        if self.config.iterations.is_none() {
+            self.search(state, belief, SearchLimits::ponder(), Some(stop));
+        }
+    }
+}
+
+/// Runs `Agent::ponder` on its own thread, handing the agent back once stopped
+pub struct Ponderer {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Box<dyn Agent>>>,
+}
+
+impl Ponderer {
+    /// Starts pondering `state`, typically right after the agent has moved
+    pub fn start(mut agent: Box<dyn Agent>, state: GameState) -> Ponderer {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                agent.ponder(&state, &stop);
+                agent
+            })
+        };
+        Ponderer {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stops pondering, returning the agent with whatever it found so far
+    pub fn stop(mut self) -> Box<dyn Agent> {
+        self.stop.store(true, Ordering::Relaxed);
+        let handle = self.handle.take().expect("Ponderer already stopped");
+        handle.join().expect("Pondering panicked")
+    }
+}
+
+impl Drop for Ponderer {
+    // The thread gives up on its own, taking the agent with it
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::agents::heuristics::{is_decisive, WIN_SCORE};
+use crate::{GameState, Move, Player};
+
+/// What an agent found while choosing a move, for tuning and debugging agents
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReport {
+    pub game_move: Move,
+    /// Positive when it favours Red, like `GameState::basic_value`
+    pub score: i64,
+    /// Deepest search completed, for agents that search to a depth
+    pub depth: Option<u16>,
+    /// Positions searched, or the size of the tree for tree searches
+    pub nodes: u64,
+    /// Moves expected from both players, starting with `game_move`
+    pub principal_variation: Vec<Move>,
+    /// Games played out, for Monte Carlo agents
+    pub simulations: Option<u64>,
+    /// How the game ends when the search found a forced win for either player
+    pub forced_win: Option<ForcedWin>,
+}
+
+/// A win `plies` moves away however the loser plays
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ForcedWin {
+    pub winner: Player,
+    pub plies: u16,
+}
+
+impl ForcedWin {
+    /// The forced win described by a search score, see `heuristics::win_value`
+    pub fn from_score(score: i64) -> Option<ForcedWin> {
+        if !is_decisive(score) {
+            return None;
+        }
+        let winner = match score > 0 {
+            true => Player::Red,
+            false => Player::Blue,
+        };
+        Some(ForcedWin {
+            winner,
+            plies: (WIN_SCORE - score.abs()) as u16,
+        })
+    }
+}
+
+impl SearchReport {
+    /// Reports only the move and any forced win its score shows, fill in whatever else the agent knows
+    pub fn new(game_move: Move, score: i64) -> SearchReport {
+        SearchReport {
+            game_move,
+            score,
+            depth: None,
+            nodes: 0,
+            principal_variation: vec![game_move],
+            simulations: None,
+            forced_win: ForcedWin::from_score(score),
+        }
+    }
+
+    /// The report as `player` sees it from `state`, the state the move was chosen in.
+    ///
+    /// `None` if they can't see the move itself, otherwise the line stops before the first move they can't see.
+    pub fn view_for(&self, state: &GameState, player: Player) -> Option<SearchReport> {
+        let mut state = *state;
+        let mut line = vec![];
+        for &game_move in self.principal_variation.iter() {
+            let next = match state {
+                GameState::Playing { board } if board.move_view_for(game_move, player) == Some(game_move) => {
+                    state.try_move(game_move)
+                }
+                _ => break,
+            };
+            // Moves for the opponent's hidden Ninjas were guessed and may not be legal in the real game
+            state = match next {
+                Ok(next) => next,
+                Err(_) => break,
+            };
+            line.push(game_move);
+        }
+        if line.first() != Some(&self.game_move) {
+            return None;
+        }
+        Some(SearchReport {
+            principal_variation: line,
+            ..self.clone()
+        })
+    }
+}
+
+/// Every move an agent scored, with the report of the search that scored them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Ranking {
+    pub ranked_moves: Vec<(Move, i64)>,
+    /// Report for the move with the best score
+    pub report: SearchReport,
+}
+
+/// The move with the best score for `player`
+pub fn best_scored(scored_moves: &[(Move, i64)], player: Player) -> Option<(Move, i64)> {
+    let scored_moves = scored_moves.iter().copied();
+    match player {
+        Player::Red => scored_moves.max_by_key(|(_, score)| *score),
+        Player::Blue => scored_moves.min_by_key(|(_, score)| *score),
+    }
+}
@@ -0,0 +1,231 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::agent::BeliefAgent;
+use crate::agents::heuristics::is_decisive;
+use crate::agents::limits::SearchLimits;
+use crate::{GameState, Move, NinjaBelief, Player, Ranking, SearchReport};
+
+/// Search budgets at skill level 1, doubled at every level above it
+const NODES_AT_LOWEST_SKILL: u64 = 250;
+const SIMULATIONS_AT_LOWEST_SKILL: u64 = 25;
+/// Noise added to scores at skill level 1, as a fraction of the spread of scores
+const NOISE_AT_LOWEST_SKILL: f64 = 0.5;
+const BLUNDER_PROBABILITY_AT_LOWEST_SKILL: f64 = 0.5;
+
+/// How well an agent plays, from 1 for beginners up to 20 for the agent at full strength
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct SkillLevel(u8);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SkillLevelError {
+    OutOfRange(u64),
+    NotANumber(String),
+}
+
+impl fmt::Display for SkillLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkillLevelError::OutOfRange(level) => write!(
+                f,
+                "Skill level {} is outside {} to {}",
+                level,
+                SkillLevel::MIN.0,
+                SkillLevel::MAX.0
+            ),
+            SkillLevelError::NotANumber(level) => write!(f, "Skill level '{}' is not a number", level),
+        }
+    }
+}
+
+impl std::error::Error for SkillLevelError {}
+
+impl SkillLevel {
+    pub const MIN: SkillLevel = SkillLevel(1);
+    pub const MAX: SkillLevel = SkillLevel(20);
+
+    pub fn new(level: u8) -> Result<SkillLevel, SkillLevelError> {
+        match (SkillLevel::MIN.0..=SkillLevel::MAX.0).contains(&level) {
+            true => Ok(SkillLevel(level)),
+            false => Err(SkillLevelError::OutOfRange(level as u64)),
+        }
+    }
+
+    /// Splits a difficulty picked by the player into the name of an agent and a skill level.
+    ///
+    /// "hard" is that agent at full strength, "hard:5" the same agent at skill level 5, and "5" leaves the name
+    /// empty for the game mode's default agent. Each game mode picks its own agent for each name.
+    pub fn from_difficulty(difficulty: &str) -> Result<(&str, SkillLevel), SkillLevelError> {
+        match difficulty.rsplit_once(':') {
+            Some((name, level)) => Ok((name, level.parse()?)),
+            None if difficulty.starts_with(|c: char| c.is_ascii_digit()) => Ok(("", difficulty.parse()?)),
+            None => Ok((difficulty, SkillLevel::MAX)),
+        }
+    }
+
+    pub fn level(self) -> u8 {
+        self.0
+    }
+
+    /// 0 at full strength up to 1 at the lowest level
+    fn weakness(self) -> f64 {
+        (SkillLevel::MAX.0 - self.0) as f64 / (SkillLevel::MAX.0 - SkillLevel::MIN.0) as f64
+    }
+
+    /// `limits` with the search cut short for this level, full strength searches aren't limited any further
+    pub fn limits(self, limits: SearchLimits) -> SearchLimits {
+        if self == SkillLevel::MAX {
+            return limits;
+        }
+        let doublings = (self.0 - SkillLevel::MIN.0) as u32;
+        let cap = |limit: Option<u64>, lowest: u64| {
+            let budget = lowest << doublings;
+            Some(limit.map_or(budget, |limit| limit.min(budget)))
+        };
+        let depth = (self.0 / 2 + 1) as u16;
+        SearchLimits {
+            nodes: cap(limits.nodes, NODES_AT_LOWEST_SKILL),
+            simulations: cap(limits.simulations, SIMULATIONS_AT_LOWEST_SKILL),
+            depth: Some(limits.depth.map_or(depth, |limit| limit.min(depth))),
+            ..limits
+        }
+    }
+
+    /// Largest noise added to each score, as a fraction of the spread of scores
+    pub fn noise(self) -> f64 {
+        NOISE_AT_LOWEST_SKILL * self.weakness()
+    }
+
+    /// Chance of playing a random move other than the one the noisy scores favour
+    pub fn blunder_probability(self) -> f64 {
+        BLUNDER_PROBABILITY_AT_LOWEST_SKILL * self.weakness().powi(2)
+    }
+
+    /// The move played by `player` at this level from `ranked_moves`, with its score
+    pub fn pick<R: Rng>(self, ranked_moves: &[(Move, i64)], player: Player, rng: &mut R) -> Option<(Move, i64)> {
+        let sign = match player {
+            Player::Red => 1.0,
+            Player::Blue => -1.0,
+        };
+        // Forced wins and losses keep their scores, so they don't stretch the noise out of proportion
+        let heuristic = ranked_moves.iter().map(|&(_, score)| score).filter(|&score| !is_decisive(score));
+        let spread = match (heuristic.clone().min(), heuristic.max()) {
+            (Some(min), Some(max)) => (max - min) as f64,
+            _ => 0.0,
+        };
+        let noise = self.noise() * spread;
+        let mut noisy_score = |score: i64| match is_decisive(score) || noise == 0.0 {
+            true => score as f64,
+            false => score as f64 + rng.gen_range(-noise..=noise),
+        };
+        let noisy_scores: Vec<f64> = ranked_moves.iter().map(|&(_, score)| sign * noisy_score(score)).collect();
+        let best = (0..ranked_moves.len()).max_by(|&a, &b| noisy_scores[a].total_cmp(&noisy_scores[b]))?;
+        let others: Vec<usize> = (0..ranked_moves.len()).filter(|&index| index != best).collect();
+        let picked = match others.choose(rng) {
+            Some(&blunder) if rng.gen_bool(self.blunder_probability()) => blunder,
+            _ => best,
+        };
+        Some(ranked_moves[picked])
+    }
+}
+
+impl Default for SkillLevel {
+    fn default() -> Self {
+        SkillLevel::MAX
+    }
+}
+
+impl TryFrom<u8> for SkillLevel {
+    type Error = SkillLevelError;
+
+    fn try_from(level: u8) -> Result<Self, Self::Error> {
+        SkillLevel::new(level)
+    }
+}
+
+impl FromStr for SkillLevel {
+    type Err = SkillLevelError;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        let number: u64 = level
+            .parse()
+            .map_err(|_| SkillLevelError::NotANumber(level.to_string()))?;
+        let number = u8::try_from(number).map_err(|_| SkillLevelError::OutOfRange(number))?;
+        SkillLevel::new(number)
+    }
+}
+
+impl From<SkillLevel> for u8 {
+    fn from(skill: SkillLevel) -> Self {
+        skill.0
+    }
+}
+
+/// Weakens `agent` to play at `skill`, by limiting its search then picking from its ranked moves with noisy
+/// scores and the odd blunder.
+///
+/// Agents that can't rank moves are only weakened by the shorter search.
+pub struct Skilled<A> {
+    agent: A,
+    pub skill: SkillLevel,
+}
+
+impl<A: BeliefAgent> Skilled<A> {
+    pub fn new(agent: A, skill: SkillLevel) -> Skilled<A> {
+        Skilled { agent, skill }
+    }
+}
+
+impl<A: BeliefAgent> BeliefAgent for Skilled<A> {
+    fn new_game(&mut self) {
+        self.agent.new_game();
+    }
+
+    fn choose_move_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<SearchReport> {
+        if self.skill == SkillLevel::MAX {
+            return self.agent.choose_move_with_belief(state, belief, limits);
+        }
+        let limits = self.skill.limits(limits);
+        let Ranking { ranked_moves, report } = match self.agent.rank_moves_with_belief(state, belief, limits) {
+            Some(ranking) => ranking,
+            None => return self.agent.choose_move_with_belief(state, belief, limits),
+        };
+        let (game_move, score) = self.skill.pick(&ranked_moves, state.board().turn, &mut limits.rng())?;
+        if game_move == report.game_move {
+            return Some(report);
+        }
+        Some(SearchReport {
+            depth: report.depth,
+            nodes: report.nodes,
+            simulations: report.simulations,
+            ..SearchReport::new(game_move, score)
+        })
+    }
+
+    fn rank_moves_with_belief(
+        &mut self,
+        state: &GameState,
+        belief: &NinjaBelief,
+        limits: SearchLimits,
+    ) -> Option<Ranking> {
+        self.agent.rank_moves_with_belief(state, belief, self.skill.limits(limits))
+    }
+
+    // Weaker levels would search further than their limits allow
+    fn ponder_with_belief(&mut self, state: &GameState, belief: &NinjaBelief, stop: &AtomicBool) {
+        if self.skill == SkillLevel::MAX {
+            self.agent.ponder_with_belief(state, belief, stop);
+        }
+    }
+}
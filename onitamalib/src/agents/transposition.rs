@@ -0,0 +1,196 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::models::Move;
+use crate::BitBoard;
+
+/// Default number of entries, kept small enough for the wasm build
+pub const DEFAULT_TABLE_BITS: u32 = 16;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Bound {
+    /// The stored value is the exact minimax value
+    Exact,
+    /// The search failed high, the real value is at least the stored value
+    Lower,
+    /// The search failed low, the real value is at most the stored value
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TableEntry {
+    pub hash: u64,
+    pub depth: u16,
+    pub value: i64,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+impl TableEntry {
+    /// Value usable as a cutoff for a search of `depth` within `(alpha, beta)`, if any
+    pub fn cutoff(&self, depth: u16, alpha: i64, beta: i64) -> Option<i64> {
+        if self.depth < depth {
+            return None;
+        }
+        match self.bound {
+            Bound::Exact => Some(self.value),
+            Bound::Lower if self.value >= beta => Some(self.value),
+            Bound::Upper if self.value <= alpha => Some(self.value),
+            _ => None,
+        }
+    }
+}
+
+/// Where searches keep their results, either to themselves or shared between threads
+pub trait Table {
+    fn probe(&self, hash: u64) -> Option<TableEntry>;
+
+    fn store(&mut self, entry: TableEntry);
+
+    /// Plays `game_move` then follows the best moves stored for each position, up to `length` moves in all
+    fn principal_variation(&self, board: &BitBoard, game_move: Move, length: usize) -> Vec<Move> {
+        let mut board = *board;
+        let mut line = vec![game_move];
+        let mut finished = board.apply_move(game_move).winner().is_some();
+        while line.len() < length && !finished {
+            let next = match self.probe(board.hash).and_then(|entry| entry.best_move) {
+                // Stale entries from other placements of hidden Ninjas can hold moves that aren't legal here
+                Some(next) if board.legal_moves().contains(&next) => next,
+                _ => break,
+            };
+            line.push(next);
+            finished = board.apply_move(next).winner().is_some();
+        }
+        line
+    }
+}
+
+/// Collisions in the index are resolved by preferring the deeper search, entries for a new
+/// position always replace entries for a different one
+fn replaces(slot: &Option<TableEntry>, entry: &TableEntry) -> bool {
+    match slot {
+        Some(existing) => existing.hash != entry.hash || existing.depth <= entry.depth,
+        None => true,
+    }
+}
+
+/// Fixed-size table of search results indexed by Zobrist hash, for a single search at a time
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+    mask: u64,
+}
+
+impl TranspositionTable {
+    /// Creates a table holding `2^bits` entries
+    pub fn new(bits: u32) -> TranspositionTable {
+        let size = 1usize << bits;
+        TranspositionTable {
+            entries: vec![None; size],
+            mask: (size - 1) as u64,
+        }
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<&TableEntry> {
+        self.entries[(hash & self.mask) as usize]
+            .as_ref()
+            .filter(|entry| entry.hash == hash)
+    }
+
+    pub fn store(&mut self, entry: TableEntry) {
+        let slot = &mut self.entries[(entry.hash & self.mask) as usize];
+        if replaces(slot, &entry) {
+            *slot = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_TABLE_BITS)
+    }
+}
+
+impl Table for TranspositionTable {
+    fn probe(&self, hash: u64) -> Option<TableEntry> {
+        TranspositionTable::probe(self, hash).copied()
+    }
+
+    fn store(&mut self, entry: TableEntry) {
+        TranspositionTable::store(self, entry)
+    }
+}
+
+impl<T: Table + ?Sized> Table for &mut T {
+    fn probe(&self, hash: u64) -> Option<TableEntry> {
+        (**self).probe(hash)
+    }
+
+    fn store(&mut self, entry: TableEntry) {
+        (**self).store(entry)
+    }
+}
+
+/// Transposition table that any number of threads can search with at once, locking one entry at a time
+#[derive(Debug)]
+pub struct SharedTable {
+    entries: Vec<Mutex<Option<TableEntry>>>,
+    mask: u64,
+}
+
+impl SharedTable {
+    /// Creates a table holding `2^bits` entries
+    pub fn new(bits: u32) -> SharedTable {
+        let size = 1usize << bits;
+        SharedTable {
+            entries: (0..size).map(|_| Mutex::new(None)).collect(),
+            mask: (size - 1) as u64,
+        }
+    }
+
+    // A search that panicked can only have left a whole entry behind
+    fn slot(&self, hash: u64) -> MutexGuard<'_, Option<TableEntry>> {
+        self.entries[(hash & self.mask) as usize]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn clear(&self) {
+        for entry in self.entries.iter() {
+            *entry.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        }
+    }
+}
+
+impl Default for SharedTable {
+    fn default() -> Self {
+        SharedTable::new(DEFAULT_TABLE_BITS)
+    }
+}
+
+impl Table for &SharedTable {
+    fn probe(&self, hash: u64) -> Option<TableEntry> {
+        self.slot(hash).filter(|entry| entry.hash == hash)
+    }
+
+    fn store(&mut self, entry: TableEntry) {
+        let mut slot = self.slot(entry.hash);
+        if replaces(&slot, &entry) {
+            *slot = Some(entry);
+        }
+    }
+}
+
+/// Bound type for a value found by searching within `(alpha, beta)`
+pub fn bound_for(value: i64, alpha: i64, beta: i64) -> Bound {
+    if value <= alpha {
+        Bound::Upper
+    } else if value >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    }
+}
@@ -0,0 +1,89 @@
+// Fits evaluation weights to game results, Texel style: a position's evaluation through a logistic
+// curve predicts the result of the game it came from, and the weights are fitted to minimize the
+// squared error of that prediction over many positions.
+use crate::evaluation::{EvalWeights, Features, FEATURES};
+use crate::Player;
+
+/// A position from a finished game, labelled with its result
+#[derive(Copy, Clone, Debug)]
+pub struct Sample {
+    pub features: Features,
+    /// 1 if Red won, 0 if Blue won and 0.5 for a game that didn't finish
+    pub result: f64,
+}
+
+impl Sample {
+    pub fn new(features: Features, winner: Option<Player>) -> Sample {
+        let result = match winner {
+            Some(Player::Red) => 1.0,
+            Some(Player::Blue) => 0.0,
+            None => 0.5,
+        };
+        Sample { features, result }
+    }
+}
+
+fn evaluate(weights: &[f64; FEATURES], features: &Features) -> f64 {
+    (0..FEATURES).map(|idx| weights[idx] * features.values[idx]).sum()
+}
+
+fn sigmoid(value: f64) -> f64 {
+    1.0 / (1.0 + (-value).exp())
+}
+
+/// Mean squared error of predicting each result from the evaluation scaled by `scale`
+pub fn prediction_error(samples: &[Sample], weights: &EvalWeights, scale: f64) -> f64 {
+    let weights = weights.to_array();
+    let total: f64 = samples
+        .iter()
+        .map(|sample| (sigmoid(scale * evaluate(&weights, &sample.features)) - sample.result).powi(2))
+        .sum();
+    total / samples.len() as f64
+}
+
+/// The scale that best turns evaluations from `weights` into win chances.
+///
+/// Fixing it before fitting keeps the weights in the units of the starting weights.
+pub fn fit_scale(samples: &[Sample], weights: &EvalWeights) -> f64 {
+    let error = |exponent: f64| prediction_error(samples, weights, 10f64.powf(exponent));
+    // Error is unimodal in the scale, so a ternary search over its exponent finds the minimum
+    let (mut low, mut high) = (-4.0, 1.0);
+    for _ in 0..60 {
+        let third = (high - low) / 3.0;
+        if error(low + third) < error(high - third) {
+            high -= third;
+        } else {
+            low += third;
+        }
+    }
+    10f64.powf((low + high) / 2.0)
+}
+
+/// Fits weights starting from `initial` with full batch Adam, keeping `scale` fixed
+pub fn fit_weights(samples: &[Sample], initial: &EvalWeights, scale: f64, epochs: usize) -> EvalWeights {
+    const LEARNING_RATE: f64 = 0.05;
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    let mut weights = initial.to_array();
+    let mut first_moment = [0.0; FEATURES];
+    let mut second_moment = [0.0; FEATURES];
+    for epoch in 1..=epochs {
+        let mut gradient = [0.0; FEATURES];
+        for sample in samples {
+            let predicted = sigmoid(scale * evaluate(&weights, &sample.features));
+            let slope = 2.0 * (predicted - sample.result) * predicted * (1.0 - predicted) * scale;
+            for (total, value) in gradient.iter_mut().zip(sample.features.values.iter()) {
+                *total += slope * value;
+            }
+        }
+        for idx in 0..FEATURES {
+            let gradient = gradient[idx] / samples.len() as f64;
+            first_moment[idx] = BETA1 * first_moment[idx] + (1.0 - BETA1) * gradient;
+            second_moment[idx] = BETA2 * second_moment[idx] + (1.0 - BETA2) * gradient * gradient;
+            let first = first_moment[idx] / (1.0 - BETA1.powi(epoch as i32));
+            let second = second_moment[idx] / (1.0 - BETA2.powi(epoch as i32));
+            weights[idx] -= LEARNING_RATE * first / (second.sqrt() + 1e-8);
+        }
+    }
+    EvalWeights::from_array(weights)
+}
@@ -1,15 +1,23 @@
 use std::collections::HashMap;
 use std::sync::{mpsc, Arc, Mutex};
-use std::thread;
+use std::{env, fs, thread};
 
 use enum_iterator::IntoEnumIterator;
 use indicatif::ProgressBar;
-use instant::Duration;
-
-use onitamalib::{AiAgent, GameState, Player};
+use onitamalib::{AiAgent, Board, EvalWeights, GameSettings, GameState, Player, SearchLimits};
 use std::thread::JoinHandle;
 
-const TURN_DURATION: Duration = Duration::from_millis(100);
+// Usage: benchmarkagents [weights file]
+// Agents that evaluate positions use the weights in the file, as written by `tuneweights`, or the defaults
+
+/// Budgets rather than time, so results don't depend on how fast or busy the machine is
+const TURN_LIMITS: SearchLimits = SearchLimits {
+    time: None,
+    nodes: Some(20_000),
+    depth: None,
+    simulations: Some(2_000),
+    seed: None,
+};
 const MATCH_REPEATS: u64 = 10;
 const MAX_TURNS: u64 = 250;
 const PARALLELISM: u64 = 14;
@@ -17,6 +25,9 @@ const PARALLELISM: u64 = 14;
 struct Match {
     red: AiAgent,
     blue: AiAgent,
+    /// Every pairing plays the same setups, so runs can be compared
+    seed: u64,
+    weights: EvalWeights,
 }
 
 impl Match {
@@ -27,7 +38,17 @@ impl Match {
         }
     }
     pub fn winner(&self) -> Option<AiAgent> {
-        let mut state = GameState::new();
+        let settings = GameSettings {
+            seed: Some(self.seed),
+            ..GameSettings::default()
+        };
+        // Agents don't take part in setup, any Ninjas are placed at random
+        let board = Board::new_with_settings(settings);
+        let mut state = GameState::Playing { board };
+        let mut red = self.red.build_with_weights(self.weights);
+        let mut blue = self.blue.build_with_weights(self.weights);
+        red.new_game(&state, Player::Red);
+        blue.new_game(&state, Player::Blue);
         let mut iteration = 0u64;
         loop {
             iteration += 1;
@@ -37,23 +58,40 @@ impl Match {
             }
             let board = match state {
                 GameState::Playing { board } => board,
+                GameState::Setup { .. } => unreachable!("Setup was skipped"),
                 GameState::Finished { winner, .. } => {
                     return Some(self.agent_from_player(winner));
                 }
             };
-            let agent = self.agent_from_player(board.turn);
-            let (game_move, _) = agent.play_move(&state, TURN_DURATION).unwrap();
+            let agent = match board.turn {
+                Player::Red => &mut red,
+                Player::Blue => &mut blue,
+            };
+            let limits = SearchLimits {
+                seed: Some(self.seed * MAX_TURNS + iteration),
+                ..TURN_LIMITS
+            };
+            let game_move = agent.choose_move(&state, limits).unwrap().game_move;
+            red.observe_move(&state, game_move);
+            blue.observe_move(&state, game_move);
             state = state.try_move(game_move).unwrap();
         }
     }
 }
 fn main() {
+    let weights = match env::args().nth(1) {
+        Some(path) => {
+            let json = fs::read_to_string(&path).expect("Failed to read the weights file");
+            EvalWeights::from_json(&json).expect("Weights file should hold weights as JSON")
+        }
+        None => EvalWeights::default(),
+    };
     let mut matches: Vec<Match> = vec![];
     for red in AiAgent::into_enum_iter() {
         for blue in AiAgent::into_enum_iter() {
             if red != blue {
-                for _ in 0..MATCH_REPEATS {
-                    let ai_match = Match { blue, red };
+                for seed in 0..MATCH_REPEATS {
+                    let ai_match = Match { blue, red, seed, weights };
                     matches.push(ai_match);
                 }
             }
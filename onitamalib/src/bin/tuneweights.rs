@@ -0,0 +1,114 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::{env, fs};
+
+use indicatif::ProgressBar;
+use instant::Duration;
+use rand::prelude::*;
+
+use onitamalib::evaluation::Features;
+use onitamalib::tuning::{fit_scale, fit_weights, prediction_error, Sample};
+use onitamalib::{AiAgent, BitBoard, Board, EvalWeights, GameSettings, GameState, Player, SearchLimits};
+
+// Usage: tuneweights [output file] [games]
+// Tunes the evaluation weights on self-play games and writes them as JSON, ready for `EvalWeights::from_json`
+const DEFAULT_OUTPUT: &str = "weights.json";
+const DEFAULT_GAMES: u64 = 500;
+const TURN_DURATION: Duration = Duration::from_millis(50);
+const MAX_TURNS: u64 = 250;
+const PARALLELISM: u64 = 14;
+/// Random moves opening each game, so the agents don't play the same game over and over
+const RANDOM_PLIES: usize = 4;
+const AGENTS: [AiAgent; 3] = [AiAgent::Greedy, AiAgent::Alphabeta, AiAgent::MonteCarloTreeSearch];
+const EPOCHS: usize = 2000;
+
+/// Plays a game between two agents picked by `seed`, returning the positions with the winner
+fn self_play(seed: u64) -> (Vec<BitBoard>, Option<Player>) {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let settings = GameSettings {
+        seed: Some(seed),
+        ..GameSettings::default()
+    };
+    // Agents don't take part in setup, any Ninjas are placed at random
+    let board = Board::new_with_settings(settings);
+    let mut state = GameState::Playing { board };
+    let mut red = AGENTS.choose(&mut rng).unwrap().build();
+    let mut blue = AGENTS.choose(&mut rng).unwrap().build();
+    red.new_game(&state, Player::Red);
+    blue.new_game(&state, Player::Blue);
+    let mut positions = vec![];
+    for turn in 0..MAX_TURNS as usize {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Setup { .. } => unreachable!("Setup was skipped"),
+            GameState::Finished { winner, .. } => return (positions, Some(winner)),
+        };
+        let game_move = match turn < RANDOM_PLIES {
+            true => board.random_legal_move(&mut rng).unwrap(),
+            false => {
+                positions.push(BitBoard::from(&board));
+                let agent = match board.turn {
+                    Player::Red => &mut red,
+                    Player::Blue => &mut blue,
+                };
+                // A search starved of time by the other threads may not find a move
+                match agent.choose_move(&state, SearchLimits::time(TURN_DURATION)) {
+                    Some(report) => report.game_move,
+                    None => board.random_legal_move(&mut rng).unwrap(),
+                }
+            }
+        };
+        red.observe_move(&state, game_move);
+        blue.observe_move(&state, game_move);
+        state = state.try_move(game_move).unwrap();
+    }
+    (positions, None)
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let output = args.next().unwrap_or_else(|| DEFAULT_OUTPUT.to_string());
+    let games: u64 = args
+        .next()
+        .map(|games| games.parse().expect("Number of games should be a number"))
+        .unwrap_or(DEFAULT_GAMES);
+
+    let pb = ProgressBar::new(games);
+    let seeds = Arc::new(Mutex::new((0..games).collect::<Vec<u64>>()));
+    let (tx, rx) = mpsc::channel();
+    let handles: Vec<JoinHandle<()>> = (0..PARALLELISM)
+        .map(|_| {
+            let (seeds, tx) = (Arc::clone(&seeds), tx.clone());
+            thread::spawn(move || loop {
+                let seed = match seeds.lock().unwrap().pop() {
+                    None => break,
+                    Some(seed) => seed,
+                };
+                tx.send(self_play(seed)).unwrap();
+            })
+        })
+        .collect();
+    drop(tx);
+    let mut samples: Vec<Sample> = vec![];
+    for (positions, winner) in rx.into_iter() {
+        samples.extend(
+            positions
+                .iter()
+                .map(|board| Sample::new(Features::new(board), winner)),
+        );
+        pb.inc(1);
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap();
+    }
+    pb.finish();
+
+    let initial = EvalWeights::default();
+    let scale = fit_scale(&samples, &initial);
+    println!("{} positions, scale {:.4}", samples.len(), scale);
+    println!("Error before: {:.5}", prediction_error(&samples, &initial, scale));
+    let tuned = fit_weights(&samples, &initial, scale, EPOCHS);
+    println!("Error after: {:.5}", prediction_error(&samples, &tuned, scale));
+    fs::write(&output, tuned.to_json()).expect("Failed to write weights");
+    println!("Weights written to {}", output);
+}
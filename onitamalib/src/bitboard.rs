@@ -0,0 +1,290 @@
+use std::sync::OnceLock;
+
+use enum_iterator::IntoEnumIterator;
+
+use crate::models::{Board, Card, CardSet, Player, Point};
+use crate::zobrist;
+
+pub const SQUARES: usize = 25;
+/// Mask with every square of the board set
+pub const FULL_BOARD: u32 = (1 << SQUARES) - 1;
+
+/// Packed form of a `Board`, used for move generation and search.
+///
+/// Every piece type is a 25-bit mask where bit `y * 5 + x` is set when a piece occupies `(x, y)`.
+/// Per-player fields are indexed by `side`, so `Red` is `0` and `Blue` is `1`.
+/// Convert with `BitBoard::from(&board)` and `Board::from(&bits)`, the serde `Board` stays the wire format.
+/// `hash` is the Zobrist hash of the position and is kept up to date by `try_move`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BitBoard {
+    pub wind_spirit: u32,
+    pub kings: [u32; 2],
+    pub pawns: [u32; 2],
+    pub ninjas: [u32; 2],
+    pub revealed_ninjas: [u32; 2],
+    pub hands: [[Card; 2]; 2],
+    pub spare_card: Card,
+    pub wind_move_pending: bool,
+    pub wind_move_card: Option<Card>,
+    pub shadow_mode: bool,
+    pub light_mode: bool,
+    pub ninja_move_pending: bool,
+    pub ninja_move_card: Option<Card>,
+    pub turn: Player,
+    pub hash: u64,
+}
+
+/// Returned by `apply_move`, holds what `undo` needs to restore the position before the move
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Undo {
+    pub(crate) previous: BitBoard,
+    pub(crate) winner: Option<Player>,
+}
+
+impl Undo {
+    /// The player who won with the move, if it finished the game
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+}
+
+pub fn side(player: Player) -> usize {
+    match player {
+        Player::Red => 0,
+        Player::Blue => 1,
+    }
+}
+
+pub fn square(point: Point) -> usize {
+    (point.y * 5 + point.x) as usize
+}
+
+pub fn point(square: usize) -> Point {
+    Point {
+        x: (square % 5) as i8,
+        y: (square / 5) as i8,
+    }
+}
+
+/// Mask with only `point` set, or an empty mask if `point` is off the board
+pub fn bit(point: Point) -> u32 {
+    if point.in_bounds() {
+        1 << square(point)
+    } else {
+        0
+    }
+}
+
+/// Iterates over the squares set in `mask`, lowest square first
+pub fn points(mut mask: u32) -> impl Iterator<Item = Point> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let idx = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(point(idx))
+    })
+}
+
+struct MoveTables {
+    // Indexed by [card][normal, king, wind][side][square]
+    targets: Vec<[[[u32; SQUARES]; 2]; 3]>,
+    wind_cards: Vec<bool>,
+}
+
+impl MoveTables {
+    fn new() -> MoveTables {
+        let card_count = Card::into_enum_iter().count();
+        let mut targets = vec![[[[0u32; SQUARES]; 2]; 3]; card_count];
+        let mut wind_cards = vec![false; card_count];
+        let way_of_the_wind = CardSet::WayOfTheWind.cards();
+        for card in Card::into_enum_iter() {
+            let idx = card.index() as usize;
+            wind_cards[idx] = way_of_the_wind.contains(&card);
+            let variants = [
+                card.moves(false, false),
+                card.moves(true, false),
+                card.moves(false, true),
+            ];
+            for (kind, deltas) in variants.iter().enumerate() {
+                for player in [Player::Red, Player::Blue] {
+                    for (src, mask) in targets[idx][kind][side(player)].iter_mut().enumerate() {
+                        let src_point = point(src);
+                        *mask = deltas.iter().fold(0, |mask, &delta| {
+                            let delta = match player {
+                                Player::Red => delta,
+                                Player::Blue => -delta,
+                            };
+                            mask | bit(src_point + delta)
+                        });
+                    }
+                }
+            }
+        }
+        MoveTables {
+            targets,
+            wind_cards,
+        }
+    }
+}
+
+fn tables() -> &'static MoveTables {
+    static TABLES: OnceLock<MoveTables> = OnceLock::new();
+    TABLES.get_or_init(MoveTables::new)
+}
+
+/// Destinations reachable from `src` by `player` using `card`, ignoring other pieces
+pub fn move_targets(card: Card, is_king: bool, is_spirit: bool, player: Player, src: Point) -> u32 {
+    let kind = match (is_king, is_spirit) {
+        (_, true) => 2,
+        (true, false) => 1,
+        (false, false) => 0,
+    };
+    tables().targets[card.index() as usize][kind][side(player)][square(src)]
+}
+
+pub fn is_wind_card(card: Card) -> bool {
+    tables().wind_cards[card.index() as usize]
+}
+
+impl BitBoard {
+    /// Pieces the current player may not move onto, their own pieces and the Wind Spirit
+    pub fn player_pieces(&self) -> u32 {
+        let player = side(self.turn);
+        self.kings[player] | self.pawns[player] | self.ninjas[player] | self.wind_spirit
+    }
+
+    pub fn opponent_pieces(&self) -> u32 {
+        let opponent = 1 - side(self.turn);
+        self.kings[opponent] | self.pawns[opponent] | self.ninjas[opponent]
+    }
+
+    pub fn has_hidden_ninjas(&self) -> bool {
+        (self.ninjas[0] & !self.revealed_ninjas[0]) | (self.ninjas[1] & !self.revealed_ninjas[1]) != 0
+    }
+
+    pub fn has_remaining_pieces(&self, player: Player) -> bool {
+        let player = side(player);
+        self.kings[player] | self.pawns[player] | self.ninjas[player] != 0
+    }
+
+    /// Writes the position into `board`, keeping each piece in its existing slot where possible
+    /// so that pieces can be followed across moves.
+    pub fn write_to(&self, board: &mut Board) {
+        board.wind_spirit = points(self.wind_spirit).next();
+        board.red_king = points(self.kings[0]).next();
+        board.blue_king = points(self.kings[1]).next();
+        board.red_pawns = sync_slots(&board.red_pawns, self.pawns[0]);
+        board.blue_pawns = sync_slots(&board.blue_pawns, self.pawns[1]);
+        board.red_ninjas = sync_ninjas(&board.red_ninjas, self.ninjas[0], self.revealed_ninjas[0]);
+        board.blue_ninjas = sync_ninjas(&board.blue_ninjas, self.ninjas[1], self.revealed_ninjas[1]);
+        board.red_hand = self.hands[0];
+        board.blue_hand = self.hands[1];
+        board.spare_card = self.spare_card;
+        board.wind_move_pending = self.wind_move_pending;
+        board.wind_move_card = self.wind_move_card;
+        board.shadow_mode = self.shadow_mode;
+        board.light_mode = self.light_mode;
+        board.ninja_move_pending = self.ninja_move_pending;
+        board.ninja_move_card = self.ninja_move_card;
+        board.turn = self.turn;
+    }
+}
+
+fn sync_slots<const N: usize>(slots: &[Option<Point>; N], mask: u32) -> [Option<Point>; N] {
+    let mut synced = [None; N];
+    let mut placed = 0;
+    for (slot, old) in synced.iter_mut().zip(slots.iter()) {
+        if let Some(pos) = old {
+            if mask & !placed & bit(*pos) != 0 {
+                *slot = Some(*pos);
+                placed |= bit(*pos);
+            }
+        }
+    }
+    // Pieces that moved take the slot they left, ahead of slots that were already empty
+    let vacated = (0..N).filter(|&idx| slots[idx].is_some());
+    let unused = (0..N).filter(|&idx| slots[idx].is_none());
+    let mut free = [0; N];
+    let mut count = 0;
+    for idx in vacated.chain(unused) {
+        if synced[idx].is_none() {
+            free[count] = idx;
+            count += 1;
+        }
+    }
+    for (pos, &idx) in points(mask & !placed).zip(free[..count].iter()) {
+        synced[idx] = Some(pos);
+    }
+    synced
+}
+
+fn sync_ninjas(slots: &[Option<(Point, bool)>; 2], mask: u32, revealed: u32) -> [Option<(Point, bool)>; 2] {
+    let positions = sync_slots(&slots.map(|ninja| ninja.map(|(pos, _)| pos)), mask);
+    positions.map(|pos| pos.map(|pos| (pos, revealed & bit(pos) != 0)))
+}
+
+impl From<&Board> for BitBoard {
+    fn from(board: &Board) -> Self {
+        let mask = |pieces: &[Option<Point>]| pieces.iter().flatten().fold(0, |m, &p| m | bit(p));
+        let ninja_mask = |ninjas: &[Option<(Point, bool)>; 2], only_revealed: bool| {
+            ninjas
+                .iter()
+                .flatten()
+                .filter(|(_, revealed)| *revealed || !only_revealed)
+                .fold(0, |m, &(p, _)| m | bit(p))
+        };
+        let mut bits = BitBoard {
+            wind_spirit: mask(&[board.wind_spirit]),
+            kings: [mask(&[board.red_king]), mask(&[board.blue_king])],
+            pawns: [mask(&board.red_pawns), mask(&board.blue_pawns)],
+            ninjas: [
+                ninja_mask(&board.red_ninjas, false),
+                ninja_mask(&board.blue_ninjas, false),
+            ],
+            revealed_ninjas: [
+                ninja_mask(&board.red_ninjas, true),
+                ninja_mask(&board.blue_ninjas, true),
+            ],
+            hands: [board.red_hand, board.blue_hand],
+            spare_card: board.spare_card,
+            wind_move_pending: board.wind_move_pending,
+            wind_move_card: board.wind_move_card,
+            shadow_mode: board.shadow_mode,
+            light_mode: board.light_mode,
+            ninja_move_pending: board.ninja_move_pending,
+            ninja_move_card: board.ninja_move_card,
+            turn: board.turn,
+            hash: 0,
+        };
+        bits.hash = zobrist::hash(&bits);
+        bits
+    }
+}
+
+impl From<&BitBoard> for Board {
+    fn from(bits: &BitBoard) -> Self {
+        let mut board = Board {
+            wind_spirit: None,
+            blue_king: None,
+            blue_pawns: [None; 4],
+            blue_ninjas: [None; 2],
+            blue_hand: bits.hands[1],
+            red_king: None,
+            red_pawns: [None; 4],
+            red_ninjas: [None; 2],
+            red_hand: bits.hands[0],
+            spare_card: bits.spare_card,
+            wind_move_pending: false,
+            wind_move_card: None,
+            shadow_mode: false,
+            light_mode: false,
+            ninja_move_pending: false,
+            ninja_move_card: None,
+            turn: bits.turn,
+        };
+        bits.write_to(&mut board);
+        board
+    }
+}
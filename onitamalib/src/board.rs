@@ -3,392 +3,388 @@ use enum_iterator::IntoEnumIterator;
 use rand::prelude::*;
 use std::collections::HashSet;
 
-use crate::models::{Board, Card, GameSquare, GameSettings, GameState, Move, Player, Point};
+use crate::bitboard::{bit, is_wind_card, move_targets, side, BitBoard, Undo};
+use crate::models::{Board, Card, GameSquare, GameSettings, GameState, Move, MoveError, Player, Point};
+use crate::zobrist;
 
-impl Board {
-    pub fn try_move(&self, game_move: Move) -> Result<GameState, String> {
-        // Destructure only what we actually need
-        let Board {
-            wind_spirit,
-            blue_king,
-            blue_hand,
-            red_king,
-            red_hand,
-            spare_card,
-            wind_move_pending,
-            shadow_mode,
-            turn,
-            ..
-        } = self;
+impl BitBoard {
+    /// Validates and plays `game_move`, returning the new position and the winner if the game ended.
+    pub fn try_move(&self, game_move: Move) -> Result<(BitBoard, Option<Player>), MoveError> {
+        let (mut updated_board, winner) = self.play(game_move)?;
+        updated_board.hash = self.hash ^ zobrist::delta(self, &updated_board);
+        Ok((updated_board, winner))
+    }
 
+    fn play(&self, game_move: Move) -> Result<(BitBoard, Option<Player>), MoveError> {
         // If there's an wind move pending, route to try_wind_move
-        if *wind_move_pending && !self.ninja_move_pending {
+        if self.wind_move_pending && !self.ninja_move_pending {
             return self.try_wind_move(game_move);
         }
 
-        // Identify current and opponent kings
-        let (player_king, opponent_king) = match turn {
-            Player::Red => (red_king, blue_king),
-            Player::Blue => (blue_king, red_king),
-        };
-
-        // Gather player's pieces (king + pawns + optional wind spirit)
-        let player_pieces = self.player_pieces();
+        let player = side(self.turn);
 
         // Parse the move
         let (card, src, dst, reveal_ninja) = match game_move {
             Move::Move { card, src, dst, reveal_ninja } => (card, src, dst, reveal_ninja),
             Move::Discard { card } => {
+                if !self.hands[player].contains(&card) {
+                    return Err(MoveError::CardNotInHand { card });
+                }
                 if self.ninja_move_pending && self.can_move() {
                     if Some(card) != self.ninja_move_card {
-                        return Err("Invalid card to discard during Ninja move phase.".to_string());
+                        return Err(MoveError::WrongNinjaDiscard {
+                            card,
+                            required: self.ninja_move_card.unwrap_or(card),
+                        });
                     }
 
-                    // Create a new board with ninja_move_pending set to false
-                    let mut updated_board = self.clone();
+                    let mut updated_board = *self;
                     updated_board.ninja_move_pending = false;
 
                     // If wind_move_pending is false, discard the card
                     if !updated_board.wind_move_pending {
-                        return updated_board.discard_card(card);
+                        return Ok((updated_board.discard_card(card), None));
                     }
 
                     // Otherwise, return the updated board without discarding
                     updated_board.ninja_move_card = None;
-                    return Ok(GameState::Playing { board: updated_board });
+                    return Ok((updated_board, None));
                 }
 
                 // If not during Ninja move phase, ensure no moves exist
                 if self.can_move() {
-                    return Err("Valid moves exist".to_string());
+                    return Err(MoveError::ValidMovesExist { card });
                 }
 
-                return self.discard_card(card);
+                return Ok((self.discard_card(card), None));
             }
+            Move::PlaceNinja { .. } => return Err(MoveError::NotInSetup),
         };
 
+        let src_bit = bit(src);
+        let player_pieces = self.player_pieces();
+
         // Validations
-        if !self.player_hand().contains(&card) {
-            return Err("Card not in hand".to_string());
+        if !self.hands[player].contains(&card) {
+            return Err(MoveError::CardNotInHand { card });
         }
-        if !player_pieces.contains(&Some(src)) {
-            return Err("No piece at source".to_string());
+        if player_pieces & src_bit == 0 {
+            return Err(MoveError::NoPieceAtSource { src });
         }
 
         // Check if this is a Way of the Shadow game and if the piece is a Ninja
-        if *shadow_mode && self.is_ninja(src) && self.can_move() && !self.ninja_move_pending {
-            return Err("You must move another piece before moving your Ninja!".to_string());
+        let moving_ninja = self.ninjas[player] & src_bit != 0;
+        if self.shadow_mode && moving_ninja && self.can_move() && !self.ninja_move_pending {
+            return Err(MoveError::NinjaMovedFirst { src });
         }
 
         // Check if ninja_move_pending is true and the piece is not a Ninja
         if self.ninja_move_pending {
-            if !self.is_ninja(src) {
-                return Err("Only the Ninja can move now.".to_string());
+            if !moving_ninja {
+                return Err(MoveError::OnlyNinjaCanMove { src });
             }
-            if let Some(ninja_card) = self.ninja_move_card {
-                if Some(card) != Some(ninja_card) {
-                    return Err(format!("Must use the {} card for the Ninja move.", ninja_card));
+            match self.ninja_move_card {
+                Some(ninja_card) if ninja_card != card => {
+                    return Err(MoveError::WrongNinjaCard {
+                        card,
+                        required: ninja_card,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    return Err(MoveError::NinjaMoveCardMissing);
                 }
-            } else {
-                return Err("Ninja move card is not set during ninja_move_pending.".to_string());
             }
         }
 
-        let move_wind_spirit = wind_spirit.map_or(false, |ws| ws == src);
-        if move_wind_spirit && CardSet::WayOfTheWind.cards().contains(&card) {
-            return Err("Wind Spirit cannot use a Way of the Wind card to move".to_string());
+        let move_wind_spirit = self.wind_spirit & src_bit != 0;
+        if move_wind_spirit && is_wind_card(card) {
+            return Err(MoveError::WindSpiritUsedWindCard { card });
         }
-        if out_of_bounds(dst) {
-            return Err("Destination is out of bounds".to_string());
+        if dst.out_of_bounds() {
+            return Err(MoveError::DestinationOutOfBounds { dst });
         }
+        let dst_bit = bit(dst);
 
-        let raw_delta = dst - src;
-        let delta = match turn {
-            Player::Red => raw_delta,
-            Player::Blue => -raw_delta,
-        };
-
-        let moving_king = *player_king == Some(src);
-        let moves = card.moves(moving_king, false);
-        if !moves.contains(&delta) {
-            return Err("Move not valid for card".to_string());
+        let moving_king = self.kings[player] & src_bit != 0;
+        if move_targets(card, moving_king, false, self.turn, src) & dst_bit == 0 {
+            return Err(MoveError::InvalidMoveForCard { card, src, dst });
         }
 
-        if move_wind_spirit && (Some(dst) == *red_king || Some(dst) == *blue_king) {
-            return Err("Wind Spirit cannot move onto a Master!".to_string());
+        if move_wind_spirit && (self.kings[0] | self.kings[1]) & dst_bit != 0 {
+            return Err(MoveError::WindSpiritOntoMaster { dst });
         }
 
         // If a non-Wind Spirit piece tries to move onto your own piece, that's invalid
-        if player_pieces.contains(&Some(dst))
-            && !(move_wind_spirit
-                && (self.player_pawns().contains(&Some(dst))
-                    || self.player_ninjas()
-                        .iter()
-                        .filter_map(|ninja| ninja.map(|(point, _)| point))
-                        .any(|point| point == dst)))
-        {
-            return Err("Destination occupied by your piece".to_string());
+        let swappable = self.pawns[player] | self.ninjas[player];
+        if player_pieces & dst_bit != 0 && !(move_wind_spirit && swappable & dst_bit != 0) {
+            return Err(MoveError::DestinationOccupied { dst });
         }
+        let mut updated_board = *self;
+        let winner = updated_board.move_piece(card, src, dst, reveal_ninja);
+        Ok((updated_board, winner))
+    }
+
+    /// Plays `game_move` without validating it, returning what `undo` needs to take it back.
+    ///
+    /// Only use this for moves from `legal_moves`, anything else leaves the position in an undefined state.
+    pub fn apply_move(&mut self, game_move: Move) -> Undo {
+        let previous = *self;
+        let winner = match game_move {
+            Move::Discard { card } => {
+                if self.ninja_move_pending && self.wind_move_pending {
+                    // Skipping the Ninja move still leaves the Wind Spirit move
+                    self.ninja_move_pending = false;
+                    self.ninja_move_card = None;
+                } else {
+                    *self = self.discard_card(card);
+                }
+                None
+            }
+            Move::Move { card, src, dst, .. } if self.wind_move_pending && !self.ninja_move_pending => {
+                self.move_or_swap_pieces(bit(src), bit(dst), true, false);
+                *self = self.discard_card(card);
+                None
+            }
+            Move::Move { card, src, dst, reveal_ninja } => self.move_piece(card, src, dst, reveal_ninja),
+            Move::PlaceNinja { .. } => unreachable!("Ninjas are only placed during setup"),
+        };
+        self.hash = previous.hash ^ zobrist::delta(&previous, self);
+        Undo { previous, winner }
+    }
+
+    /// Takes back the move `undo` was returned for, which must be the last move applied
+    pub fn undo(&mut self, undo: Undo) {
+        *self = undo.previous;
+    }
+
+    // Moves a piece outside of the wind phase, setting up any follow up move and returning the winner if any
+    fn move_piece(&mut self, card: Card, src: Point, dst: Point, reveal_ninja: bool) -> Option<Player> {
+        let player = side(self.turn);
+        let opponent = 1 - player;
+        let src_bit = bit(src);
+        let dst_bit = bit(dst);
+        let moving_ninja = self.ninjas[player] & src_bit != 0;
+        let moving_king = self.kings[player] & src_bit != 0;
+        let move_wind_spirit = self.wind_spirit & src_bit != 0;
+        let had_ninjas = self.ninjas[player] != 0;
+        let captures_king = self.kings[opponent] & dst_bit != 0;
 
         // Set opponent's Temple Arch to goal_square
-        let goal_square = match turn {
+        let goal_square = match self.turn {
             Player::Red => Point { x: 2, y: 0 },
             Player::Blue => Point { x: 2, y: 4 },
         };
 
-        // Move/swap pieces
-        let mut player_pawns = self.player_pawns();
-        let mut opponent_pawns = self.opponent_pawns();
-        let mut player_ninjas = self.player_ninjas();
-        let mut opponent_ninjas = self.opponent_ninjas();
-        move_or_swap_pieces(
-            &mut player_pawns,
-            &mut opponent_pawns,
-            &mut player_ninjas,
-            &mut opponent_ninjas,
-            src,
-            dst,
-            move_wind_spirit,
-            reveal_ninja,
-        );
-
-        let mut ninja_pending = self.ninja_move_pending;
-        let mut ninja_card = self.ninja_move_card;
-
-        if *shadow_mode && self.has_ninja() {
-            if self.is_ninja(src) {
+        self.move_or_swap_pieces(src_bit, dst_bit, move_wind_spirit, reveal_ninja);
+
+        if self.shadow_mode && had_ninjas {
+            if moving_ninja {
                 // Reset ninja_move_pending and ninja_move_card if a Ninja is moved
-                ninja_pending = false;
-                ninja_card = None;
+                self.ninja_move_pending = false;
+                self.ninja_move_card = None;
             } else {
-                // Use enable_ninja_move to check and set pending Ninja move
-                ninja_pending = self.enable_ninja_move(card, src, dst);
-                ninja_card = if ninja_pending { Some(card) } else { None };
+                // The Ninja may follow up with the same card if it has somewhere to go
+                self.ninja_move_pending = self.can_ninja_move(card);
+                self.ninja_move_card = if self.ninja_move_pending { Some(card) } else { None };
             }
         }
 
-
         // Check if we can enable wind move
-        let wind_pending = self.enable_wind_move(card, src, dst);
-        let wind_card = if wind_pending { Some(card) } else { None };
+        self.wind_move_pending = is_wind_card(card) && self.can_wind_move(card);
+        self.wind_move_card = if self.wind_move_pending { Some(card) } else { None };
 
-        // If no wind move, we replace the used card with the spare
-        let player_hand = if !wind_pending && !ninja_pending {
-            replace_card(self.player_hand(), card, *spare_card)
-        } else {
-            *self.player_hand()
-        };
-
-        // Update king position if it moved
-        let new_king_pos = if moving_king { Some(dst) } else { *player_king };
-
-        // Wind spirit might have moved
-        let new_wind_spirit = if move_wind_spirit { Some(dst) } else { *wind_spirit };
-
-        // Build the updated board
-        let updated_board = match turn {
-            Player::Red => Board {
-                wind_spirit: new_wind_spirit,
-                blue_king: *blue_king,
-                blue_pawns: opponent_pawns,
-                blue_ninjas: opponent_ninjas,
-                blue_hand: *blue_hand,
-                red_king: new_king_pos,
-                red_pawns: player_pawns,
-                red_ninjas: player_ninjas,
-                red_hand: player_hand,
-                spare_card: if wind_pending || ninja_pending { *spare_card } else { card },
-                wind_move_pending: wind_pending,
-                wind_move_card: wind_card,
-                shadow_mode: *shadow_mode,
-                ninja_move_pending: ninja_pending,
-                ninja_move_card: ninja_card,
-                turn: if wind_pending || ninja_pending { Player::Red } else { Player::Blue },
-            },
-            Player::Blue => Board {
-                wind_spirit: new_wind_spirit,
-                blue_king: new_king_pos,
-                blue_pawns: player_pawns,
-                blue_ninjas: player_ninjas,
-                blue_hand: player_hand,
-                red_king: *red_king,
-                red_pawns: opponent_pawns,
-                red_ninjas: opponent_ninjas,
-                red_hand: *red_hand,
-                spare_card: if wind_pending || ninja_pending { *spare_card } else { card },
-                wind_move_pending: wind_pending,
-                wind_move_card: wind_card,
-                shadow_mode: *shadow_mode,
-                ninja_move_pending: ninja_pending,
-                ninja_move_card: ninja_card,
-                turn: if wind_pending || ninja_pending { Player::Blue } else { Player::Red },
-            },
-        };
+        // If no follow up move is pending, we replace the used card with the spare and pass the turn
+        let mover = self.turn;
+        if !self.wind_move_pending && !self.ninja_move_pending {
+            self.hands[player] = replace_card(&self.hands[player], card, self.spare_card);
+            self.spare_card = card;
+            self.turn = mover.invert();
+        }
 
         // Check if this move finishes the game
-        if Some(dst) == *opponent_king || (moving_king && dst == goal_square) || !updated_board.has_remaining_pieces(turn.invert()) {
-            return Ok(GameState::Finished {
-                winner: *turn,
-                board: updated_board,
-            });
+        if captures_king || (moving_king && dst == goal_square) || !self.has_remaining_pieces(mover.invert()) {
+            return Some(mover);
         }
-
-        Ok(GameState::Playing { board: updated_board })
+        None
     }
 
-    fn try_wind_move(&self, game_move: Move) -> Result<GameState, String> {
-         let Board {
-            wind_spirit,
-            blue_king,
-            blue_hand,
-            red_king,
-            red_hand,
-            spare_card,
-            wind_move_card,
-            turn,
-            ..
-        } = self;
+    fn try_wind_move(&self, game_move: Move) -> Result<(BitBoard, Option<Player>), MoveError> {
+        let wind_move_card = match self.wind_move_card {
+            Some(card) => card,
+            None => return Err(MoveError::WindMoveCardMissing),
+        };
 
         // If no valid moves exist for wind move, auto-discard
         if !self.can_move() {
-            let card = wind_move_card.unwrap();
-            let player_hand = replace_card(self.player_hand(), card, *spare_card);
-            let (red_hand, blue_hand) = match turn {
-                Player::Red => (player_hand, *blue_hand),
-                Player::Blue => (*red_hand, player_hand),
-            };
-    
-            return Ok(GameState::Playing {
-                board: Board {
-                    wind_spirit: *wind_spirit,
-                    blue_king: *blue_king,
-                    blue_pawns: self.blue_pawns,
-                    blue_ninjas: self.blue_ninjas,
-                    blue_hand,
-                    red_king: *red_king,
-                    red_pawns: self.red_pawns,
-                    red_ninjas: self.red_ninjas,
-                    red_hand,
-                    spare_card: card,
-                    wind_move_pending: false,
-                    wind_move_card: None,
-                    shadow_mode: self.shadow_mode,
-                    ninja_move_pending: false,
-                    ninja_move_card: None,
-                    turn: turn.invert(),
-                },
-            });
+            return Ok((self.discard_card(wind_move_card), None));
         }
-    
-        let (card, src, dst, ..) = match game_move {
+
+        let (card, src, dst) = match game_move {
             Move::Move { card, src, dst, .. } => (card, src, dst),
+            // Discarding instead skips the Wind Spirit move, which is only allowed when no normal move exists
             Move::Discard { card } => {
-                let mut updated_board = self.clone();
+                let mut updated_board = *self;
                 updated_board.wind_move_pending = false;
-                return updated_board.try_move(Move::Discard { card });
+                return updated_board.play(Move::Discard { card });
             }
+            Move::PlaceNinja { .. } => return Err(MoveError::NotInSetup),
         };
 
-        let wind_spirit_pos = match wind_spirit {
-            Some(pos) => pos,
-            None => return Err("Wind Spirit is missing!".to_string()),
-        };
-        if src != *wind_spirit_pos {
-            return Err("You must move the Wind Spirit".to_string());
+        if self.wind_spirit == 0 {
+            return Err(MoveError::WindSpiritMissing);
         }
-        if card != wind_move_card.unwrap() {
-            return Err(format!("Must use {} to move", wind_move_card.unwrap()));
+        if self.wind_spirit & bit(src) == 0 {
+            return Err(MoveError::MustMoveWindSpirit { src });
         }
-    
-        let (player_king, _opponent_king) = match turn {
-            Player::Red => (red_king, blue_king),
-            Player::Blue => (blue_king, red_king),
-        };
-    
-        if out_of_bounds(dst) {
-            return Err("Destination is out of bounds".to_string());
+        if card != wind_move_card {
+            return Err(MoveError::WrongWindCard {
+                card,
+                required: wind_move_card,
+            });
+        }
+        if dst.out_of_bounds() {
+            return Err(MoveError::DestinationOutOfBounds { dst });
+        }
+        let dst_bit = bit(dst);
+        if move_targets(card, false, true, self.turn, src) & dst_bit == 0 {
+            return Err(MoveError::InvalidMoveForCard { card, src, dst });
+        }
+        if (self.kings[0] | self.kings[1]) & dst_bit != 0 {
+            return Err(MoveError::WindSpiritOntoMaster { dst });
         }
 
-        let raw_delta = dst - src;
-        let delta = match turn {
-            Player::Red => raw_delta,
-            Player::Blue => -raw_delta,
-        };
-    
-        let moves = card.moves(false, true);
-        if !moves.contains(&delta) {
-            return Err("Move not valid for card".to_string());
+        let mut updated_board = *self;
+        updated_board.move_or_swap_pieces(bit(src), dst_bit, true, false);
+        Ok((updated_board.discard_card(card), None))
+    }
+
+    // Moves the piece on `src` to `dst`, capturing or swapping with whatever is there
+    fn move_or_swap_pieces(&mut self, src: u32, dst: u32, wind_spirit_moving: bool, reveal_ninja: bool) {
+        if wind_spirit_moving {
+            // The Wind Spirit swaps places with everything on `dst`, revealing any Ninja
+            for player in 0..2 {
+                if self.pawns[player] & dst != 0 {
+                    self.pawns[player] = (self.pawns[player] & !dst) | src;
+                }
+                if self.ninjas[player] & dst != 0 {
+                    self.ninjas[player] = (self.ninjas[player] & !dst) | src;
+                    self.revealed_ninjas[player] = (self.revealed_ninjas[player] & !dst) | src;
+                }
+            }
+            self.wind_spirit = dst;
+            return;
+        }
+
+        let player = side(self.turn);
+        let opponent = 1 - player;
+        // In Way of the Light Ninjas only show themselves by capturing
+        let reveal_ninja = reveal_ninja && !self.light_mode;
+        let mover_is_king = self.kings[player] & src != 0;
+        let mover_is_pawn = self.pawns[player] & src != 0;
+        let mover_is_ninja = self.ninjas[player] & src != 0;
+        let mover_is_hidden_ninja = mover_is_ninja && !reveal_ninja && self.revealed_ninjas[player] & src == 0;
+        let mut captured_something = false;
+
+        // Capture whatever the opponent has on `dst`
+        if self.pawns[opponent] & dst != 0 {
+            self.pawns[opponent] &= !dst;
+            captured_something = true;
+        } else if self.ninjas[opponent] & dst != 0 {
+            let occupant_revealed = self.revealed_ninjas[opponent] & dst != 0;
+            if mover_is_hidden_ninja && !occupant_revealed {
+                // Hidden ninja vs hidden ninja -> no capture or reveal
+            } else {
+                self.ninjas[opponent] &= !dst;
+                self.revealed_ninjas[opponent] &= !dst;
+                captured_something = true;
+            }
         }
-        if Some(dst) == *red_king || Some(dst) == *blue_king {
-            return Err("Wind Spirit cannot move onto a Master!".to_string());
+
+        // Always move the piece
+        if mover_is_king {
+            self.kings[player] = dst;
+        }
+        if mover_is_pawn {
+            self.pawns[player] = (self.pawns[player] & !src) | dst;
+        }
+        if mover_is_ninja {
+            self.ninjas[player] = (self.ninjas[player] & !src) | dst;
+            self.revealed_ninjas[player] &= !src;
+            if captured_something || reveal_ninja {
+                self.revealed_ninjas[player] |= dst;
+            }
+        }
+
+        // Hide ninjas that didnt perform a revealing action, in Way of the Light only the moving Ninja hides again
+        if !captured_something && !self.light_mode {
+            self.revealed_ninjas[player] &= dst;
         }
-    
-        let player_hand = replace_card(self.player_hand(), card, *spare_card);
-
-        let mut player_pawns = self.player_pawns();
-        let mut opponent_pawns = self.opponent_pawns();
-        let mut player_ninjas = self.player_ninjas();
-        let mut opponent_ninjas = self.opponent_ninjas();
-        move_or_swap_pieces(
-            &mut player_pawns,
-            &mut opponent_pawns,
-            &mut player_ninjas,
-            &mut opponent_ninjas,
-            src,
-            dst,
-            true,
-            false,
-        );
-    
-        let wind_spirit = Some(dst);
-        let player_king = *player_king;
-  
-        // Construct the updated board after the wind move
-        let updated_board = match turn {
-            Player::Red => Board {
-                wind_spirit,
-                blue_king: *blue_king,
-                blue_pawns: opponent_pawns,
-                blue_ninjas: opponent_ninjas,
-                blue_hand: *blue_hand,
-                red_king: player_king,
-                red_pawns: player_pawns,
-                red_ninjas: player_ninjas,
-                red_hand: player_hand,
-                spare_card: card,
-                wind_move_pending: false,
-                wind_move_card: None,
-                shadow_mode: self.shadow_mode,
-                ninja_move_pending: false,
-                ninja_move_card: None,
-                turn: Player::Blue,
-            },
-            Player::Blue => Board {
-                wind_spirit,
-                blue_king: player_king,
-                blue_pawns: player_pawns,
-                blue_ninjas: player_ninjas,
-                blue_hand: player_hand,
-                red_king: *red_king,
-                red_pawns: opponent_pawns,
-                red_ninjas: opponent_ninjas,
-                red_hand: *red_hand,
-                spare_card: card,
-                wind_move_pending: false,
-                wind_move_card: None,
-                shadow_mode: self.shadow_mode,
-                ninja_move_pending: false,
-                ninja_move_card: None,
-                turn: Player::Red,
-            },
-        };
-    
-        Ok(GameState::Playing { board: updated_board })
     }
 
-    pub fn new_with_settings(settings: GameSettings) -> Board { 
-        let mut rng = thread_rng();
-        
+    // Swaps `card` with the spare card and passes the turn
+    fn discard_card(&self, card: Card) -> BitBoard {
+        let player = side(self.turn);
+        let mut updated_board = *self;
+        updated_board.hands[player] = replace_card(&self.hands[player], card, self.spare_card);
+        updated_board.spare_card = card;
+        updated_board.wind_move_pending = false;
+        updated_board.wind_move_card = None;
+        updated_board.ninja_move_pending = false;
+        updated_board.ninja_move_card = None;
+        updated_board.turn = self.turn.invert();
+        updated_board
+    }
+}
+
+impl Board {
+    pub fn try_move(&self, game_move: Move) -> Result<GameState, MoveError> {
+        let (bits, winner) = BitBoard::from(self).try_move(game_move)?;
+        let mut board = *self;
+        bits.write_to(&mut board);
+        Ok(match winner {
+            Some(winner) => GameState::Finished { winner, board },
+            None => GameState::Playing { board },
+        })
+    }
+
+    /// Plays a move from `legal_moves` without validating it, see `BitBoard::apply_move`.
+    ///
+    /// Pieces keep their slots where possible, though a piece restored by `undo` may come back in another slot.
+    pub fn apply_move(&mut self, game_move: Move) -> Undo {
+        let mut bits = BitBoard::from(&*self);
+        let undo = bits.apply_move(game_move);
+        bits.write_to(self);
+        undo
+    }
+
+    pub fn undo(&mut self, undo: Undo) {
+        undo.previous.write_to(self);
+    }
+
+    /// Sets up a board with randomly placed Ninjas, the setup is reproducible if the settings include a seed
+    pub fn new_with_settings(settings: GameSettings) -> Board {
+        match settings.seed {
+            Some(seed) => Board::new_with_settings_rng(settings, &mut StdRng::seed_from_u64(seed)),
+            None => Board::new_with_settings_rng(settings, &mut thread_rng()),
+        }
+    }
+
+    /// Sets up a board using `rng` for every random choice, ignoring the seed in the settings.
+    ///
+    /// Ninjas are placed at random, use `GameState::new_with_settings_rng` to let players place them.
+    pub fn new_with_settings_rng<R: Rng>(settings: GameSettings, rng: &mut R) -> Board {
+        let mut state = GameState::new_with_settings_rng(settings, rng);
+        for player in [Player::Red, Player::Blue] {
+            for placement in state.random_ninja_placements(player, rng) {
+                state = state.try_move(placement).expect("Random placements are valid");
+            }
+        }
+        state.board()
+    }
+
+    /// Board before any Ninja is placed, along with how many Red and Blue have to place
+    fn new_unplaced<R: Rng>(settings: GameSettings, rng: &mut R) -> (Board, u8, u8) {
         // Determine if the Light and Shadow expansion should be used
         let include_light_and_shadow = settings.force_light_and_shadow
             || (settings.enable_light_and_shadow && rng.gen_bool(0.05));
@@ -450,8 +446,8 @@ impl Board {
             0
         };
 
-        way_of_the_wind_cards.shuffle(&mut rng);
-        other_cards.shuffle(&mut rng);
+        way_of_the_wind_cards.shuffle(rng);
+        other_cards.shuffle(rng);
 
         // Distribute cards
         let (player_hand_red, player_hand_blue, spare_card) = match num_wind_cards {
@@ -522,139 +518,97 @@ impl Board {
             wind_move_pending: false,
             wind_move_card: None,
             shadow_mode: false,
+            light_mode: false,
             ninja_move_pending: false,
             ninja_move_card: None,
             turn: Player::Red,
         };
 
-        // Overwrite board for Light and Shadow modes
+        // Overwrite board for Light and Shadow modes, Ninjas are placed by the players afterwards
         if selected_mode == "Light" {
+            board.light_mode = true;
             board.blue_pawns = [None, None, None, None]; // Shadow Master has no Pawns
             board.blue_king = None; // Shadow Master has no King
-
-            // Randomize Ninja placement until Player placement is implemented
-            let mut x_positions = vec![0, 1, 2, 3, 4];
-            x_positions.shuffle(&mut rng);
-
-            board.blue_ninjas = [
-                Some((Point { x: x_positions[0], y: 0 }, false)),
-                Some((Point { x: x_positions[1], y: 0 }, false)),
-            ];
+            return (board, 0, 2); // Two Ninjas anywhere on Blue's home row
         } else if selected_mode == "Shadow" {
             board.shadow_mode = true;    
 
-            // Randomize Ninja placement until Player placement is implemented
-            let red_ninja_x = if rng.gen_bool(0.5) { 1 } else { 3 };
-            let blue_ninja_x = if rng.gen_bool(0.5) { 1 } else { 3 };
-
+            // Ninjas start on one of the two squares left between the Pawns and the Master
             board.red_pawns = [None, None, Some(Point { x: 0, y: 4 }), Some(Point { x: 4, y: 4 })];
-            board.red_ninjas = [Some((Point { x: red_ninja_x, y: 4 }, false)), None]; // One Ninja for Red
             board.blue_pawns = [None, None, Some(Point { x: 0, y: 0 }), Some(Point { x: 4, y: 0 })];
-            board.blue_ninjas = [Some((Point { x: blue_ninja_x, y: 0 }, false)), None]; // One Ninja for Blue
+            return (board, 1, 1); // One Ninja each
         }
 
-        return board;
+        return (board, 0, 0);
 
     }
 
-    pub fn new() -> Board {
-        let settings = GameSettings::default();
-        Board::new_with_settings(settings)
+    /// Squares `player` may place a Ninja on during setup, which are the empty squares of their home row
+    pub fn ninja_placements(&self, player: Player) -> Vec<Point> {
+        let y = match player {
+            Player::Red => 4,
+            Player::Blue => 0,
+        };
+        let grid = self.to_grid();
+        (0..5)
+            .map(|x| Point { x, y })
+            .filter(|point| matches!(grid[point.y as usize][point.x as usize], GameSquare::Empty))
+            .collect()
     }
 
-    pub fn can_move(&self) -> bool {
-        // If a Ninja move is pending, only the Ninja can move
-        if self.ninja_move_pending {
-            if let Some(ninja_card) = self.ninja_move_card {
-                let player_ninjas = self.player_ninjas();
-                for (ninja_pos, _) in player_ninjas.iter().filter_map(|&ninja| ninja) {
-                    for &raw_delta in ninja_card.moves(false, false).iter() {
-                        let delta = match self.turn {
-                            Player::Red => raw_delta,
-                            Player::Blue => -raw_delta,
-                        };
-                        let dst = ninja_pos + delta;
-
-                        if dst.in_bounds()
-                            && !self.player_pieces().contains(&Some(dst))
-                        {
-                            return true;
-                        }
-                    }
-                }
+    fn place_ninja(&mut self, player: Player, dst: Point) {
+        let ninjas = match player {
+            Player::Red => &mut self.red_ninjas,
+            Player::Blue => &mut self.blue_ninjas,
+        };
+        let slot = ninjas
+            .iter_mut()
+            .find(|ninja| ninja.is_none())
+            .expect("No free Ninja slot");
+        *slot = Some((dst, false));
+    }
+
+    /// The board as `player` sees it, without the opponent's hidden Ninjas
+    pub fn view_for(&self, player: Player) -> Board {
+        let mut board = *self;
+        let ninjas = match player {
+            Player::Red => &mut board.blue_ninjas,
+            Player::Blue => &mut board.red_ninjas,
+        };
+        for ninja in ninjas.iter_mut() {
+            if let Some((_, false)) = ninja {
+                *ninja = None;
             }
-            return false; // No valid moves for Ninjas
         }
+        board
+    }
 
-        // If a wind move is pending, only the Wind Spirit can move using the wind_move_card
-        if self.wind_move_pending {
-            if let Some(wind_spirit_pos) = self.wind_spirit() {
-                if let Some(wind_card) = self.wind_move_card {
-                    for &raw_delta in wind_card.moves(false, true).iter() {
-                        let delta = match self.turn {
-                            Player::Red => raw_delta,
-                            Player::Blue => -raw_delta,
-                        };
-                        let dst = wind_spirit_pos + delta;
-
-                        if dst.in_bounds()
-                            && ![self.red_king, self.blue_king].contains(&Some(dst))
-                        {
-                            return true;
-                        }
-                    }
-                }
-            }
-            return false; // No valid moves for Wind Spirit
+    /// What `player` sees of `game_move` played on this board, the opponent's hidden Ninjas move unseen
+    pub fn move_view_for(&self, game_move: Move, player: Player) -> Option<Move> {
+        let (src, dst) = match game_move {
+            Move::Move { src, dst, .. } if self.turn != player => (src, dst),
+            Move::PlaceNinja { player: owner, .. } if owner != player => return None,
+            _ => return Some(game_move),
+        };
+        let bits = BitBoard::from(self);
+        let mover = side(self.turn);
+        if bits.ninjas[mover] & bit(src) == 0 {
+            return Some(game_move);
         }
+        // Only a Ninja that was in sight before and after the move can be followed
+        let (after, _) = bits.try_move(game_move).ok()?;
+        let seen = bits.revealed_ninjas[mover] & bit(src) != 0 && after.revealed_ninjas[mover] & bit(dst) != 0;
+        seen.then_some(game_move)
+    }
 
-        // Normal move check
-        let player_pieces = self.player_pieces();
-        let kings = [self.red_king, self.blue_king];
-
-        for src in player_pieces.iter().filter_map(|&p| p) {
-            for &card in self.player_hand() {
-                let is_king = self.player_king() == Some(src);
-                let is_wind_spirit = self.wind_spirit() == Some(src);
-                let is_ninja = self.is_ninja(src);
-
-                // Skip Ninja moves during shadow_mode
-                if self.shadow_mode && is_ninja {
-                    continue;
-                }
-
-                // Wind Spirit cannot use WayOfTheWind card for normal moves
-                if is_wind_spirit && CardSet::WayOfTheWind.cards().contains(&card) {
-                    continue;
-                }
 
-                for &raw_delta in card.moves(is_king, false).iter() {
-                    let delta = match self.turn {
-                        Player::Red => raw_delta,
-                        Player::Blue => -raw_delta,
-                    };
-                    let dst = src + delta;
-
-                    if dst.in_bounds() {
-                        // Can't move onto the Wind Spirit
-                        if let Some(ws) = self.wind_spirit() {
-                            if dst == ws {
-                                continue;
-                            }
-                        }
-                        // Can't move onto your own piece, unless Wind Spirit is swapping
-                        if !player_pieces.contains(&Some(dst)) || is_wind_spirit {
-                            // Wind Spirit can't move onto a Master
-                            if !(is_wind_spirit && kings.contains(&Some(dst))) {
-                                return true;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    pub fn new() -> Board {
+        let settings = GameSettings::default();
+        Board::new_with_settings(settings)
+    }
 
-        false // No valid moves found
+    pub fn can_move(&self) -> bool {
+        BitBoard::from(self).can_move()
     }
 
     pub fn to_grid(&self) -> [[GameSquare; 5]; 5] {
@@ -771,136 +725,120 @@ impl Board {
     pub fn wind_spirit(&self) -> Option<Point> {
         self.wind_spirit
     }
+}
 
-    fn has_remaining_pieces(&self, player: Player) -> bool {
-        match player {
-            Player::Red => {
-                self.red_king.is_some()
-                    || self.red_pawns.iter().any(Option::is_some)
-                    || self.red_ninjas.iter().any(Option::is_some)
-            }
-            Player::Blue => {
-                self.blue_king.is_some()
-                    || self.blue_pawns.iter().any(Option::is_some)
-                    || self.blue_ninjas.iter().any(Option::is_some)
-            }
-        }
+impl GameState {
+    pub fn new() -> GameState {
+        log::info!("GameState::new() called");
+        GameState::new_with_settings(GameSettings::default())
     }
 
-    fn enable_wind_move(&self, card: Card, moved_src: Point, moved_dst: Point) -> bool {
-        if !CardSet::WayOfTheWind.cards().contains(&card) {
-            return false;
-        }
-
-        let mut temp_board = self.clone();
-        for pawn in temp_board.player_pawns().iter_mut().chain(temp_board.opponent_pawns().iter_mut()) {
-            if let Some(pos) = pawn {
-                if *pos == moved_src {
-                    *pos = moved_dst;
-                }
-            }
-        }
-
-        // Update the King if it moved
-        if temp_board.player_king() == Some(moved_src) {
-            if self.turn == Player::Red {
-                temp_board.red_king = Some(moved_dst);
-            } else {
-                temp_board.blue_king = Some(moved_dst);
-            }
+    /// Starts a game, in `Setup` if the players have Ninjas to place
+    pub fn new_with_settings(settings: GameSettings) -> GameState {
+        log::info!("GameState::new_with_settings() called with settings: {:?}", settings);
+        match settings.seed {
+            Some(seed) => GameState::new_with_settings_rng(settings, &mut StdRng::seed_from_u64(seed)),
+            None => GameState::new_with_settings_rng(settings, &mut thread_rng()),
         }
-
-        temp_board.wind_move_pending = true;
-        temp_board.wind_move_card = Some(card);
-        temp_board.can_move()
     }
 
-    fn enable_ninja_move(&self, card: Card, moved_src: Point, moved_dst: Point) -> bool {
-    if !self.shadow_mode || !self.has_ninja() {
-        return false; // Only enable Ninja move in Shadow mode with Ninjas present
+    pub fn new_with_settings_rng<R: Rng>(settings: GameSettings, rng: &mut R) -> GameState {
+        let (board, red_ninjas_to_place, blue_ninjas_to_place) = Board::new_unplaced(settings, rng);
+        GameState::setup(board, red_ninjas_to_place, blue_ninjas_to_place)
     }
 
-    // Clone the board to simulate the state after the move
-    let mut temp_board = self.clone();
-    for pawn in temp_board.player_pawns().iter_mut().chain(temp_board.opponent_pawns().iter_mut()) {
-        if let Some(pos) = pawn {
-            if *pos == moved_src {
-                *pos = moved_dst; // Update the pawn's position
-            }
+    fn setup(board: Board, red_ninjas_to_place: u8, blue_ninjas_to_place: u8) -> GameState {
+        if red_ninjas_to_place == 0 && blue_ninjas_to_place == 0 {
+            return GameState::Playing { board };
+        }
+        GameState::Setup {
+            board,
+            red_ninjas_to_place,
+            blue_ninjas_to_place,
         }
     }
 
-    // Update the King if it moved
-    if temp_board.player_king() == Some(moved_src) {
-        if self.turn == Player::Red {
-            temp_board.red_king = Some(moved_dst);
-        } else {
-            temp_board.blue_king = Some(moved_dst);
+    /// Number of Ninjas `player` still has to place, zero once the game is past setup
+    pub fn ninjas_to_place(&self, player: Player) -> u8 {
+        match (self, player) {
+            (GameState::Setup { red_ninjas_to_place, .. }, Player::Red) => *red_ninjas_to_place,
+            (GameState::Setup { blue_ninjas_to_place, .. }, Player::Blue) => *blue_ninjas_to_place,
+            _ => 0,
         }
     }
 
-    // Temporarily set ninja_move_pending and ninja_move_card
-    temp_board.ninja_move_pending = true;
-    temp_board.ninja_move_card = Some(card);
-
-    // Check if a Ninja can move with the given card
-    temp_board.can_move()
-}
-
-    // Helper that discards a card
-    fn discard_card(&self, card: Card) -> Result<GameState, String> {
-        let player_hand = replace_card(self.player_hand(), card, self.spare_card);
-        let (red_hand, blue_hand) = match self.turn {
-            Player::Red => (player_hand, self.blue_hand),
-            Player::Blue => (self.red_hand, player_hand),
-        };
+    /// Placements for all of `player`'s remaining Ninjas, chosen at random
+    pub fn random_ninja_placements<R: Rng>(&self, player: Player, rng: &mut R) -> Vec<Move> {
+        let count = self.ninjas_to_place(player) as usize;
+        self.board()
+            .ninja_placements(player)
+            .choose_multiple(rng, count)
+            .map(|&dst| Move::PlaceNinja { player, dst })
+            .collect()
+    }
 
-        Ok(GameState::Playing {
-            board: Board {
-                wind_spirit: self.wind_spirit,
-                blue_king: self.blue_king,
-                blue_pawns: self.blue_pawns,
-                blue_ninjas: self.blue_ninjas,
-                blue_hand,
-                red_king: self.red_king,
-                red_pawns: self.red_pawns,
-                red_ninjas: self.red_ninjas,
-                red_hand,
-                spare_card: card,
-                wind_move_pending: false,
-                wind_move_card: None,
-                shadow_mode: self.shadow_mode,
-                ninja_move_pending: false,
-                ninja_move_card: None,
-                turn: self.turn.invert(),
-            },
-        })
+    /// The state once `player`'s remaining Ninjas are placed at random, for players who can't place their own
+    pub fn with_random_ninjas<R: Rng>(&self, player: Player, rng: &mut R) -> GameState {
+        self.random_ninja_placements(player, rng)
+            .into_iter()
+            .fold(*self, |state, placement| {
+                state.try_move(placement).expect("Random placements are valid")
+            })
     }
-}
 
-impl GameState {
-    pub fn new() -> GameState {
-        log::info!("GameState::new() called");
-        GameState::Playing {
-            board: Board::new_with_settings(GameSettings::default()),
-        }
+    pub fn finished(&self) -> bool {
+        matches!(self, GameState::Finished { .. })
     }
 
-    pub fn new_with_settings(settings: GameSettings) -> GameState {
-        log::info!("GameState::new_with_settings() called with settings: {:?}", settings);
-        GameState::Playing {
-            board: Board::new_with_settings(settings),
+    /// The state as `player` sees it, nothing is hidden once the game is over
+    pub fn view_for(&self, player: Player) -> GameState {
+        match *self {
+            GameState::Setup {
+                board,
+                red_ninjas_to_place,
+                blue_ninjas_to_place,
+            } => GameState::Setup {
+                board: board.view_for(player),
+                red_ninjas_to_place,
+                blue_ninjas_to_place,
+            },
+            GameState::Playing { board } => GameState::Playing {
+                board: board.view_for(player),
+            },
+            GameState::Finished { .. } => *self,
         }
     }
 
-    pub fn finished(&self) -> bool {
-        matches!(self, GameState::Finished { .. })
+    pub fn board(&self) -> Board {
+        match self {
+            GameState::Setup { board, .. } | GameState::Playing { board } | GameState::Finished { board, .. } => *board,
+        }
     }
 
-    pub fn try_move(&self, game_move: Move) -> Result<GameState, String> {
+    pub fn try_move(&self, game_move: Move) -> Result<GameState, MoveError> {
         match self {
+            GameState::Setup { board, .. } => {
+                let (player, dst) = match game_move {
+                    Move::PlaceNinja { player, dst } => (player, dst),
+                    _ => return Err(MoveError::SetupInProgress),
+                };
+                if self.ninjas_to_place(player) == 0 {
+                    return Err(MoveError::NoNinjaToPlace { player });
+                }
+                if !board.ninja_placements(player).contains(&dst) {
+                    return Err(MoveError::InvalidNinjaPlacement { dst });
+                }
+                let mut board = *board;
+                board.place_ninja(player, dst);
+                let (mut red, mut blue) = (self.ninjas_to_place(Player::Red), self.ninjas_to_place(Player::Blue));
+                match player {
+                    Player::Red => red -= 1,
+                    Player::Blue => blue -= 1,
+                }
+                Ok(GameState::setup(board, red, blue))
+            }
             GameState::Playing { board } => board.try_move(game_move),
-            GameState::Finished { .. } => Err("Game already finished".to_string()),
+            GameState::Finished { .. } => Err(MoveError::GameFinished),
         }
     }
 }
@@ -912,140 +850,3 @@ fn replace_card(hand: &[Card; 2], used: Card, spare: Card) -> [Card; 2] {
         if *c2 == used { spare } else { *c2 },
     ]
 }
-
-fn out_of_bounds(point: Point) -> bool {
-    point.x < 0 || point.x > 4 || point.y < 0 || point.y > 4
-}
-
-fn move_or_swap_pieces(
-    player_pawns: &mut [Option<Point>; 4],
-    opponent_pawns: &mut [Option<Point>; 4],
-    player_ninjas: &mut [Option<(Point, bool)>; 2],
-    opponent_ninjas: &mut [Option<(Point, bool)>; 2],
-    src: Point,
-    dst: Point,
-    wind_spirit_moving: bool,
-    reveal_ninja:bool,
-) {
-    struct Occupant {
-        is_player_side: bool,
-        is_ninja: bool,
-        index: usize,
-        revealed: bool,
-    }
-
-    // Identify the occupant at `dst`
-    let occupant = player_pawns
-        .iter()
-        .enumerate()
-        .find(|(_, pawn)| pawn.map_or(false, |p| p == dst))
-        .map(|(i, _)| Occupant {
-            is_player_side: true,
-            is_ninja: false,
-            index: i,
-            revealed: false,
-        })
-        .or_else(|| {
-            opponent_pawns
-                .iter()
-                .enumerate()
-                .find(|(_, pawn)| pawn.map_or(false, |p| p == dst))
-                .map(|(i, _)| Occupant {
-                    is_player_side: false,
-                    is_ninja: false,
-                    index: i,
-                    revealed: false,
-                })
-        })
-        .or_else(|| {
-            player_ninjas
-                .iter()
-                .enumerate()
-                .find(|(_, ninja)| ninja.map_or(false, |(pos, _)| pos == dst))
-                .map(|(i, ninja)| Occupant {
-                    is_player_side: true,
-                    is_ninja: true,
-                    index: i,
-                    revealed: ninja.unwrap().1,
-                })
-        })
-        .or_else(|| {
-            opponent_ninjas
-                .iter()
-                .enumerate()
-                .find(|(_, ninja)| ninja.map_or(false, |(pos, _)| pos == dst))
-                .map(|(i, ninja)| Occupant {
-                    is_player_side: false,
-                    is_ninja: true,
-                    index: i,
-                    revealed: ninja.unwrap().1,
-                })
-        });
-
-    // Extract mover information
-    let mover_is_pawn = player_pawns.iter().position(|pawn| pawn.map_or(false, |p| p == src));
-    let mover_is_ninja = player_ninjas
-        .iter()
-        .enumerate()
-        .find(|(_, ninja)| ninja.map_or(false, |(pos, _)| pos == src))
-        .map(|(i, ninja)| (i, ninja.unwrap().1 || reveal_ninja)); // Extract index and revealed state
-
-    let mut captured_something = false;
-
-    // Process interactions
-    if let Some(occ) = occupant {
-        if !occ.is_player_side {
-            if wind_spirit_moving {
-                if occ.is_ninja {
-                    opponent_ninjas[occ.index] = Some((src, true)); // Reveal enemy ninja
-                } else {
-                    opponent_pawns[occ.index] = Some(src);
-                }
-            } else {
-                let mover_is_hidden_ninja = mover_is_ninja.map_or(false, |(_, revealed)| !revealed);
-                if occ.is_ninja {
-                    if mover_is_hidden_ninja && !occ.revealed {
-                        // Hidden ninja vs hidden ninja -> no capture or reveal
-                    } else {
-                        // Capture enemy ninja
-                        opponent_ninjas[occ.index] = None;
-                        captured_something = true;
-                    }
-                } else {
-                    // Capture enemy pawn
-                    opponent_pawns[occ.index] = None;
-                    captured_something = true;
-                }
-            }
-        } else if wind_spirit_moving {
-            if occ.is_ninja {
-                player_ninjas[occ.index] = Some((src, true)); // Reveal own ninja
-            } else {
-                player_pawns[occ.index] = Some(src);
-            }
-        }
-    }
-
-    // Always move the piece
-    if let Some(i) = mover_is_pawn {
-        player_pawns[i] = Some(dst);
-    }
-    if let Some((i, was_revealed)) = mover_is_ninja {
-        let new_revealed_state = captured_something || wind_spirit_moving || was_revealed || reveal_ninja;
-        player_ninjas[i] = Some((dst, new_revealed_state));
-
-        // Explicitly hide the mover Ninja if it didn't capture or swap and wasn't revealed on purpose
-        if !captured_something && !wind_spirit_moving && !reveal_ninja {
-            player_ninjas[i] = Some((dst, false));
-        }
-    }
-
-    // Hide ninjas that didnt perform a revealing action
-    for ninja in player_ninjas.iter_mut() {
-        if let Some((pos, rev)) = ninja {
-            if *rev && *pos != dst && !captured_something && !wind_spirit_moving {
-                *ninja = Some((*pos, false)); // Hide the ninja
-            }
-        }
-    }
-}
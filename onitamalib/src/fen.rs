@@ -0,0 +1,410 @@
+//! Single line position format, modelled on chess FEN.
+//!
+//! `ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - -` has seven space separated fields:
+//!
+//! 1. The grid, ranks 5 to 1 separated by `/` so Blue's side comes first. Red pieces are upper case
+//!    and Blue pieces lower case: `K` Master, `P` Pawn, `N` Ninja with `N*` once revealed, and `W` for
+//!    the Wind Spirit. Digits count empty squares, pieces sharing a square are grouped as `(Nn)`.
+//! 2. Red's hand, then 3. Blue's hand, as two comma separated cards.
+//! 4. The spare card.
+//! 5. The side to move, `r` or `b`.
+//! 6. Pending follow up moves, `-` or a comma separated list of `n:<card>` and `w:<card>`.
+//! 7. `shadow` in Way of the Shadow games, `light` in Way of the Light games, otherwise `-`.
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::models::{Board, Card, Player, Point};
+
+const FIELDS: usize = 7;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FenError {
+    FieldCount(usize),
+    InvalidGrid(String),
+    UnknownPiece(char),
+    UnknownCard(String),
+    DuplicateCard(Card),
+    InvalidHand(String),
+    InvalidTurn(String),
+    InvalidPending(String),
+    InvalidMode(String),
+    TooManyPieces(String),
+    SharedSquare(Point),
+    MissingMaster(Player),
+    PendingCardNotInHand(Card),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(count) => write!(f, "Expected {} fields, found {}", FIELDS, count),
+            FenError::InvalidGrid(grid) => write!(f, "Invalid grid '{}'", grid),
+            FenError::UnknownPiece(piece) => write!(f, "Unknown piece '{}'", piece),
+            FenError::UnknownCard(card) => write!(f, "Unknown card '{}'", card),
+            FenError::DuplicateCard(card) => write!(f, "{} is used more than once", card),
+            FenError::InvalidHand(hand) => write!(f, "A hand must hold two cards, found '{}'", hand),
+            FenError::InvalidTurn(turn) => write!(f, "Invalid side to move '{}'", turn),
+            FenError::InvalidPending(pending) => write!(f, "Invalid pending moves '{}'", pending),
+            FenError::InvalidMode(mode) => write!(f, "Invalid mode '{}'", mode),
+            FenError::TooManyPieces(pieces) => write!(f, "Too many pieces: {}", pieces),
+            FenError::SharedSquare(pos) => write!(f, "Pieces cannot share {}", pos),
+            FenError::MissingMaster(player) => write!(f, "{} Master is missing", player),
+            FenError::PendingCardNotInHand(card) => {
+                write!(f, "Pending move card {} is not in the mover's hand", card)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Piece {
+    King,
+    Pawn,
+    Ninja { revealed: bool },
+    WindSpirit,
+}
+
+// Everything on one square, the Wind Spirit belongs to neither player
+type Square = Vec<(Option<Player>, Piece)>;
+
+impl Board {
+    pub fn to_fen(&self) -> String {
+        let grid: Vec<String> = (0..5).map(|y| self.fen_rank(y)).collect();
+        let hand = |hand: &[Card; 2]| format!("{},{}", hand[0], hand[1]);
+        let turn = match self.turn {
+            Player::Red => "r",
+            Player::Blue => "b",
+        };
+        let mut pending = vec![];
+        if let (true, Some(card)) = (self.ninja_move_pending, self.ninja_move_card) {
+            pending.push(format!("n:{}", card));
+        }
+        if let (true, Some(card)) = (self.wind_move_pending, self.wind_move_card) {
+            pending.push(format!("w:{}", card));
+        }
+        let pending = match pending.is_empty() {
+            true => "-".to_string(),
+            false => pending.join(","),
+        };
+        let mode = match (self.shadow_mode, self.light_mode) {
+            (true, _) => "shadow",
+            (false, true) => "light",
+            (false, false) => "-",
+        };
+        format!(
+            "{} {} {} {} {} {} {}",
+            grid.join("/"),
+            hand(&self.red_hand),
+            hand(&self.blue_hand),
+            self.spare_card,
+            turn,
+            pending,
+            mode
+        )
+    }
+
+    fn fen_rank(&self, y: i8) -> String {
+        let mut rank = String::new();
+        let mut empty = 0;
+        for x in 0..5 {
+            let pieces: Vec<String> = self
+                .pieces_at(Point { x, y })
+                .into_iter()
+                .map(|(player, piece)| piece_symbol(player, piece))
+                .collect();
+            if pieces.is_empty() {
+                empty += 1;
+                continue;
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+                empty = 0;
+            }
+            match pieces.len() {
+                1 => rank.push_str(&pieces[0]),
+                _ => rank.push_str(&format!("({})", pieces.concat())),
+            }
+        }
+        if empty > 0 {
+            rank.push_str(&empty.to_string());
+        }
+        rank
+    }
+
+    fn pieces_at(&self, pos: Point) -> Square {
+        let mut pieces = vec![];
+        if self.wind_spirit == Some(pos) {
+            pieces.push((None, Piece::WindSpirit));
+        }
+        let sides = [
+            (Player::Red, self.red_king, &self.red_pawns, &self.red_ninjas),
+            (Player::Blue, self.blue_king, &self.blue_pawns, &self.blue_ninjas),
+        ];
+        for (player, king, pawns, ninjas) in sides {
+            if king == Some(pos) {
+                pieces.push((Some(player), Piece::King));
+            }
+            for _ in pawns.iter().filter(|pawn| **pawn == Some(pos)) {
+                pieces.push((Some(player), Piece::Pawn));
+            }
+            for (_, revealed) in ninjas.iter().flatten().filter(|(ninja, _)| *ninja == pos) {
+                pieces.push((Some(player), Piece::Ninja { revealed: *revealed }));
+            }
+        }
+        pieces
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let (grid, red_hand, blue_hand, spare, turn, pending, mode) = match fields[..] {
+            [grid, red_hand, blue_hand, spare, turn, pending, mode] => {
+                (grid, red_hand, blue_hand, spare, turn, pending, mode)
+            }
+            _ => return Err(FenError::FieldCount(fields.len())),
+        };
+
+        let mut board = Board {
+            wind_spirit: None,
+            blue_king: None,
+            blue_pawns: [None; 4],
+            blue_ninjas: [None; 2],
+            blue_hand: parse_hand(blue_hand)?,
+            red_king: None,
+            red_pawns: [None; 4],
+            red_ninjas: [None; 2],
+            red_hand: parse_hand(red_hand)?,
+            spare_card: parse_card(spare)?,
+            wind_move_pending: false,
+            wind_move_card: None,
+            shadow_mode: false,
+            light_mode: false,
+            ninja_move_pending: false,
+            ninja_move_card: None,
+            turn: match turn {
+                "r" => Player::Red,
+                "b" => Player::Blue,
+                _ => return Err(FenError::InvalidTurn(turn.to_string())),
+            },
+        };
+
+        (board.shadow_mode, board.light_mode) = match mode {
+            "shadow" => (true, false),
+            "light" => (false, true),
+            "-" => (false, false),
+            _ => return Err(FenError::InvalidMode(mode.to_string())),
+        };
+
+        let mut cards = HashSet::new();
+        for card in board.red_hand.iter().chain(board.blue_hand.iter()) {
+            if !cards.insert(card.index()) {
+                return Err(FenError::DuplicateCard(*card));
+            }
+        }
+        if !cards.insert(board.spare_card.index()) {
+            return Err(FenError::DuplicateCard(board.spare_card));
+        }
+
+        let ranks: Vec<&str> = grid.split('/').collect();
+        if ranks.len() != 5 {
+            return Err(FenError::InvalidGrid(grid.to_string()));
+        }
+        for (y, rank) in ranks.iter().enumerate() {
+            let squares = parse_rank(rank)?;
+            if squares.len() != 5 {
+                return Err(FenError::InvalidGrid(grid.to_string()));
+            }
+            for (x, pieces) in squares.into_iter().enumerate() {
+                let pos = Point { x: x as i8, y: y as i8 };
+                board.place_pieces(pos, pieces)?;
+            }
+        }
+        // The Shadow Master of Way of the Light plays without one
+        if board.red_king.is_none() {
+            return Err(FenError::MissingMaster(Player::Red));
+        }
+        if board.blue_king.is_none() && !board.light_mode {
+            return Err(FenError::MissingMaster(Player::Blue));
+        }
+
+        if pending != "-" {
+            for follow_up in pending.split(',') {
+                let invalid = || FenError::InvalidPending(pending.to_string());
+                let (kind, card) = follow_up.split_once(':').ok_or_else(invalid)?;
+                let card = parse_card(card)?;
+                if !board.player_hand().contains(&card) {
+                    return Err(FenError::PendingCardNotInHand(card));
+                }
+                let has_ninja = match board.turn {
+                    Player::Red => board.red_ninjas.iter().any(Option::is_some),
+                    Player::Blue => board.blue_ninjas.iter().any(Option::is_some),
+                };
+                match kind {
+                    "n" if !board.ninja_move_pending && board.shadow_mode && has_ninja => {
+                        board.ninja_move_pending = true;
+                        board.ninja_move_card = Some(card);
+                    }
+                    "w" if !board.wind_move_pending && board.wind_spirit.is_some() => {
+                        board.wind_move_pending = true;
+                        board.wind_move_card = Some(card);
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+        }
+
+        Ok(board)
+    }
+
+    fn place_pieces(&mut self, pos: Point, pieces: Square) -> Result<(), FenError> {
+        // Only a hidden Ninja ever shares a square, and only with a piece of the other side
+        let hidden = |piece| piece == Piece::Ninja { revealed: false };
+        let shared_with_hidden_ninja = match pieces[..] {
+            [(Some(first), first_piece), (Some(second), second_piece)] => {
+                first != second && (hidden(first_piece) || hidden(second_piece))
+            }
+            _ => false,
+        };
+        if pieces.len() > 1 && !shared_with_hidden_ninja {
+            return Err(FenError::SharedSquare(pos));
+        }
+        for (player, piece) in pieces {
+            let too_many = |name: &str| FenError::TooManyPieces(name.to_string());
+            match (player, piece) {
+                (None, Piece::WindSpirit) => match self.wind_spirit {
+                    None => self.wind_spirit = Some(pos),
+                    Some(_) => return Err(too_many("Wind Spirit")),
+                },
+                (Some(player), Piece::King) => {
+                    let king = match player {
+                        Player::Red => &mut self.red_king,
+                        Player::Blue => &mut self.blue_king,
+                    };
+                    match king {
+                        None => *king = Some(pos),
+                        Some(_) => return Err(too_many(&format!("{} Master", player))),
+                    }
+                }
+                (Some(player), Piece::Pawn) => {
+                    let pawns = match player {
+                        Player::Red => &mut self.red_pawns,
+                        Player::Blue => &mut self.blue_pawns,
+                    };
+                    match pawns.iter_mut().find(|pawn| pawn.is_none()) {
+                        Some(slot) => *slot = Some(pos),
+                        None => return Err(too_many(&format!("{} Pawns", player))),
+                    }
+                }
+                (Some(player), Piece::Ninja { revealed }) => {
+                    let ninjas = match player {
+                        Player::Red => &mut self.red_ninjas,
+                        Player::Blue => &mut self.blue_ninjas,
+                    };
+                    match ninjas.iter_mut().find(|ninja| ninja.is_none()) {
+                        Some(slot) => *slot = Some((pos, revealed)),
+                        None => return Err(too_many(&format!("{} Ninjas", player))),
+                    }
+                }
+                _ => unreachable!("the Wind Spirit belongs to neither player"),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn piece_symbol(player: Option<Player>, piece: Piece) -> String {
+    let symbol = match piece {
+        Piece::King => "K",
+        Piece::Pawn => "P",
+        Piece::Ninja { revealed: false } => "N",
+        Piece::Ninja { revealed: true } => "N*",
+        Piece::WindSpirit => "W",
+    };
+    match player {
+        Some(Player::Blue) => symbol.to_lowercase(),
+        _ => symbol.to_string(),
+    }
+}
+
+fn parse_piece(symbol: char) -> Result<(Option<Player>, Piece), FenError> {
+    let piece = match symbol.to_ascii_uppercase() {
+        'K' => Piece::King,
+        'P' => Piece::Pawn,
+        'N' => Piece::Ninja { revealed: false },
+        'W' if symbol == 'W' => return Ok((None, Piece::WindSpirit)),
+        _ => return Err(FenError::UnknownPiece(symbol)),
+    };
+    let player = match symbol.is_ascii_uppercase() {
+        true => Player::Red,
+        false => Player::Blue,
+    };
+    Ok((Some(player), piece))
+}
+
+// Reads a rank into the pieces on each of its squares
+fn parse_rank(rank: &str) -> Result<Vec<Square>, FenError> {
+    let invalid = || FenError::InvalidGrid(rank.to_string());
+    let mut squares: Vec<Square> = vec![];
+    let mut group: Option<Square> = None;
+    for symbol in rank.chars() {
+        let pieces = match &mut group {
+            Some(pieces) => pieces,
+            None => {
+                if let Some(empty) = symbol.to_digit(10) {
+                    if !(1..=5).contains(&empty) {
+                        return Err(invalid());
+                    }
+                    squares.extend((0..empty).map(|_| vec![]));
+                    continue;
+                }
+                match symbol {
+                    '(' => {
+                        group = Some(vec![]);
+                        continue;
+                    }
+                    '*' => {
+                        let last = squares.last_mut().ok_or_else(invalid)?;
+                        reveal_last(last).ok_or_else(invalid)?;
+                        continue;
+                    }
+                    _ => {
+                        squares.push(vec![parse_piece(symbol)?]);
+                        continue;
+                    }
+                }
+            }
+        };
+        match symbol {
+            ')' if pieces.len() < 2 => return Err(invalid()),
+            ')' => squares.push(group.take().unwrap()),
+            '*' => reveal_last(pieces).ok_or_else(invalid)?,
+            _ => pieces.push(parse_piece(symbol)?),
+        }
+    }
+    if group.is_some() {
+        return Err(invalid());
+    }
+    Ok(squares)
+}
+
+fn reveal_last(pieces: &mut [(Option<Player>, Piece)]) -> Option<()> {
+    match pieces.last_mut() {
+        Some((_, Piece::Ninja { revealed })) if !*revealed => {
+            *revealed = true;
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+fn parse_card(card: &str) -> Result<Card, FenError> {
+    card.parse().map_err(|_| FenError::UnknownCard(card.to_string()))
+}
+
+fn parse_hand(hand: &str) -> Result<[Card; 2], FenError> {
+    match hand.split(',').collect::<Vec<_>>()[..] {
+        [first, second] => Ok([parse_card(first)?, parse_card(second)?]),
+        _ => Err(FenError::InvalidHand(hand.to_string())),
+    }
+}
@@ -1,5 +1,7 @@
-use crate::models::{CardSet, GameState, Move, Player, GameSettings};
+use crate::models::{CardSet, GameState, Move, MoveError, Player, GameSettings};
+use crate::record::GameRecord;
 use enum_iterator::IntoEnumIterator;
+use rand::prelude::*;
 use std::collections::HashSet;
 
 #[derive(Clone)]
@@ -7,6 +9,7 @@ pub struct Game {
     state: GameState,
     last_move: Option<Move>,
     settings: GameSettings, 
+    record: GameRecord,
 }
 
 impl Game {
@@ -49,6 +52,7 @@ impl Game {
         let game = Game {
             state,
             last_move: None,
+            record: GameRecord::new(settings.clone(), state.board()),
             settings,
         };
 
@@ -60,27 +64,69 @@ impl Game {
     pub fn reset(&mut self) {
         self.state = GameState::new_with_settings(self.settings.clone());
         self.last_move = None;
+        self.record = GameRecord::new(self.settings.clone(), self.state.board());
     }
 
-    pub fn try_move(&mut self, game_move: Move) -> Result<(), String> {
-        let board = match &self.state {
-            GameState::Playing { board } => board,
-            GameState::Finished { .. } => {
-                return Err("Game Already Finished".to_string());
+    pub fn try_move(&mut self, game_move: Move) -> Result<(), MoveError> {
+        let was_setup = self.is_setup();
+        self.state = self.state.try_move(game_move)?;
+        if was_setup {
+            // Placements stay secret, the record starts from the position they lead to
+            if !self.is_setup() {
+                self.record = GameRecord::new(self.settings.clone(), self.state.board());
             }
-        };
-        self.state = board.try_move(game_move)?;
+            return Ok(());
+        }
         self.last_move = Some(game_move);
+        self.record.push(game_move, &self.state);
         Ok(())
     }
 
+    /// Places `player`'s remaining Ninjas at random, until the board lets players place their own
+    pub fn place_random_ninjas(&mut self, player: Player) {
+        let placements = self.state.random_ninja_placements(player, &mut thread_rng());
+        for placement in placements {
+            if let Err(err) = self.try_move(placement) {
+                log::error!("Failed to place Ninja: {}", err);
+                return;
+            }
+        }
+    }
+
     pub fn get_last_move(&self) -> Option<Move> {
         self.last_move
     }
 
+    /// Replaces the state, the record restarts from the new position
     pub fn set_state(&mut self, state: GameState) {
         self.last_move = None;
         self.state = state;
+        self.record = GameRecord::new(self.settings.clone(), state.board());
+    }
+
+    /// Takes a state from the server, which only shows the moves this player can see.
+    /// Visible moves are added to the record, the server sends the complete one when the game ends
+    pub fn update(&mut self, state: GameState, last_move: Option<Move>) {
+        let was_setup = self.is_setup();
+        self.state = state;
+        self.last_move = last_move;
+        if was_setup {
+            if !self.is_setup() {
+                self.record = GameRecord::new(self.settings.clone(), state.board());
+            }
+            return;
+        }
+        if let Some(game_move) = last_move {
+            self.record.push(game_move, &state);
+        }
+    }
+
+    pub fn get_record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub fn get_record_mut(&mut self) -> &mut GameRecord {
+        &mut self.record
     }
 
     pub fn get_state(&self) -> GameState {
@@ -90,10 +136,18 @@ impl Game {
     pub fn get_turn(&self) -> Option<Player> {
         match &self.state {
             GameState::Playing { board } => Some(board.turn),
-            GameState::Finished { .. } => None,
+            GameState::Setup { .. } | GameState::Finished { .. } => None,
         }
     }
 
+    pub fn is_setup(&self) -> bool {
+        matches!(self.state, GameState::Setup { .. })
+    }
+
+    pub fn ninjas_to_place(&self, player: Player) -> u8 {
+        self.state.ninjas_to_place(player)
+    }
+
     pub fn is_finished(&self) -> bool {
         matches!(self.state, GameState::Finished { .. })
     }
@@ -101,7 +155,7 @@ impl Game {
     pub fn get_winner(&self) -> Option<Player> {
         match self.state {
             GameState::Finished { winner, .. } => Some(winner),
-            GameState::Playing { .. } => None,
+            GameState::Setup { .. } | GameState::Playing { .. } => None,
         }
     }
 }
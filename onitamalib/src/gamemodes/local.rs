@@ -2,7 +2,8 @@ use serde_cbor::ser;
 use wasm_bindgen::prelude::*;
 
 use crate::gamemodes::base::Game;
-use crate::models::{Move, GameSettings, GameMeta};
+use crate::gamemodes::utils::send_move_error;
+use crate::models::{Move, MoveError, GameSettings, GameMeta, Player};
 use crate::{GameEvent, GameView};
 
 #[wasm_bindgen]
@@ -36,7 +37,9 @@ impl LocalGame {
         };
 
         // Use the settings to initialize the game
-        let game = Game::new_with_settings(settings);
+        let mut game = Game::new_with_settings(settings);
+        game.place_random_ninjas(Player::Red);
+        game.place_random_ninjas(Player::Blue);
 
         // Deserialize metadata
         let meta = match serde_wasm_bindgen::from_value::<GameMeta>(meta) {
@@ -66,7 +69,7 @@ impl LocalGame {
 }
 
 impl LocalGame {
-    fn try_move(&mut self, game_move: Move) -> Result<(), String> {
+    fn try_move(&mut self, game_move: Move) -> Result<(), MoveError> {
         self.game.try_move(game_move)?;
         self.send_current_view();
         match self.game.get_winner() {
@@ -139,7 +142,7 @@ impl LocalGame {
                 log::info!("Successfully played move");
             }
             Err(err) => {
-                self.send_error(err);
+                send_move_error(&self.on_send_error, err);
             }
         };
     }
@@ -151,6 +154,14 @@ impl LocalGame {
             meta: self.meta.clone(),
         });
         self.game.reset();
+        self.game.place_random_ninjas(Player::Red);
+        self.game.place_random_ninjas(Player::Blue);
         self.send_current_view();
     }
+
+    /// The record of the game so far in its text form, for saving or sharing
+    #[wasm_bindgen(js_name = record)]
+    pub fn record(&self) -> String {
+        self.game.get_record().to_string()
+    }
 }
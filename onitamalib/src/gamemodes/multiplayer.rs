@@ -5,9 +5,10 @@ use wasm_bindgen::JsCast;
 use web_sys::MessageEvent;
 
 use crate::gamemodes::base::Game;
+use crate::gamemodes::utils::send_move_error;
 use crate::messages::GameMessage;
-use crate::models::{Move, Player};
-use crate::{GameEvent, GameMeta, GameView};
+use crate::models::{GameState, Move, Player};
+use crate::{GameEvent, GameMeta, GameView, SearchReport};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
 pub enum ConnectionState {
@@ -32,6 +33,8 @@ pub struct MultiplayerView {
     #[serde(flatten)]
     game: GameView,
     last_move: Option<Move>,
+    /// What the AI found when choosing its last move, only sent when playing the AI
+    search_report: Option<SearchReport>,
 }
 
 #[wasm_bindgen]
@@ -47,6 +50,7 @@ pub struct MultiplayerGame {
     player: Player,
     room_id: Option<String>,
     error: Option<String>,
+    search_report: Option<SearchReport>,
 }
 
 impl MultiplayerGame {
@@ -59,6 +63,7 @@ impl MultiplayerGame {
             player: self.player,
             error: self.error.clone(),
             last_move: self.game.get_last_move(),
+            search_report: self.search_report.clone(),
         };
         self.send_view(view);
     }
@@ -112,6 +117,7 @@ impl MultiplayerGame {
             conn_state: ConnectionState::Connecting,
             resume_state: ConnectionState::Connecting,
             error: None,
+            search_report: None,
         };
         game.send_current_view();
         return game;
@@ -133,14 +139,22 @@ impl MultiplayerGame {
                 return;
             }
         };
-        match self.try_move(game_move) {
-            Ok(()) => {
-                log::info!("Successfully played move");
+        if let Move::PlaceNinja { player, .. } = game_move {
+            if player != self.player {
+                self.send_error("You can only place your own Ninjas".to_string());
+                return;
+            }
+        }
+        // The view lacks the opponent's hidden Ninjas, so the move is only checked here and the
+        // server sends back the actual outcome
+        match self.game.get_state().try_move(game_move) {
+            Ok(_) => {
+                log::info!("Sending move");
                 let msg = GameMessage::Move { game_move };
                 self.send_msg(msg);
             }
             Err(err) => {
-                self.send_error(err);
+                send_move_error(&self.on_send_error, err);
             }
         };
     }
@@ -151,9 +165,15 @@ impl MultiplayerGame {
         self.send_msg(msg);
         self.send_current_view();
     }
+
+    /// The record of the game so far in its text form, for saving or sharing
+    #[wasm_bindgen(js_name = record)]
+    pub fn record(&self) -> String {
+        self.game.get_record().to_string()
+    }
     fn is_player_turn(&self) -> bool {
         match self.game.get_turn() {
-            None => false,
+            None => self.game.ninjas_to_place(self.player) > 0,
             Some(turn) => turn == self.player,
         }
     }
@@ -202,8 +222,8 @@ impl MultiplayerGame {
             log::info!("Received unexpected msg type: {:?}", &msg);
         }
     }
-    fn try_move(&mut self, game_move: Move) -> Result<(), String> {
-        self.game.try_move(game_move)?;
+    fn update(&mut self, state: GameState, last_move: Option<Move>) {
+        self.game.update(state, last_move);
         if self.game.is_finished() {
             self.conn_state = ConnectionState::Finished;
             let winner = match self.game.get_winner() {
@@ -221,8 +241,6 @@ impl MultiplayerGame {
                 meta: self.meta.clone(),
             })
         }
-        self.send_current_view();
-        Ok(())
     }
     fn handle_game_message(&mut self, msg: GameMessage) {
         log::info!("Message: {:?}", &msg);
@@ -263,20 +281,12 @@ impl MultiplayerGame {
                 log::info!("Player re-joined");
                 self.conn_state = self.resume_state;
             }
-            (ConnectionState::Running, GameMessage::Move { game_move }) => {
-                log::info!("Received move");
-                if self.is_player_turn() {
-                    log::error!("Opponent attempted to play during our turn");
-                    self.send_error("Opponent played out of turn".to_string());
-                    return;
-                }
-                match self.try_move(game_move) {
-                    Ok(()) => {}
-                    Err(err) => {
-                        log::error!("Opponent played illegal move: {}", err);
-                        self.send_error("Opponent played illegal move".to_string());
-                    }
-                }
+            (
+                ConnectionState::Waiting | ConnectionState::Running,
+                GameMessage::Update { state, last_move },
+            ) => {
+                log::info!("Received update");
+                self.update(state, last_move);
             }
             (ConnectionState::Finished, GameMessage::RequestRematch) => {
                 self.conn_state = ConnectionState::OpponentRematchRequested;
@@ -289,6 +299,12 @@ impl MultiplayerGame {
                 self.conn_state = ConnectionState::Errored;
                 self.error = Some(message);
             }
+            (_, GameMessage::SearchReport { report }) => {
+                self.search_report = Some(report);
+            }
+            (_, GameMessage::Record { record }) => {
+                *self.game.get_record_mut() = record;
+            }
             (state, msg) => {
                 log::error!(
                     "Illegal state transition state = {:?}, message = {:?}",
@@ -4,12 +4,14 @@ use serde_cbor::ser;
 use wasm_bindgen::prelude::*;
 
 use crate::gamemodes::base::Game;
-use crate::models::{Move, GameSettings, GameMeta};
-use crate::{AiAgent, Player, GameEvent, GameView, MoveRequest};
+use crate::gamemodes::utils::send_move_error;
+use crate::models::{Move, MoveError, GameSettings, GameMeta};
+use crate::{AiAgent, Player, GameEvent, GameView, MoveRequest, NinjaBelief, SkillLevel};
 
 struct PreviousState {
     game: Game,
     last_move: Option<Move>,
+    belief: NinjaBelief,
 }
 
 #[wasm_bindgen]
@@ -18,7 +20,10 @@ pub struct SinglePlayerGame {
     meta: GameMeta,
     player: Player,
     agent: AiAgent,
+    skill: SkillLevel,
     last_move: Option<Move>,
+    /// What the agent has seen of the player's hidden Ninjas
+    belief: NinjaBelief,
     previous_states: Vec<PreviousState>,
     training_mode: bool,
     on_send_view: js_sys::Function,
@@ -54,7 +59,14 @@ impl SinglePlayerGame {
     ) -> SinglePlayerGame {
         // Determine player color and AI agent based on difficulty
         let is_red: bool = random();
-        let agent = match difficulty {
+        let (name, skill) = match SkillLevel::from_difficulty(difficulty) {
+            Ok(difficulty) => difficulty,
+            Err(e) => {
+                log::error!("Invalid difficulty {}: {}", difficulty, e);
+                ("", SkillLevel::MAX)
+            }
+        };
+        let agent = match name {
             "easy" => AiAgent::PureMonteCarlo,
             "medium" => AiAgent::Alphabeta,
             "hard" => AiAgent::HybridMonteCarlo,
@@ -65,7 +77,7 @@ impl SinglePlayerGame {
         // Log difficulty and player details
         log::info!("Difficulty level: {}", difficulty);
         log::info!("Assigned player color: {:?}", player);
-        log::info!("Assigned agent: {:?}", agent);
+        log::info!("Assigned agent: {:?} at skill level {}", agent, skill.level());
 
         // Deserialize GameSettings from JsValue
         let settings = match serde_wasm_bindgen::from_value::<GameSettings>(game_settings) {
@@ -87,14 +99,18 @@ impl SinglePlayerGame {
             Err(_) => GameMeta::blank(),
         };
 
+        let belief = NinjaBelief::new(&game.get_state().board(), player.invert());
+
         // Construct the SinglePlayerGame
         let mut game = SinglePlayerGame {
+            belief,
             game,
             meta,
             on_send_view,
             on_send_error,
             player,
             agent,
+            skill,
             last_move: None,
             previous_states: vec![],
             request_ai_move,
@@ -117,6 +133,9 @@ impl SinglePlayerGame {
 
 impl SinglePlayerGame {
     fn agent_move(&mut self) {
+        if self.game.is_setup() {
+            self.place_ninjas();
+        }
         if self.game.get_turn() != Some(self.player.invert()) {
             log::info!("Not AI's turn");
             return;
@@ -125,6 +144,8 @@ impl SinglePlayerGame {
         let msg = MoveRequest {
             state,
             agent: self.agent,
+            belief: Some(self.belief),
+            skill: Some(self.skill),
         };
         let msg = JsValue::from_serde(&msg).unwrap();
         let this = JsValue::null();
@@ -136,6 +157,24 @@ impl SinglePlayerGame {
         };
     }
 
+    // Both sides' Ninjas are placed at random as soon as the game starts, players can't place theirs from the board yet
+    fn place_ninjas(&mut self) {
+        let state = self.game.get_state();
+        let placements: Vec<Move> = [self.player, self.player.invert()]
+            .iter()
+            .flat_map(|player| state.random_ninja_placements(*player, &mut thread_rng()))
+            .collect();
+        for placement in placements.iter() {
+            if let Err(err) = self.try_move(*placement) {
+                log::error!("Failed to place Ninja: {}", err);
+                return;
+            }
+        }
+        if !placements.is_empty() {
+            self.send_current_view();
+        }
+    }
+
     fn rank_moves(&mut self) {
         if !self.training_mode {
             return;
@@ -157,9 +196,13 @@ impl SinglePlayerGame {
 }
 
 impl SinglePlayerGame {
-    fn try_move(&mut self, game_move: Move) -> Result<(), String> {
+    fn try_move(&mut self, game_move: Move) -> Result<(), MoveError> {
+        let board = self.game.get_state().board();
         self.game.try_move(game_move)?;
-        self.last_move = Some(game_move);
+        self.belief.observe(&board, game_move);
+        if !matches!(game_move, Move::PlaceNinja { .. }) {
+            self.last_move = Some(game_move);
+        }
         self.send_current_view();
         match self.game.get_winner() {
             None => {}
@@ -230,13 +273,14 @@ impl SinglePlayerGame {
 impl SinglePlayerGame {
     #[wasm_bindgen(js_name = move)]
     pub fn play_move(&mut self, game_move: &JsValue, is_player: bool) {
-        let player_turn = self.game.get_turn() == Some(self.player);
+        let player_turn = self.game.get_turn() == Some(self.player) || self.game.ninjas_to_place(self.player) > 0;
         if player_turn != is_player {
             return self.send_error("Not your turn".to_string());
         }
         let current_state = PreviousState {
             game: self.game.clone(),
             last_move: self.last_move,
+            belief: self.belief,
         };
         let game_move: Move = match game_move.into_serde() {
             Ok(game_move) => game_move,
@@ -250,7 +294,7 @@ impl SinglePlayerGame {
                 log::info!("Successfully played move");
             }
             Err(err) => {
-                self.send_error(err);
+                send_move_error(&self.on_send_error, err);
                 return;
             }
         };
@@ -271,6 +315,7 @@ impl SinglePlayerGame {
         };
         self.game = previous_state.game;
         self.last_move = previous_state.last_move;
+        self.belief = previous_state.belief;
         self.send_current_view();
         self.rank_moves();
     }
@@ -284,8 +329,15 @@ impl SinglePlayerGame {
         });
         self.game.reset();
         self.last_move = None;
+        self.belief = NinjaBelief::new(&self.game.get_state().board(), self.player.invert());
         self.send_current_view();
         self.agent_move();
         self.rank_moves();
     }
+
+    /// The record of the game so far in its text form, for saving or sharing
+    #[wasm_bindgen(js_name = record)]
+    pub fn record(&self) -> String {
+        self.game.get_record().to_string()
+    }
 }
@@ -1,7 +1,7 @@
 use wasm_bindgen::prelude::*;
 use serde::Serialize;
 use serde_wasm_bindgen;
-use crate::models::{Card, CardDirection, CardSet, Point};
+use crate::models::{Card, CardDirection, CardSet, MoveError, Point};
 use enum_iterator::IntoEnumIterator;
 
 /// A struct for serializing cards and their moves
@@ -58,4 +58,17 @@ pub fn list_card_sets() -> JsValue {
         .collect();
 
     serde_wasm_bindgen::to_value(&card_sets).unwrap()
-}
\ No newline at end of file
+}
+
+/// Sends the message along with the serialized error, so the client can tell rejected moves apart
+pub(crate) fn send_move_error(on_send_error: &js_sys::Function, error: MoveError) {
+    let message = JsValue::from(error.to_string());
+    let kind = serde_wasm_bindgen::to_value(&error).unwrap();
+    let this = JsValue::null();
+    match on_send_error.call2(&this, &message, &kind) {
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("Failed to call on_send_error: {:?}", err);
+        }
+    };
+}
@@ -4,7 +4,17 @@ pub use models::*;
 
 mod board;
 mod cards;
+pub mod fen;
+pub use fen::FenError;
 mod models;
+pub mod notation;
+pub use notation::NotationError;
+pub mod record;
+pub use record::{GameRecord, RecordError, ReplayError};
+
+pub mod bitboard;
+pub use bitboard::{BitBoard, Undo};
+pub mod zobrist;
 
 pub mod agents;
 pub use agents::*;
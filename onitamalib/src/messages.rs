@@ -1,23 +1,38 @@
 use serde::{Deserialize, Serialize};
 
 use crate::models::Player;
-use crate::{GameState, Move};
+use crate::{GameRecord, GameState, Move, SearchReport};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum GameMessage {
     Joined,
     RequestRematch,
     Disconnected,
+    /// States sent by the server are always redacted with `GameState::view_for` for the receiving player
     Initialize {
         state: GameState,
         room_id: String,
         player: Player,
         waiting: bool,
     },
+    /// Sent by clients, the server answers both players with an `Update`
     Move {
         game_move: Move,
     },
+    /// The state after a move was accepted, `last_move` is `None` when the player can't see it
+    Update {
+        state: GameState,
+        last_move: Option<Move>,
+    },
     Error {
         message: String,
     },
+    /// Sent by the AI after each of its moves, redacted with `SearchReport::view_for`
+    SearchReport {
+        report: SearchReport,
+    },
+    /// Sent by the server once the game is finished, with every move including the hidden ones
+    Record {
+        record: GameRecord,
+    },
 }
@@ -2,7 +2,7 @@ use std::fmt;
 use std::str::FromStr;
 use std::ops::{Add, Neg, Sub};
 
-use crate::AiAgent;
+use crate::{AiAgent, NinjaBelief, SkillLevel};
 use enum_iterator::IntoEnumIterator;
 use serde::{Deserialize, Serialize};
 
@@ -156,6 +156,16 @@ impl fmt::Display for Card {
     }
 }
 
+impl FromStr for Card {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Card, Self::Err> {
+        Card::into_enum_iter()
+            .find(|card| card.to_string().eq_ignore_ascii_case(input))
+            .ok_or(())
+    }
+}
+
 #[derive(Eq, PartialEq, Copy, Clone, Hash, IntoEnumIterator, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum CardSet {
@@ -213,6 +223,9 @@ pub struct Board {
     pub wind_move_pending: bool,
     pub wind_move_card: Option<Card>,
     pub shadow_mode: bool,
+    /// Way of the Light: Red keeps the standard army against two hidden Blue Ninjas
+    #[serde(default)]
+    pub light_mode: bool,
     pub ninja_move_pending: bool,
     pub ninja_move_card: Option<Card>,
     pub turn: Player,
@@ -223,11 +236,19 @@ pub struct Board {
 pub enum Move {
     Move { card: Card, src: Point, dst: Point, reveal_ninja: bool },
     Discard { card: Card },
+    /// Puts one of `player`'s Ninjas on its starting square during setup
+    PlaceNinja { player: Player, dst: Point },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 #[serde(tag = "status")]
 pub enum GameState {
+    /// Light and Shadow games start with both players secretly placing their Ninjas
+    Setup {
+        board: Board,
+        red_ninjas_to_place: u8,
+        blue_ninjas_to_place: u8,
+    },
     Playing { board: Board },
     Finished { board: Board, winner: Player },
 }
@@ -247,6 +268,19 @@ pub enum GameSquare {
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(tag = "status")]
 pub enum GameView {
+    Setup {
+        grid: [[GameSquare; 5]; 5],
+        #[serde(rename = "redCards")]
+        red_cards: Vec<CardDescription>,
+        #[serde(rename = "blueCards")]
+        blue_cards: Vec<CardDescription>,
+        spare: CardDescription,
+        turn: Player,
+        #[serde(rename = "redNinjasToPlace")]
+        red_ninjas_to_place: u8,
+        #[serde(rename = "blueNinjasToPlace")]
+        blue_ninjas_to_place: u8,
+    },
     Playing {
         grid: [[GameSquare; 5]; 5],
         #[serde(rename = "redCards")]
@@ -323,10 +357,99 @@ pub enum GameError {
     Error { message: String },
 }
 
+/// Why a move was rejected, `Display` gives the message shown to players
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum MoveError {
+    GameFinished,
+    CardNotInHand { card: Card },
+    NoPieceAtSource { src: Point },
+    DestinationOutOfBounds { dst: Point },
+    InvalidMoveForCard { card: Card, src: Point, dst: Point },
+    DestinationOccupied { dst: Point },
+    /// Discarding is only allowed when no piece can move
+    ValidMovesExist { card: Card },
+    /// In Way of the Shadow, the Ninja may only move after another piece
+    NinjaMovedFirst { src: Point },
+    OnlyNinjaCanMove { src: Point },
+    WrongNinjaCard { card: Card, required: Card },
+    WrongNinjaDiscard { card: Card, required: Card },
+    NinjaMoveCardMissing,
+    WindSpiritUsedWindCard { card: Card },
+    WindSpiritOntoMaster { dst: Point },
+    WindSpiritMissing,
+    MustMoveWindSpirit { src: Point },
+    WrongWindCard { card: Card, required: Card },
+    WindMoveCardMissing,
+    /// Pieces can only move once every Ninja is placed
+    SetupInProgress,
+    NotInSetup,
+    NoNinjaToPlace { player: Player },
+    InvalidNinjaPlacement { dst: Point },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::GameFinished => write!(f, "Game already finished"),
+            MoveError::CardNotInHand { .. } => write!(f, "Card not in hand"),
+            MoveError::NoPieceAtSource { .. } => write!(f, "No piece at source"),
+            MoveError::DestinationOutOfBounds { .. } => write!(f, "Destination is out of bounds"),
+            MoveError::InvalidMoveForCard { .. } => write!(f, "Move not valid for card"),
+            MoveError::DestinationOccupied { .. } => write!(f, "Destination occupied by your piece"),
+            MoveError::ValidMovesExist { .. } => write!(f, "Valid moves exist"),
+            MoveError::NinjaMovedFirst { .. } => {
+                write!(f, "You must move another piece before moving your Ninja!")
+            }
+            MoveError::OnlyNinjaCanMove { .. } => write!(f, "Only the Ninja can move now."),
+            MoveError::WrongNinjaCard { required, .. } => {
+                write!(f, "Must use the {} card for the Ninja move.", required)
+            }
+            MoveError::WrongNinjaDiscard { .. } => {
+                write!(f, "Invalid card to discard during Ninja move phase.")
+            }
+            MoveError::NinjaMoveCardMissing => {
+                write!(f, "Ninja move card is not set during ninja_move_pending.")
+            }
+            MoveError::WindSpiritUsedWindCard { .. } => {
+                write!(f, "Wind Spirit cannot use a Way of the Wind card to move")
+            }
+            MoveError::WindSpiritOntoMaster { .. } => write!(f, "Wind Spirit cannot move onto a Master!"),
+            MoveError::WindSpiritMissing => write!(f, "Wind Spirit is missing!"),
+            MoveError::MustMoveWindSpirit { .. } => write!(f, "You must move the Wind Spirit"),
+            MoveError::WrongWindCard { required, .. } => write!(f, "Must use {} to move", required),
+            MoveError::WindMoveCardMissing => {
+                write!(f, "Wind move card is not set during wind_move_pending.")
+            }
+            MoveError::SetupInProgress => write!(f, "All Ninjas must be placed before moving"),
+            MoveError::NotInSetup => write!(f, "Ninjas can only be placed before the game starts"),
+            MoveError::NoNinjaToPlace { .. } => write!(f, "All your Ninjas are already placed"),
+            MoveError::InvalidNinjaPlacement { .. } => {
+                write!(f, "Ninjas must start on an empty square of your home row")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
 impl From<&GameState> for GameView {
     fn from(state: &GameState) -> Self {
         let to_card = |card: &Card| CardDescription::from(*card);
         match state {
+            GameState::Setup {
+                board,
+                red_ninjas_to_place,
+                blue_ninjas_to_place,
+            } => Self::Setup {
+                grid: board.to_grid(),
+                red_cards: board.red_hand.iter().map(to_card).collect(),
+                blue_cards: board.blue_hand.iter().map(to_card).collect(),
+                spare: to_card(&board.spare_card),
+                turn: board.turn,
+                red_ninjas_to_place: *red_ninjas_to_place,
+                blue_ninjas_to_place: *blue_ninjas_to_place,
+            },
             GameState::Playing { board } => Self::Playing {
                 grid: board.to_grid(),
                 red_cards: board.red_hand.iter().map(to_card).collect(),
@@ -398,6 +521,12 @@ pub enum GameEvent {
 pub struct MoveRequest {
     pub state: GameState,
     pub agent: AiAgent,
+    /// What the agent knows about hidden Ninjas, the agent assumes nothing when it is left out
+    #[serde(default)]
+    pub belief: Option<NinjaBelief>,
+    /// How well the agent plays, at full strength when it is left out
+    #[serde(default)]
+    pub skill: Option<SkillLevel>,
 }
 
 // Implementing Card to determine the associated CardSet
@@ -438,6 +567,8 @@ pub struct GameSettings {
     pub enable_light_and_shadow: bool, // Enable Light and Shadow Expansion
     pub force_light_and_shadow: bool, // Force Light and Shadow expansion use
     pub light_and_shadow_mode: Option<String>, // Force specific Light and Shadow mode, or None for randomized
+    #[serde(default)]
+    pub seed: Option<u64>, // Seed for a reproducible setup, or None for a random one
 }
 
 impl GameSettings {
@@ -449,6 +580,7 @@ impl GameSettings {
             enable_light_and_shadow: true, // Default to enabling Light and Shadow expansion
             force_light_and_shadow: false, // Default to 5% of games being Light or Shadow
             light_and_shadow_mode: None, // Default to randomizing Light or Shadow as the mode
+            seed: None, // Default to a different setup every game
         }
     }
 }
@@ -0,0 +1,112 @@
+//! Compact text notation for moves, such as `Tiger c1-c3`, `Mantis b2-c3*`, `Discard Frog` or
+//! `Place Red b1` for a Ninja placed during setup.
+//!
+//! Squares are named like in chess, files `a` to `e` from left to right and ranks `1` to `5`
+//! counting from Red's side of the board. A trailing `*` marks a Ninja revealing itself.
+//! The moving piece is not written down, it is whatever stands on the source square, so Wind Spirit
+//! and Ninja follow up moves look like any other move.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::{Card, Move, Player, Point};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NotationError {
+    InvalidSquare(String),
+    UnknownCard(String),
+    InvalidMove(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::InvalidSquare(square) => write!(f, "Invalid square '{}'", square),
+            NotationError::UnknownCard(card) => write!(f, "Unknown card '{}'", card),
+            NotationError::InvalidMove(text) => write!(f, "Invalid move '{}'", text),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = (b'a' + self.x as u8) as char;
+        write!(f, "{}{}", file, 5 - self.y)
+    }
+}
+
+impl FromStr for Point {
+    type Err = NotationError;
+
+    fn from_str(input: &str) -> Result<Point, Self::Err> {
+        let invalid = || NotationError::InvalidSquare(input.to_string());
+        match input.as_bytes() {
+            [file @ b'a'..=b'e', rank @ b'1'..=b'5'] => Ok(Point {
+                x: (file - b'a') as i8,
+                y: (b'5' - rank) as i8,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Move { card, src, dst, reveal_ninja } => {
+                let reveal = if *reveal_ninja { "*" } else { "" };
+                write!(f, "{} {}-{}{}", card, src, dst, reveal)
+            }
+            Move::Discard { card } => write!(f, "Discard {}", card),
+            Move::PlaceNinja { player, dst } => write!(f, "Place {} {}", player, dst),
+        }
+    }
+}
+
+fn parse_card(input: &str) -> Result<Card, NotationError> {
+    input
+        .parse()
+        .map_err(|_| NotationError::UnknownCard(input.to_string()))
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    fn from_str(input: &str) -> Result<Move, Self::Err> {
+        let invalid = || NotationError::InvalidMove(input.to_string());
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        let (first, second) = match parts[..] {
+            [first, second] => (first, second),
+            [place, player, dst] if place.eq_ignore_ascii_case("place") => {
+                let player = match player.to_ascii_lowercase().as_str() {
+                    "red" => Player::Red,
+                    "blue" => Player::Blue,
+                    _ => return Err(invalid()),
+                };
+                return Ok(Move::PlaceNinja {
+                    player,
+                    dst: dst.parse()?,
+                });
+            }
+            _ => return Err(invalid()),
+        };
+        if first.eq_ignore_ascii_case("discard") {
+            return Ok(Move::Discard {
+                card: parse_card(second)?,
+            });
+        }
+        let card = parse_card(first)?;
+        let (squares, reveal_ninja) = match second.strip_suffix('*') {
+            Some(squares) => (squares, true),
+            None => (second, false),
+        };
+        let (src, dst) = squares.split_once('-').ok_or_else(invalid)?;
+        Ok(Move::Move {
+            card,
+            src: src.parse()?,
+            dst: dst.parse()?,
+            reveal_ninja,
+        })
+    }
+}
@@ -0,0 +1,313 @@
+//! Complete record of a game, with a PGN-like text form.
+//!
+//! ```text
+//! [Event "Club night"]
+//! [Red "alice"]
+//! [Blue "Alphabeta"]
+//! [Result "Red"]
+//! [Position "ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - -"]
+//! [DisabledCardSets ""]
+//! ...
+//!
+//! 1. Tiger c1-c3
+//! 2. Frog b5-a4
+//! ```
+//!
+//! Tags come first, one per line, followed by a blank line and one numbered move per line in the
+//! notation from `notation`. `Result`, `Position` and the settings tags are read into their own
+//! fields, any other tag is kept as metadata.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fen::FenError;
+use crate::models::{Board, GameSettings, GameState, Move, MoveError, Player};
+use crate::notation::NotationError;
+
+pub const EVENT_TAG: &str = "Event";
+pub const DATE_TAG: &str = "Date";
+pub const RED_TAG: &str = "Red";
+pub const BLUE_TAG: &str = "Blue";
+pub const RED_AGENT_TAG: &str = "RedAgent";
+pub const BLUE_AGENT_TAG: &str = "BlueAgent";
+
+const RESULT_TAG: &str = "Result";
+const POSITION_TAG: &str = "Position";
+const DISABLED_CARD_SETS_TAG: &str = "DisabledCardSets";
+const NUMBER_OF_WIND_CARDS_TAG: &str = "NumberOfWindCards";
+const FORCE_WIND_SPIRIT_TAG: &str = "ForceWindSpiritInclusion";
+const ENABLE_LIGHT_AND_SHADOW_TAG: &str = "EnableLightAndShadow";
+const FORCE_LIGHT_AND_SHADOW_TAG: &str = "ForceLightAndShadow";
+const LIGHT_AND_SHADOW_MODE_TAG: &str = "LightAndShadowMode";
+const SEED_TAG: &str = "Seed";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameRecord {
+    pub settings: GameSettings,
+    pub initial_board: Board,
+    pub moves: Vec<Move>,
+    /// Winner of the game, `None` while it is unfinished
+    pub result: Option<Player>,
+    /// Players, dates, agent names and any other tags
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplayError {
+    /// `ply` counts from 1, like the move numbers in the text form
+    IllegalMove {
+        ply: usize,
+        game_move: Move,
+        error: MoveError,
+    },
+    ResultMismatch {
+        recorded: Option<Player>,
+        actual: Option<Player>,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = |winner: &Option<Player>| match winner {
+            Some(player) => format!("{} wins", player),
+            None => "unfinished".to_string(),
+        };
+        match self {
+            ReplayError::IllegalMove { ply, game_move, error } => {
+                write!(f, "Move {} ({}) is illegal: {}", ply, game_move, error)
+            }
+            ReplayError::ResultMismatch { recorded, actual } => write!(
+                f,
+                "Recorded result is {} but the game is {}",
+                result(recorded),
+                result(actual)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RecordError {
+    InvalidTag(String),
+    MissingTag(&'static str),
+    InvalidSetting(String),
+    InvalidResult(String),
+    InvalidPosition(FenError),
+    InvalidMove { line: usize, error: NotationError },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::InvalidTag(line) => write!(f, "Invalid tag '{}'", line),
+            RecordError::MissingTag(tag) => write!(f, "Missing {} tag", tag),
+            RecordError::InvalidSetting(setting) => write!(f, "Invalid setting '{}'", setting),
+            RecordError::InvalidResult(result) => write!(f, "Invalid result '{}'", result),
+            RecordError::InvalidPosition(error) => write!(f, "Invalid position: {}", error),
+            RecordError::InvalidMove { line, error } => write!(f, "Line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl GameRecord {
+    pub fn new(settings: GameSettings, initial_board: Board) -> GameRecord {
+        GameRecord {
+            settings,
+            initial_board,
+            moves: vec![],
+            result: None,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a move that was already played, along with the state it led to
+    pub fn push(&mut self, game_move: Move, state: &GameState) {
+        self.moves.push(game_move);
+        if let GameState::Finished { winner, .. } = state {
+            self.result = Some(*winner);
+        }
+    }
+
+    pub fn set_tag(&mut self, tag: &str, value: &str) {
+        self.tags.insert(tag.to_string(), value.to_string());
+    }
+
+    /// Plays every move from the initial board, checking each one and the recorded result
+    pub fn replay(&self) -> Result<GameState, ReplayError> {
+        let mut state = GameState::Playing {
+            board: self.initial_board,
+        };
+        for (idx, game_move) in self.moves.iter().enumerate() {
+            state = state.try_move(*game_move).map_err(|error| ReplayError::IllegalMove {
+                ply: idx + 1,
+                game_move: *game_move,
+                error,
+            })?;
+        }
+        let actual = match state {
+            GameState::Finished { winner, .. } => Some(winner),
+            GameState::Setup { .. } | GameState::Playing { .. } => None,
+        };
+        if actual != self.result {
+            return Err(ReplayError::ResultMismatch {
+                recorded: self.result,
+                actual,
+            });
+        }
+        Ok(state)
+    }
+
+    fn reserved_tags(&self) -> Vec<(&'static str, String)> {
+        let settings = &self.settings;
+        let result = match self.result {
+            Some(player) => player.to_string(),
+            None => "*".to_string(),
+        };
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        vec![
+            (RESULT_TAG, result),
+            (POSITION_TAG, self.initial_board.to_fen()),
+            (DISABLED_CARD_SETS_TAG, settings.disabled_card_sets.join(",")),
+            (NUMBER_OF_WIND_CARDS_TAG, optional(settings.number_of_wind_cards.map(|n| n.to_string()))),
+            (FORCE_WIND_SPIRIT_TAG, settings.force_wind_spirit_inclusion.to_string()),
+            (ENABLE_LIGHT_AND_SHADOW_TAG, settings.enable_light_and_shadow.to_string()),
+            (FORCE_LIGHT_AND_SHADOW_TAG, settings.force_light_and_shadow.to_string()),
+            (LIGHT_AND_SHADOW_MODE_TAG, optional(settings.light_and_shadow_mode.clone())),
+            (SEED_TAG, optional(settings.seed.map(|seed| seed.to_string()))),
+        ]
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (tag, value) in self.tags.iter() {
+            writeln!(f, "[{} \"{}\"]", tag, escape(value))?;
+        }
+        for (tag, value) in self.reserved_tags() {
+            writeln!(f, "[{} \"{}\"]", tag, escape(&value))?;
+        }
+        writeln!(f)?;
+        for (idx, game_move) in self.moves.iter().enumerate() {
+            writeln!(f, "{}. {}", idx + 1, game_move)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), RecordError> {
+    let invalid = || RecordError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (tag, value) = inner.split_once(' ').ok_or_else(invalid)?;
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((tag.to_string(), unescape(value)))
+}
+
+fn parse_bool(tag: &str, value: &str) -> Result<bool, RecordError> {
+    value
+        .parse()
+        .map_err(|_| RecordError::InvalidSetting(format!("{} {}", tag, value)))
+}
+
+fn parse_optional<T: FromStr>(tag: &str, value: Option<String>) -> Result<Option<T>, RecordError> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| RecordError::InvalidSetting(format!("{} {}", tag, value))),
+        None => Ok(None),
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(input: &str) -> Result<GameRecord, Self::Err> {
+        let mut tags = BTreeMap::new();
+        let mut moves = vec![];
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                let (tag, value) = parse_tag(line)?;
+                tags.insert(tag, value);
+                continue;
+            }
+            // Move numbers are only there for readers, the order of the lines is what counts
+            let text = line.split_once(". ").map_or(line, |(_, text)| text);
+            let game_move = text.parse().map_err(|error| RecordError::InvalidMove {
+                line: idx + 1,
+                error,
+            })?;
+            moves.push(game_move);
+        }
+
+        // Records written before seeds were added have no Seed tag
+        let seed = parse_optional(SEED_TAG, tags.remove(SEED_TAG).filter(|seed| seed != "-"))?;
+        let mut take = |tag: &'static str| tags.remove(tag).ok_or(RecordError::MissingTag(tag));
+        let result = match take(RESULT_TAG)?.as_str() {
+            "Red" => Some(Player::Red),
+            "Blue" => Some(Player::Blue),
+            "*" => None,
+            other => return Err(RecordError::InvalidResult(other.to_string())),
+        };
+        let initial_board = Board::from_fen(&take(POSITION_TAG)?).map_err(RecordError::InvalidPosition)?;
+        let optional = |value: String| match value.as_str() {
+            "-" => None,
+            _ => Some(value),
+        };
+        let disabled_card_sets = take(DISABLED_CARD_SETS_TAG)?;
+        let number_of_wind_cards = parse_optional(NUMBER_OF_WIND_CARDS_TAG, optional(take(NUMBER_OF_WIND_CARDS_TAG)?))?;
+        let settings = GameSettings {
+            disabled_card_sets: disabled_card_sets
+                .split(',')
+                .filter(|set| !set.is_empty())
+                .map(|set| set.to_string())
+                .collect(),
+            number_of_wind_cards,
+            force_wind_spirit_inclusion: parse_bool(FORCE_WIND_SPIRIT_TAG, &take(FORCE_WIND_SPIRIT_TAG)?)?,
+            enable_light_and_shadow: parse_bool(ENABLE_LIGHT_AND_SHADOW_TAG, &take(ENABLE_LIGHT_AND_SHADOW_TAG)?)?,
+            force_light_and_shadow: parse_bool(FORCE_LIGHT_AND_SHADOW_TAG, &take(FORCE_LIGHT_AND_SHADOW_TAG)?)?,
+            light_and_shadow_mode: optional(take(LIGHT_AND_SHADOW_MODE_TAG)?),
+            seed,
+        };
+
+        Ok(GameRecord {
+            settings,
+            initial_board,
+            moves,
+            result,
+            tags,
+        })
+    }
+}
@@ -1,3 +1,22 @@
+mod test_agent;
 mod test_alphabeta;
+mod test_belief;
+mod test_bitboard;
+mod test_evaluation;
+mod test_fen;
+mod test_ismcts;
+mod test_light;
+mod test_limits;
+mod test_mcts;
 mod test_montecarlo;
+mod test_move_error;
+mod test_notation;
+#[cfg(feature = "parallel")]
+mod test_parallel;
+mod test_record;
+mod test_setup;
+mod test_skill;
+mod test_tuning;
+mod test_view;
+mod test_zobrist;
 mod utils;
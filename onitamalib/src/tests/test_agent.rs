@@ -0,0 +1,127 @@
+use enum_iterator::IntoEnumIterator;
+use instant::Duration;
+use rand::prelude::*;
+
+use super::utils;
+use crate::evaluation::FEATURES;
+use crate::{Agent, AiAgent, EvalWeights, GameSettings, GameState, Player, SearchLimits, SearchReport};
+
+const DURATION: Duration = Duration::from_millis(50);
+const PLIES: usize = 10;
+
+// A downstream agent only has to pick moves
+struct FirstMove;
+
+impl Agent for FirstMove {
+    fn choose_move(&mut self, state: &GameState, _: SearchLimits) -> Option<SearchReport> {
+        Some(SearchReport::new(*state.board().legal_moves().first()?, 0))
+    }
+}
+
+fn play_game(mut red: Box<dyn Agent>, mut blue: Box<dyn Agent>, seed: u64) {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let settings = GameSettings {
+        force_light_and_shadow: true,
+        light_and_shadow_mode: Some("Shadow".to_string()),
+        ..GameSettings::default()
+    };
+    let mut state = GameState::new_with_settings_rng(settings, &mut rng);
+    red.new_game(&state, Player::Red);
+    blue.new_game(&state, Player::Blue);
+    let mut placements = state.random_ninja_placements(Player::Red, &mut rng);
+    placements.extend(state.random_ninja_placements(Player::Blue, &mut rng));
+    for _ in 0..PLIES {
+        let game_move = match (state, placements.pop()) {
+            (GameState::Setup { .. }, Some(placement)) => placement,
+            (GameState::Playing { board }, _) => {
+                let agent = match board.turn {
+                    Player::Red => &mut red,
+                    Player::Blue => &mut blue,
+                };
+                agent.choose_move(&state, DURATION.into()).expect("No move found").game_move
+            }
+            _ => break,
+        };
+        red.observe_move(&state, game_move);
+        blue.observe_move(&state, game_move);
+        state = state.try_move(game_move).expect("Agent played an illegal move");
+    }
+}
+
+#[test]
+fn built_agents_play_games() {
+    for (seed, agent) in AiAgent::into_enum_iter().enumerate() {
+        play_game(agent.build(), agent.build(), seed as u64);
+    }
+}
+
+#[test]
+fn custom_agents_play_games() {
+    play_game(Box::new(FirstMove), AiAgent::Greedy.build(), 0);
+}
+
+#[test]
+fn ranked_moves_are_legal() {
+    let states = utils::generate_expansion_test_states();
+    for agent in AiAgent::into_enum_iter() {
+        let mut built = agent.build();
+        for state in states.iter().step_by(20) {
+            let ranked = match built.rank_moves(state, DURATION.into()) {
+                Some(ranking) => ranking.ranked_moves,
+                None => continue,
+            };
+            // Moves are ranked on a guess of where hidden Ninjas are, which can drop a redundant reveal
+            assert!(!ranked.is_empty());
+            for (game_move, _) in ranked {
+                assert!(state.try_move(game_move).is_ok(), "{:?} ranked illegal {:?}", agent, game_move);
+            }
+        }
+    }
+}
+
+#[test]
+fn reports_follow_the_chosen_move() {
+    // Without hidden Ninjas every agent searches the real position, so the whole line is legal
+    let states = utils::generate_test_states();
+    for agent in AiAgent::into_enum_iter() {
+        for state in states.iter().step_by(25) {
+            let report = agent.play_move(state, DURATION.into()).expect("No move found");
+            assert_eq!(report.principal_variation.first(), Some(&report.game_move));
+            let mut line = *state;
+            for game_move in report.principal_variation.iter() {
+                line = line.try_move(*game_move).expect("Principal variation has an illegal move");
+            }
+            let json = serde_json::to_string(&report).unwrap();
+            assert_eq!(serde_json::from_str::<SearchReport>(&json).unwrap(), report);
+        }
+    }
+}
+
+#[test]
+fn report_views_hide_what_the_player_cannot_see() {
+    for state in utils::generate_expansion_test_states() {
+        let board = state.board();
+        let opponent = board.turn.invert();
+        for game_move in board.legal_moves() {
+            let visible = board.move_view_for(game_move, opponent) == Some(game_move);
+            let view = SearchReport::new(game_move, 0).view_for(&state, opponent);
+            assert_eq!(view.is_some(), visible, "{:?}", game_move);
+        }
+    }
+}
+
+#[test]
+fn built_agents_evaluate_with_given_weights() {
+    let state = utils::generate_test_states()[0];
+    let limits = SearchLimits {
+        depth: Some(2),
+        ..SearchLimits::default()
+    };
+    let score = |agent: &mut Box<dyn Agent>| agent.choose_move(&state, limits).unwrap().score;
+    for agent in [AiAgent::Greedy, AiAgent::Minimax, AiAgent::Alphabeta] {
+        assert_ne!(score(&mut agent.build()), 0, "{:?}", agent);
+        // Nothing is worth anything, and neither side can win within the search
+        let weightless = EvalWeights::from_array([0.0; FEATURES]);
+        assert_eq!(score(&mut agent.build_with_weights(weightless)), 0, "{:?}", agent);
+    }
+}
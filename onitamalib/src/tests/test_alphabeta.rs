@@ -1,7 +1,10 @@
+use std::sync::atomic::AtomicBool;
+
 use indicatif::ProgressBar;
-use instant::Duration;
+use instant::{Duration, Instant};
 
-use crate::{alphabeta, minimax, GameState};
+use crate::transposition::TranspositionTable;
+use crate::{alphabeta, minimax, BitBoard, Evaluator, ForcedWin, NinjaBelief, SearchLimits};
 
 use super::utils;
 
@@ -13,11 +16,11 @@ fn alphabeta_equivalent_to_minimax() {
     let bar = ProgressBar::new(100);
     for state in test_states.into_iter() {
         bar.inc(1);
-        let ab_value = match alphabeta::optimal_move(&state, SHARED_DEPTH) {
+        let ab_value = match alphabeta::optimal_move(&state, SHARED_DEPTH, &Evaluator::default()) {
             None => panic!("Alphabeta returned None"),
             Some((_, expected)) => expected,
         };
-        let minimax_value = match minimax::optimal_move(&state, SHARED_DEPTH) {
+        let minimax_value = match minimax::optimal_move(&state, SHARED_DEPTH, &Evaluator::default()) {
             None => panic!("Minimax returned None"),
             Some((_, expected)) => expected,
         };
@@ -26,6 +29,51 @@ fn alphabeta_equivalent_to_minimax() {
     bar.finish();
 }
 
+#[test]
+fn searches_take_the_fastest_win() {
+    let mut winning_states = 0;
+    for state in utils::generate_test_states() {
+        let board = state.board();
+        let can_win = board
+            .legal_moves()
+            .into_iter()
+            .any(|game_move| state.try_move(game_move).unwrap().finished());
+        if !can_win {
+            continue;
+        }
+        winning_states += 1;
+        let forced_win = ForcedWin {
+            winner: board.turn,
+            plies: 1,
+        };
+        // Slower wins are found at this depth too
+        let results = [
+            alphabeta::optimal_move(&state, SHARED_DEPTH, &Evaluator::default()),
+            minimax::optimal_move(&state, SHARED_DEPTH, &Evaluator::default()),
+        ];
+        for (game_move, score) in results.iter().map(|result| result.expect("No move found")) {
+            assert_eq!(ForcedWin::from_score(score), Some(forced_win));
+            assert!(state.try_move(game_move).unwrap().finished());
+        }
+    }
+    assert!(winning_states > 0);
+}
+
+#[test]
+fn stopped_ponder_keeps_the_first_ply() {
+    // The first ply always finishes, even once stopped
+    let stop = AtomicBool::new(true);
+    for state in utils::generate_test_states() {
+        let board = state.board();
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        let mut table = TranspositionTable::default();
+        alphabeta::ponder(&state, &belief, SHARED_DEPTH, &mut table, &Evaluator::default(), &stop);
+        let entry = table.probe(BitBoard::from(&board).hash).expect("Root wasn't stored");
+        assert_eq!(entry.depth, 1);
+        assert!(state.try_move(entry.best_move.unwrap()).is_ok());
+    }
+}
+
 #[test]
 fn alphabeta_benchmark() {
     let duration = Duration::from_millis(100);
@@ -50,3 +98,34 @@ fn alphabeta_benchmark() {
         average_depth, counted
     );
 }
+
+#[test]
+fn alphabeta_depth_per_second() {
+    let test_states = utils::generate_test_states();
+    let evaluator = Evaluator::default();
+    // Each depth also searches every depth before it, like a timed search would
+    for depth in 1.. {
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        };
+        let start = Instant::now();
+        let mut nodes = 0u64;
+        for state in test_states.iter() {
+            let board = state.board();
+            let belief = NinjaBelief::unknown(&board, board.turn);
+            let report = alphabeta::iterative_deepening(state, &belief, limits, &evaluator).expect("No move found");
+            nodes += report.nodes;
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "Depth {}: {:.2}ms per position, {:.0} nodes per second",
+            depth,
+            elapsed.as_secs_f64() * 1000.0 / test_states.len() as f64,
+            nodes as f64 / elapsed.as_secs_f64()
+        );
+        if elapsed > Duration::from_secs(1) {
+            break;
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use rand::prelude::*;
+
+use crate::bitboard::{bit, points, side};
+use crate::{BitBoard, GameSettings, GameState, Move, NinjaBelief, Player, Point};
+
+const GAMES: u64 = 50;
+const MAX_PLIES: usize = 200;
+
+fn settings(mode: &str) -> GameSettings {
+    GameSettings {
+        force_light_and_shadow: true,
+        light_and_shadow_mode: Some(mode.to_string()),
+        ..GameSettings::default()
+    }
+}
+
+// Plays random games, calling `check` with both beliefs after every move
+fn play_random_games(mut check: impl FnMut(&GameState, &NinjaBelief)) {
+    let mut rng = SmallRng::seed_from_u64(0);
+    for mode in ["Light", "Shadow"] {
+        for _ in 0..GAMES {
+            let mut state = GameState::new_with_settings_rng(settings(mode), &mut rng);
+            let mut beliefs = [
+                NinjaBelief::new(&state.board(), Player::Red),
+                NinjaBelief::new(&state.board(), Player::Blue),
+            ];
+            let mut placements = state.random_ninja_placements(Player::Red, &mut rng);
+            placements.extend(state.random_ninja_placements(Player::Blue, &mut rng));
+            for _ in 0..MAX_PLIES {
+                let board = state.board();
+                let game_move = match (state, placements.pop()) {
+                    (GameState::Setup { .. }, Some(placement)) => placement,
+                    (GameState::Playing { board }, _) => board.random_legal_move(&mut rng).unwrap(),
+                    _ => break,
+                };
+                state = state.try_move(game_move).unwrap();
+                // A captured Master stays on the board, so the last position can't be checked
+                if state.finished() {
+                    break;
+                }
+                for belief in beliefs.iter_mut() {
+                    belief.observe(&board, game_move);
+                    check(&state, belief);
+                }
+            }
+        }
+    }
+}
+
+fn hidden_ninjas(state: &GameState, player: Player) -> u32 {
+    let bits = BitBoard::from(&state.board());
+    bits.ninjas[side(player)] & !bits.revealed_ninjas[side(player)]
+}
+
+#[test]
+fn belief_contains_hidden_ninjas() {
+    play_random_games(|state, belief| {
+        let ninjas = hidden_ninjas(state, belief.observer.invert());
+        for pos in points(ninjas) {
+            assert!(belief.contains(pos), "{} missing from {:?}", pos, belief.squares());
+        }
+        assert!(belief.placements().contains(&ninjas), "{:b} missing from {:?}", ninjas, belief.placements());
+    });
+}
+
+#[test]
+fn samples_come_from_belief() {
+    let mut rng = SmallRng::seed_from_u64(1);
+    play_random_games(|state, belief| {
+        let opponent = belief.observer.invert();
+        let board = BitBoard::from(&state.board());
+        let sampled = belief.determinize(&state.board(), &mut rng);
+        assert_eq!(hidden_ninjas(state, opponent).count_ones(), {
+            let side = side(opponent);
+            (sampled.ninjas[side] & !sampled.revealed_ninjas[side]).count_ones()
+        });
+        assert_eq!(sampled.ninjas[side(belief.observer)], board.ninjas[side(belief.observer)]);
+        assert_eq!(sampled.revealed_ninjas, board.revealed_ninjas);
+        let ninjas = sampled.ninjas[side(opponent)] & !sampled.revealed_ninjas[side(opponent)];
+        assert!(belief.placements().contains(&ninjas));
+    });
+}
+
+#[test]
+fn unmoved_ninjas_stay_on_home_row() {
+    let mut rng = SmallRng::seed_from_u64(2);
+    let mut state = GameState::new_with_settings_rng(settings("Shadow"), &mut rng);
+    for player in [Player::Red, Player::Blue].iter().copied() {
+        for placement in state.random_ninja_placements(player, &mut rng) {
+            state = state.try_move(placement).unwrap();
+        }
+    }
+    let board = state.board();
+    let home_y = match board.turn {
+        Player::Red => 4,
+        Player::Blue => 0,
+    };
+    let mut belief = NinjaBelief::new(&board, board.turn.invert());
+    assert!(belief.squares().iter().all(|pos| pos.y == home_y));
+
+    // The mover's Ninja only moves after another piece did, so one move keeps it at home
+    let game_move = board
+        .legal_moves()
+        .into_iter()
+        .find(|game_move| matches!(game_move, Move::Move { src, .. } if !board.is_ninja(*src)))
+        .unwrap();
+    belief.observe(&board, game_move);
+    assert!(belief.squares().iter().all(|pos| pos.y == home_y));
+}
+
+#[test]
+fn one_moved_ninja_leaves_the_other_at_home() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    let mut state = GameState::new_with_settings_rng(settings("Light"), &mut rng);
+    for placement in state.random_ninja_placements(Player::Blue, &mut rng) {
+        state = state.try_move(placement).unwrap();
+    }
+    let mut belief = NinjaBelief::new(&state.board(), Player::Red);
+    let home_row = (0..5).fold(0, |row, x| row | bit(Point { x, y: 0 }));
+    let board = state.board();
+    let game_move = board.random_legal_move(&mut rng).unwrap();
+    belief.observe(&board, game_move);
+    state = state.try_move(game_move).unwrap();
+
+    // One of Blue's Ninjas steps off the home row without showing itself
+    let board = state.board();
+    let game_move = board
+        .legal_moves()
+        .into_iter()
+        .find(|game_move| {
+            let ninjas = hidden_ninjas(&board.try_move(*game_move).unwrap(), Player::Blue);
+            ninjas.count_ones() == 2 && ninjas & !home_row != 0
+        })
+        .unwrap();
+    belief.observe(&board, game_move);
+    state = state.try_move(game_move).unwrap();
+    assert!(belief.placements().iter().all(|ninjas| ninjas & home_row != 0), "{:?}", belief.placements());
+    for _ in 0..100 {
+        let sampled = belief.determinize(&state.board(), &mut rng);
+        assert_ne!(sampled.ninjas[side(Player::Blue)] & home_row, 0);
+    }
+}
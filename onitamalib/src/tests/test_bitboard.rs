@@ -0,0 +1,141 @@
+use super::utils;
+use crate::bitboard::{point, side};
+use crate::{BitBoard, Board, Card, GameState, Move, MoveError, Player};
+
+fn test_boards() -> Vec<Board> {
+    utils::generate_test_states()
+        .into_iter()
+        .chain(utils::generate_expansion_test_states())
+        .map(|state| match state {
+            GameState::Playing { board } => board,
+            _ => panic!("Expected a game in play"),
+        })
+        .collect()
+}
+
+#[test]
+fn bitboard_round_trip() {
+    for board in test_boards() {
+        let bits = BitBoard::from(&board);
+        assert_eq!(BitBoard::from(&Board::from(&bits)), bits);
+    }
+}
+
+#[test]
+fn legal_moves_are_accepted() {
+    for board in test_boards() {
+        let bits = BitBoard::from(&board);
+        let moves = bits.legal_moves();
+        assert!(!moves.is_empty());
+        for game_move in moves {
+            assert!(bits.try_move(game_move).is_ok(), "{:?} rejected", game_move);
+            assert!(board.try_move(game_move).is_ok(), "{:?} rejected", game_move);
+        }
+    }
+}
+
+#[test]
+fn captures_are_ordered_first() {
+    for board in test_boards() {
+        let bits = BitBoard::from(&board);
+        let is_capture = |game_move: &Move| match game_move {
+            Move::Move { dst, .. } => bits.opponent_pieces() & crate::bitboard::bit(*dst) != 0,
+            Move::Discard { .. } | Move::PlaceNinja { .. } => false,
+        };
+        let moves = bits.legal_moves();
+        let first_quiet = moves.iter().position(|m| !is_capture(m)).unwrap_or(moves.len());
+        assert!(moves[first_quiet..].iter().all(|m| !is_capture(m)));
+    }
+}
+
+#[test]
+fn apply_move_matches_try_move() {
+    for board in test_boards() {
+        let mut bits = BitBoard::from(&board);
+        let before = bits;
+        for game_move in bits.legal_moves() {
+            let expected = bits.try_move(game_move).expect("Generated illegal move");
+            let undo = bits.apply_move(game_move);
+            assert_eq!((bits, undo.winner()), expected, "{:?}", game_move);
+            bits.undo(undo);
+            assert_eq!(bits, before);
+        }
+    }
+}
+
+#[test]
+fn board_undo_restores_pieces() {
+    for board in test_boards() {
+        for game_move in board.legal_moves() {
+            let mut updated = board;
+            let undo = updated.apply_move(game_move);
+            updated.undo(undo);
+            assert_eq!(BitBoard::from(&updated), BitBoard::from(&board));
+        }
+    }
+}
+
+#[test]
+fn legal_moves_match_try_move() {
+    for board in test_boards() {
+        let bits = BitBoard::from(&board);
+        let outcomes: Vec<_> = bits
+            .legal_moves()
+            .into_iter()
+            .map(|game_move| bits.try_move(game_move).expect("Generated illegal move"))
+            .collect();
+        for (idx, outcome) in outcomes.iter().enumerate() {
+            assert!(!outcomes[..idx].contains(outcome), "Duplicate move generated");
+        }
+
+        // Anything `try_move` accepts has to play out like one of the generated moves
+        let mut cards = bits.hands[side(bits.turn)].to_vec();
+        cards.push(bits.spare_card);
+        for card in cards {
+            let mut candidates = vec![Move::Discard { card }];
+            for src in 0..25 {
+                for dst in 0..25 {
+                    for &reveal_ninja in [false, true].iter() {
+                        candidates.push(Move::Move {
+                            card,
+                            src: point(src),
+                            dst: point(dst),
+                            reveal_ninja,
+                        });
+                    }
+                }
+            }
+            for game_move in candidates {
+                if let Ok(outcome) = bits.try_move(game_move) {
+                    assert!(outcomes.contains(&outcome), "{:?} accepted but not generated", game_move);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn hidden_ninjas_can_reveal() {
+    let moves: Vec<Move> = test_boards().iter().flat_map(|board| board.legal_moves()).collect();
+    assert!(moves
+        .iter()
+        .any(|game_move| matches!(game_move, Move::Move { reveal_ninja: true, .. })));
+}
+
+#[test]
+fn wind_phase_discards_without_normal_moves() {
+    // Neither Scorpion nor Spider move the Red Master, and the Wind Spirit can't use them outside its own move
+    let board = Board::from_fen("5/1p3/5/k4/p1W1K Scorpion,Spider Bat,Eagle Lion r w:Spider -").unwrap();
+    let discard = Move::Discard { card: Card::Scorpion };
+    assert!(board.legal_moves().contains(&discard));
+    let next = board.try_move(discard).expect("Discard rejected").board();
+    assert_eq!(next.turn, Player::Blue);
+    assert_eq!(next.spare_card, Card::Scorpion);
+    assert!(!next.wind_move_pending);
+
+    // Otherwise the Wind Spirit move can't be skipped
+    let board = Board::from_fen("1pkn*p/5/2W2/5/(Nn)PKP1 Tiger,Eagle Frog,Crab Eel b w:Frog shadow").unwrap();
+    let discard = Move::Discard { card: Card::Frog };
+    assert!(!board.legal_moves().contains(&discard));
+    assert_eq!(board.try_move(discard).err(), Some(MoveError::ValidMovesExist { card: Card::Frog }));
+}
@@ -0,0 +1,102 @@
+use rand::prelude::*;
+
+use super::utils;
+use crate::bitboard::{bit, side};
+use crate::evaluation::{EvalWeights, Evaluator, Features, EVAL_SCALE, FEATURES};
+use crate::{BitBoard, GameState, Move};
+
+fn boards() -> Vec<BitBoard> {
+    utils::generate_test_states()
+        .into_iter()
+        .chain(utils::generate_expansion_test_states())
+        .map(|state| BitBoard::from(&state.board()))
+        .collect()
+}
+
+#[test]
+fn material_weights_match_basic_value() {
+    let evaluator = Evaluator::new(EvalWeights::material());
+    for state in utils::generate_test_states() {
+        let board = BitBoard::from(&state.board());
+        assert_eq!(evaluator.evaluate(&board), state.basic_value() * EVAL_SCALE);
+    }
+}
+
+#[test]
+fn evaluation_is_linear_in_features() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut weights = [0.0; FEATURES];
+    weights.iter_mut().for_each(|weight| *weight = rng.gen_range(-5.0..5.0));
+    let evaluator = Evaluator::new(EvalWeights::from_array(weights));
+    for board in boards() {
+        let features = Features::new(&board);
+        let expected: f64 = (0..FEATURES).map(|idx| weights[idx] * features.values[idx]).sum();
+        assert_eq!(evaluator.evaluate(&board), (expected * EVAL_SCALE as f64).round() as i64);
+    }
+}
+
+#[test]
+fn weights_load_from_json() {
+    let weights = EvalWeights::from_json(r#"{"mobility": 0.5, "pawns": [9, 7, 5, 3]}"#).unwrap();
+    assert_eq!(weights.mobility, 0.5);
+    assert_eq!(weights.pawns, [9.0, 7.0, 5.0, 3.0]);
+    assert_eq!(weights.ninja, EvalWeights::default().ninja);
+    assert_eq!(EvalWeights::from_json(&weights.to_json()).unwrap(), weights);
+    assert!(Evaluator::from_json("{\"mobility\": \"high\"}").is_err());
+}
+
+#[test]
+fn features_favour_the_side_with_more() {
+    let state = match utils::generate_test_states()[0] {
+        GameState::Playing { board } => board,
+        _ => panic!("Expected a game in play"),
+    };
+    let mut board = BitBoard::from(&state);
+    let start = Features::new(&board).values;
+    // Taking away one of Blue's pawns only helps Red
+    board.pawns[1] &= board.pawns[1] - 1;
+    let fewer = Features::new(&board).values;
+    assert!(fewer[..4].iter().sum::<f64>() > start[..4].iter().sum::<f64>());
+}
+
+#[test]
+fn fractional_weights_count() {
+    // Each card offers a few moves, too few for the spare card's weight to survive rounding to whole units
+    let weights = EvalWeights {
+        spare_card: 0.2,
+        ..EvalWeights::from_array([0.0; FEATURES])
+    };
+    let evaluator = Evaluator::new(weights);
+    for board in boards() {
+        let spare = Features::new(&board).values[12];
+        assert_ne!(spare, 0.0);
+        assert_eq!(evaluator.evaluate(&board), (0.2 * spare * EVAL_SCALE as f64).round() as i64);
+    }
+}
+
+#[test]
+fn master_mobility_counts_master_moves() {
+    for mut board in boards() {
+        if board.ninja_move_pending || board.wind_move_pending {
+            continue;
+        }
+        let mut master_squares = [0, 0];
+        for turn in [board.turn, board.turn.invert()] {
+            board.turn = turn;
+            let king = board.kings[side(turn)];
+            let mut squares: Vec<_> = board
+                .legal_moves()
+                .into_iter()
+                .filter_map(|game_move| match game_move {
+                    Move::Move { src, dst, .. } if bit(src) == king => Some(dst),
+                    _ => None,
+                })
+                .collect();
+            squares.sort_by_key(|dst| (dst.x, dst.y));
+            squares.dedup();
+            master_squares[side(turn)] = squares.len() as i64;
+        }
+        let expected = (master_squares[0] - master_squares[1]) as f64;
+        assert_eq!(Features::new(&board).values[13], expected);
+    }
+}
@@ -0,0 +1,85 @@
+use super::utils;
+use crate::{BitBoard, Board, FenError, GameState, Point};
+
+fn round_trip(board: &Board) {
+    let fen = board.to_fen();
+    let parsed = Board::from_fen(&fen).unwrap_or_else(|err| panic!("{}: {}", fen, err));
+    assert_eq!(parsed.to_fen(), fen);
+    assert_eq!(BitBoard::from(&parsed), BitBoard::from(board), "{}", fen);
+}
+
+#[test]
+fn positions_round_trip() {
+    let states = utils::generate_test_states()
+        .into_iter()
+        .chain(utils::generate_expansion_test_states());
+    for state in states {
+        let board = match state {
+            GameState::Playing { board } => board,
+            _ => panic!("Expected a game in play"),
+        };
+        round_trip(&board);
+        // Covers pending follow up moves, finished games keep the captured Master on its square
+        for game_move in board.legal_moves() {
+            if let GameState::Playing { board } = board.try_move(game_move).unwrap() {
+                round_trip(&board);
+            }
+        }
+    }
+}
+
+#[test]
+fn parses_starting_position() {
+    let board = Board::from_fen("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - -").unwrap();
+    assert_eq!(board.red_king, Some(Point { x: 2, y: 4 }));
+    assert_eq!(board.blue_king, Some(Point { x: 2, y: 0 }));
+    assert_eq!(board.red_pawns.iter().flatten().count(), 4);
+    assert!(!board.shadow_mode);
+
+    let board = Board::from_fen("1pkn*p/5/2W2/5/(Nn)PKP1 Tiger,Eagle Frog,Crab Eel b w:Frog shadow").unwrap();
+    assert_eq!(board.wind_spirit, Some(Point { x: 2, y: 2 }));
+    assert_eq!(board.blue_ninjas, [Some((Point { x: 3, y: 0 }, true)), Some((Point { x: 0, y: 4 }, false))]);
+    assert_eq!(board.red_ninjas[0], Some((Point { x: 0, y: 4 }, false)));
+    assert_eq!(board.wind_move_card, Some(crate::Card::Frog));
+    assert!(board.wind_move_pending && board.shadow_mode);
+}
+
+#[test]
+fn parses_hidden_ninjas_and_light_mode() {
+    let board = Board::from_fen("pp(kN)pp/5/5/5/PPKPN Tiger,Dragon Frog,Crab Eel r n:Tiger shadow").unwrap();
+    assert_eq!(board.red_ninjas[0], Some((Point { x: 2, y: 0 }, false)));
+    assert_eq!(board.ninja_move_card, Some(crate::Card::Tiger));
+
+    let board = Board::from_fen("n3n/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - light").unwrap();
+    assert_eq!(board.blue_king, None);
+    assert!(board.light_mode);
+}
+
+#[test]
+fn rejects_invalid_positions() {
+    let cases = [
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r -", FenError::FieldCount(6)),
+        ("ppkpp/5/5/5/PPKP Tiger,Dragon Frog,Crab Eel r - -", FenError::InvalidGrid("ppkpp/5/5/5/PPKP".to_string())),
+        ("ppkpp/5/5/5/PPKPX Tiger,Dragon Frog,Crab Eel r - -", FenError::UnknownPiece('X')),
+        ("ppkpp/5/5/5/PPKPP Tiger,Tiger Frog,Crab Eel r - -", FenError::DuplicateCard(crate::Card::Tiger)),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Unicorn r - -", FenError::UnknownCard("Unicorn".to_string())),
+        ("ppkpp/5/5/P4/PPKPP Tiger,Dragon Frog,Crab Eel r - -", FenError::TooManyPieces("Red Pawns".to_string())),
+        ("ppkpp/5/5/5/(PP)1KPP Tiger,Dragon Frog,Crab Eel r - -", FenError::SharedSquare(Point { x: 0, y: 4 })),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel g - -", FenError::InvalidTurn("g".to_string())),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r w:Frog -", FenError::PendingCardNotInHand(crate::Card::Frog)),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r x:Tiger -", FenError::InvalidPending("x:Tiger".to_string())),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - dark", FenError::InvalidMode("dark".to_string())),
+        ("pp1pp/5/5/5/PP(Kk)PP Tiger,Dragon Frog,Crab Eel r - -", FenError::SharedSquare(Point { x: 2, y: 4 })),
+        ("ppkpp/5/5/5/(Pp)PKPP Tiger,Dragon Frog,Crab Eel r - -", FenError::SharedSquare(Point { x: 0, y: 4 })),
+        ("ppkpp/5/5/5/(N*p)PKPP Tiger,Dragon Frog,Crab Eel r - shadow", FenError::SharedSquare(Point { x: 0, y: 4 })),
+        ("5/5/5/5/5 Tiger,Dragon Frog,Crab Eel r - -", FenError::MissingMaster(crate::Player::Red)),
+        ("pp1pp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - -", FenError::MissingMaster(crate::Player::Blue)),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r n:Tiger -", FenError::InvalidPending("n:Tiger".to_string())),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r n:Tiger shadow", FenError::InvalidPending("n:Tiger".to_string())),
+        ("ppkpp/5/5/5/PPKPN Tiger,Dragon Frog,Crab Eel r n:Tiger -", FenError::InvalidPending("n:Tiger".to_string())),
+        ("ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r w:Tiger -", FenError::InvalidPending("w:Tiger".to_string())),
+    ];
+    for (fen, expected) in cases.iter() {
+        assert_eq!(Board::from_fen(fen).unwrap_err(), *expected, "{}", fen);
+    }
+}
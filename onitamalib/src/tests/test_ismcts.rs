@@ -0,0 +1,38 @@
+use super::utils;
+use crate::ismcts::{self, IsmctsConfig};
+use crate::{AiAgent, GameState, NinjaBelief};
+use instant::Duration;
+
+#[test]
+fn ismcts_plays_legal_moves() {
+    let duration = Duration::from_millis(5);
+    for state in utils::generate_expansion_test_states() {
+        let board = state.board();
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        let game_move = ismcts::ismcts_agent(&state, &belief, IsmctsConfig::default(), duration.into()).expect("No move found").game_move;
+        assert!(state.try_move(game_move).is_ok(), "{:?} is not legal", game_move);
+    }
+}
+
+#[test]
+fn ismcts_takes_winning_moves() {
+    let duration = Duration::from_millis(20);
+    let mut winning_states = 0;
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            _ => panic!("Expected a game in play"),
+        };
+        let can_win = board
+            .legal_moves()
+            .into_iter()
+            .any(|game_move| state.try_move(game_move).unwrap().finished());
+        if !can_win {
+            continue;
+        }
+        winning_states += 1;
+        let game_move = AiAgent::InformationSetMonteCarlo.play_move(&state, duration.into()).unwrap().game_move;
+        assert!(state.try_move(game_move).unwrap().finished(), "Missed a win with {:?}", game_move);
+    }
+    assert!(winning_states > 0);
+}
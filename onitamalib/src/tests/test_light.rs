@@ -0,0 +1,113 @@
+use crate::{Board, GameSettings, GameState, Move, Player, Point};
+
+fn board(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap_or_else(|err| panic!("{}: {}", fen, err))
+}
+
+// Plays the first legal move from `src` to `dst`, whatever the card
+fn play(board: &Board, src: Point, dst: Point) -> GameState {
+    let game_move = board
+        .legal_moves()
+        .into_iter()
+        .find(|game_move| matches!(game_move, Move::Move { src: s, dst: d, .. } if *s == src && *d == dst))
+        .unwrap_or_else(|| panic!("No move from {} to {} in {}", src, dst, board.to_fen()));
+    board.try_move(game_move).unwrap()
+}
+
+fn playing(state: GameState) -> Board {
+    match state {
+        GameState::Playing { board } => board,
+        _ => panic!("Expected a game in play"),
+    }
+}
+
+fn blue_ninja_at(board: &Board, pos: Point) -> Option<bool> {
+    board
+        .blue_ninjas
+        .iter()
+        .flatten()
+        .find(|(ninja, _)| *ninja == pos)
+        .map(|(_, revealed)| *revealed)
+}
+
+#[test]
+fn light_setup_uses_light_rules() {
+    let settings = GameSettings {
+        force_light_and_shadow: true,
+        light_and_shadow_mode: Some("Light".to_string()),
+        ..GameSettings::default()
+    };
+    let board = Board::new_with_settings(settings);
+    assert!(board.light_mode && !board.shadow_mode);
+    assert_eq!(board.blue_king, None);
+    assert!(board.blue_pawns.iter().all(|pawn| pawn.is_none()));
+    assert_eq!(board.blue_ninjas.iter().flatten().filter(|(_, revealed)| !revealed).count(), 2);
+    assert!(board.to_fen().ends_with(" light"));
+    assert!(Board::from_fen(&board.to_fen()).unwrap().light_mode);
+}
+
+#[test]
+fn ninjas_move_hidden_without_follow_up() {
+    let start = board("n3n/5/5/5/PPKPP Horse,Ox Boar,Crab Tiger b - light");
+    let after = playing(play(&start, Point { x: 0, y: 0 }, Point { x: 0, y: 1 }));
+    assert_eq!(blue_ninja_at(&after, Point { x: 0, y: 1 }), Some(false));
+    assert!(!after.ninja_move_pending);
+    assert_eq!(after.turn, Player::Red);
+
+    // Asking to reveal does nothing, Ninjas only show themselves by capturing
+    let reveal = Move::Move {
+        card: crate::Card::Boar,
+        src: Point { x: 4, y: 0 },
+        dst: Point { x: 4, y: 1 },
+        reveal_ninja: true,
+    };
+    let after = playing(start.try_move(reveal).unwrap());
+    assert_eq!(blue_ninja_at(&after, Point { x: 4, y: 1 }), Some(false));
+}
+
+#[test]
+fn capturing_reveals_only_that_ninja() {
+    let start = board("4n/5/1n3/1P3/P1K1P Horse,Ox Boar,Crab Tiger b - light");
+    let striker = Point { x: 1, y: 3 };
+    let board = playing(play(&start, Point { x: 1, y: 2 }, striker));
+    assert_eq!(blue_ninja_at(&board, striker), Some(true));
+    assert!(board.red_pawns.iter().flatten().all(|pawn| *pawn != striker));
+    assert_eq!(blue_ninja_at(&board, Point { x: 4, y: 0 }), Some(false));
+
+    // The revealed Ninja stays in sight while the other one moves
+    let board = playing(play(&board, Point { x: 0, y: 4 }, Point { x: 0, y: 3 }));
+    let board = playing(play(&board, Point { x: 4, y: 0 }, Point { x: 4, y: 1 }));
+    assert_eq!(blue_ninja_at(&board, striker), Some(true));
+    assert_eq!(blue_ninja_at(&board, Point { x: 4, y: 1 }), Some(false));
+
+    // And vanishes again once it moves without capturing
+    let board = playing(play(&board, Point { x: 4, y: 4 }, Point { x: 4, y: 3 }));
+    let board = playing(play(&board, striker, Point { x: 1, y: 2 }));
+    assert_eq!(blue_ninja_at(&board, Point { x: 1, y: 2 }), Some(false));
+}
+
+#[test]
+fn light_wins_by_capturing_every_ninja() {
+    let start = board("5/5/5/1n3/1PK2 Horse,Ox Boar,Crab Tiger r - light");
+    match play(&start, Point { x: 1, y: 4 }, Point { x: 1, y: 3 }) {
+        GameState::Finished { winner, board } => {
+            assert_eq!(winner, Player::Red);
+            assert!(board.blue_ninjas.iter().all(|ninja| ninja.is_none()));
+        }
+        _ => panic!("Capturing the last Ninja should end the game"),
+    }
+}
+
+#[test]
+fn light_master_wins_on_shadow_temple() {
+    let start = board("n4/2K2/5/5/P3P Horse,Ox Boar,Crab Tiger r - light");
+    let state = play(&start, Point { x: 2, y: 1 }, Point { x: 2, y: 0 });
+    assert!(matches!(state, GameState::Finished { winner: Player::Red, .. }));
+}
+
+#[test]
+fn shadow_wins_by_capturing_master() {
+    let start = board("4n/5/5/2n2/P1K1P Horse,Ox Boar,Crab Tiger b - light");
+    let state = play(&start, Point { x: 2, y: 3 }, Point { x: 2, y: 4 });
+    assert!(matches!(state, GameState::Finished { winner: Player::Blue, .. }));
+}
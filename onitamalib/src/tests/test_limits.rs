@@ -0,0 +1,51 @@
+use enum_iterator::IntoEnumIterator;
+use rand::prelude::*;
+
+use super::utils;
+use crate::{AiAgent, SearchLimits};
+
+fn budget() -> SearchLimits {
+    SearchLimits {
+        nodes: Some(2_000),
+        simulations: Some(200),
+        seed: Some(7),
+        ..SearchLimits::default()
+    }
+}
+
+#[test]
+fn budgeted_searches_are_reproducible() {
+    for state in utils::generate_expansion_test_states() {
+        for agent in AiAgent::into_enum_iter() {
+            let first = agent.play_move(&state, budget()).expect("No move found");
+            let second = agent.play_move(&state, budget()).expect("No move found");
+            assert_eq!(first, second, "{:?} searched differently", agent);
+        }
+    }
+}
+
+#[test]
+fn searches_stay_within_limits() {
+    let limits = SearchLimits {
+        depth: Some(2),
+        simulations: Some(100),
+        seed: Some(0),
+        ..SearchLimits::default()
+    };
+    for state in utils::generate_test_states() {
+        for agent in [AiAgent::Minimax, AiAgent::Alphabeta].iter() {
+            let report = agent.play_move(&state, limits).expect("No move found");
+            assert!(report.depth.unwrap() <= 2, "{:?} searched to depth {:?}", agent, report.depth);
+        }
+        let report = AiAgent::PureMonteCarlo.play_move(&state, limits).expect("No move found");
+        assert_eq!(report.simulations, Some(100));
+    }
+}
+
+#[test]
+fn unseeded_searches_draw_different_numbers() {
+    let limits = SearchLimits::default();
+    let draws: Vec<u64> = (0..4).map(|_| limits.rng().gen()).collect();
+    assert!(draws.iter().any(|&draw| draw != draws[0]));
+    assert_eq!(budget().rng().gen::<u64>(), budget().rng().gen::<u64>());
+}
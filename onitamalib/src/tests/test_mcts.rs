@@ -0,0 +1,81 @@
+use super::utils;
+use crate::mcts::{Budget, Mcts, MctsConfig, Rollout, Selection};
+use crate::{BitBoard, ForcedWin, GameState};
+
+fn configs() -> Vec<MctsConfig> {
+    let mut configs = vec![];
+    for selection in [Selection::Uct, Selection::Puct].iter().copied() {
+        for rollout in [Rollout::Random, Rollout::Heuristic].iter().copied() {
+            configs.push(MctsConfig {
+                selection,
+                rollout,
+                ..MctsConfig::default()
+            });
+        }
+    }
+    configs
+}
+
+#[test]
+fn mcts_plays_legal_moves() {
+    for state in utils::generate_expansion_test_states() {
+        let board = BitBoard::from(&state.board());
+        for config in configs() {
+            let game_move = Mcts::new(config).search(&board, Budget::Iterations(50)).unwrap().game_move;
+            assert!(board.try_move(game_move).is_ok(), "{:?} is not legal", game_move);
+        }
+    }
+}
+
+#[test]
+fn mcts_takes_winning_moves() {
+    let mut winning_states = 0;
+    for state in utils::generate_test_states() {
+        let board = BitBoard::from(&state.board());
+        let can_win = board
+            .legal_moves()
+            .into_iter()
+            .any(|game_move| state.try_move(game_move).unwrap().finished());
+        if !can_win {
+            continue;
+        }
+        winning_states += 1;
+        // Winning moves are proven as soon as they're expanded, and reported like alphabeta reports them
+        let forced_win = ForcedWin {
+            winner: board.turn,
+            plies: 1,
+        };
+        for config in configs() {
+            let report = Mcts::new(config).search(&board, Budget::Iterations(500)).unwrap();
+            let game_move = report.game_move;
+            assert!(state.try_move(game_move).unwrap().finished(), "Missed a win with {:?}", game_move);
+            assert_eq!(report.forced_win, Some(forced_win));
+        }
+    }
+    assert!(winning_states > 0);
+}
+
+#[test]
+fn mcts_reuses_tree() {
+    let state = utils::generate_test_states()[0];
+    let board = match state {
+        GameState::Playing { board } => board,
+        _ => panic!("Expected a game in play"),
+    };
+    let mut mcts = Mcts::new(MctsConfig::default());
+    let game_move = mcts.search(&BitBoard::from(&board), Budget::Iterations(2000)).unwrap().game_move;
+    let mut next = BitBoard::from(&board);
+    next.apply_move(game_move);
+    let reply = next.legal_moves()[0];
+    next.apply_move(reply);
+    mcts.search(&next, Budget::Iterations(0));
+    assert!(mcts.root_visits() > 0);
+
+    let mut fresh = Mcts::new(MctsConfig {
+        reuse_tree: false,
+        ..MctsConfig::default()
+    });
+    fresh.search(&BitBoard::from(&board), Budget::Iterations(2000));
+    fresh.search(&next, Budget::Iterations(0));
+    assert_eq!(fresh.root_visits(), 0);
+}
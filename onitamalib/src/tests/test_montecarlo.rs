@@ -1,7 +1,10 @@
 use super::utils;
-use crate::{montecarlo, GameState};
+use crate::{montecarlo, BitBoard, GameState};
 use indicatif::ProgressBar;
-use instant::Duration;
+use instant::{Duration, Instant};
+use rand::prelude::*;
+
+const BENCHMARK_PLAYOUTS: usize = 20;
 
 #[test]
 fn montecarlo_benchmark() {
@@ -9,15 +12,61 @@ fn montecarlo_benchmark() {
     let bar = ProgressBar::new(100);
     let test_states = utils::generate_test_states();
     let mut total_simulations = 0u64;
+    let start = Instant::now();
     for state in test_states.into_iter() {
         bar.inc(1);
         let board = match state {
             GameState::Playing { board } => board,
-            GameState::Finished { .. } => panic!("Unexpected finished state"),
+            _ => panic!("Expected a game in play"),
         };
         let moves = board.legal_moves();
         total_simulations += montecarlo::montecarlo_count_simulations(&board, moves, duration);
     }
     bar.finish();
+    let elapsed = start.elapsed().as_secs_f64();
     println!("Total Simulations: {}", total_simulations);
+    println!("Simulations per second: {:.0}", total_simulations as f64 / elapsed);
+}
+
+// How the playouts ran before bitboards, validating each random move on the `Board` through `try_move`
+fn board_playout<R: Rng>(mut state: GameState, rng: &mut R) {
+    for _ in 0..1000 {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Setup { .. } | GameState::Finished { .. } => return,
+        };
+        let mut moves = board.legal_moves();
+        moves.shuffle(rng);
+        state = match moves.into_iter().find_map(|game_move| state.try_move(game_move).ok()) {
+            Some(state) => state,
+            None => return,
+        };
+    }
+}
+
+#[test]
+fn playouts_before_and_after_bitboards() {
+    let test_states = utils::generate_test_states();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let start = Instant::now();
+    for state in test_states.iter() {
+        for _ in 0..BENCHMARK_PLAYOUTS {
+            board_playout(*state, &mut rng);
+        }
+    }
+    let before = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    for state in test_states.iter() {
+        let board = BitBoard::from(&state.board());
+        for _ in 0..BENCHMARK_PLAYOUTS {
+            montecarlo::simulate(board, &mut rng);
+        }
+    }
+    let after = start.elapsed().as_secs_f64();
+
+    let playouts = (test_states.len() * BENCHMARK_PLAYOUTS) as f64;
+    println!("Board playouts per second: {:.0}", playouts / before);
+    println!("BitBoard playouts per second: {:.0}", playouts / after);
+    println!("Speedup: {:.1}x", before / after);
 }
@@ -0,0 +1,55 @@
+use super::utils;
+use crate::{Card, GameState, Move, MoveError, Point};
+
+#[test]
+fn card_not_in_hand() {
+    let board = match utils::generate_test_states().remove(0) {
+        GameState::Playing { board } => board,
+        _ => panic!("Expected a game in play"),
+    };
+    let card = [Card::Tiger, Card::Dragon, Card::Frog, Card::Rabbit, Card::Crab, Card::Elephant]
+        .iter()
+        .copied()
+        .find(|card| !board.red_hand.contains(card) && !board.blue_hand.contains(card))
+        .unwrap();
+    let src = board.red_king.unwrap();
+    let game_move = Move::Move {
+        card,
+        src,
+        dst: Point { x: src.x, y: src.y - 1 },
+        reveal_ninja: false,
+    };
+    let err = board.try_move(game_move).unwrap_err();
+    assert_eq!(err, MoveError::CardNotInHand { card });
+    assert_eq!(err.to_string(), "Card not in hand");
+}
+
+#[test]
+fn finished_game_rejects_moves() {
+    let finished = utils::generate_test_states().into_iter().find_map(|state| {
+        let board = match state {
+            GameState::Playing { board } => board,
+            _ => return None,
+        };
+        board
+            .legal_moves()
+            .into_iter()
+            .map(|game_move| (board.try_move(game_move).unwrap(), game_move))
+            .find(|(state, _)| state.finished())
+    });
+    let (state, game_move) = finished.expect("No finishing move in test states");
+    let err = state.try_move(game_move).unwrap_err();
+    assert_eq!(err, MoveError::GameFinished);
+    assert_eq!(err.to_string(), "Game already finished");
+}
+
+#[test]
+fn round_trips_with_data() {
+    let err = MoveError::WrongNinjaCard {
+        card: Card::Tiger,
+        required: Card::Crab,
+    };
+    let bytes = serde_cbor::to_vec(&err).unwrap();
+    assert_eq!(serde_cbor::from_slice::<MoveError>(&bytes).unwrap(), err);
+    assert_eq!(err.to_string(), "Must use the Crab card for the Ninja move.");
+}
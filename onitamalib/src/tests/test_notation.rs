@@ -0,0 +1,72 @@
+use super::utils;
+use crate::{Card, GameState, Move, NotationError, Player, Point};
+
+#[test]
+fn legal_moves_round_trip() {
+    let states = utils::generate_test_states()
+        .into_iter()
+        .chain(utils::generate_expansion_test_states());
+    for state in states {
+        let board = match state {
+            GameState::Playing { board } => board,
+            _ => panic!("Expected a game in play"),
+        };
+        for game_move in board.legal_moves() {
+            let revealed = match game_move {
+                Move::Move { card, src, dst, .. } => Move::Move {
+                    card,
+                    src,
+                    dst,
+                    reveal_ninja: true,
+                },
+                discard => discard,
+            };
+            for game_move in [game_move, revealed] {
+                let text = game_move.to_string();
+                assert_eq!(text.parse::<Move>(), Ok(game_move), "{}", text);
+            }
+        }
+    }
+}
+
+#[test]
+fn formats_moves() {
+    let game_move = Move::Move {
+        card: Card::Tiger,
+        src: Point { x: 2, y: 4 },
+        dst: Point { x: 2, y: 2 },
+        reveal_ninja: false,
+    };
+    assert_eq!(game_move.to_string(), "Tiger c1-c3");
+    let game_move = Move::Move {
+        card: Card::Eagle,
+        src: Point { x: 1, y: 3 },
+        dst: Point { x: 2, y: 2 },
+        reveal_ninja: true,
+    };
+    assert_eq!(game_move.to_string(), "Eagle b2-c3*");
+    assert_eq!(Move::Discard { card: Card::Frog }.to_string(), "Discard Frog");
+    let placement = Move::PlaceNinja {
+        player: Player::Blue,
+        dst: Point { x: 1, y: 0 },
+    };
+    assert_eq!(placement.to_string(), "Place Blue b5");
+    assert_eq!("place blue b5".parse::<Move>().unwrap(), placement);
+}
+
+#[test]
+fn rejects_bad_notation() {
+    assert_eq!(
+        "Tiger f1-a2".parse::<Move>(),
+        Err(NotationError::InvalidSquare("f1".to_string()))
+    );
+    assert_eq!(
+        "Unicorn a1-a2".parse::<Move>(),
+        Err(NotationError::UnknownCard("Unicorn".to_string()))
+    );
+    assert_eq!(
+        "Tiger a1a2".parse::<Move>(),
+        Err(NotationError::InvalidMove("Tiger a1a2".to_string()))
+    );
+    assert!("Discard".parse::<Move>().is_err());
+}
@@ -0,0 +1,166 @@
+use std::thread;
+use std::time::Duration;
+
+use super::utils;
+use crate::agents::parallel::{LazySmpAgent, LazySmpHybridAgent, Ponderer, RootParallelMctsAgent};
+use crate::alphabeta::AlphabetaConfig;
+use crate::mcts::MctsConfig;
+use crate::montecarlo::HybridMonteCarloConfig;
+use crate::{AiAgent, BeliefAgent, ForcedWin, NinjaBelief, SearchLimits};
+
+const THREADS: usize = 3;
+
+#[test]
+fn parallel_agents_play_legal_moves() {
+    let limits = SearchLimits {
+        nodes: Some(2_000),
+        simulations: Some(100),
+        ..SearchLimits::default()
+    };
+    let mut agents: Vec<Box<dyn BeliefAgent>> = vec![
+        Box::new(LazySmpAgent::new(AlphabetaConfig::default(), THREADS)),
+        Box::new(RootParallelMctsAgent::new(MctsConfig::default(), THREADS)),
+        Box::new(LazySmpHybridAgent::new(HybridMonteCarloConfig::default(), THREADS)),
+    ];
+    for state in utils::generate_expansion_test_states() {
+        let board = state.board();
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        for agent in agents.iter_mut() {
+            agent.new_game();
+            let report = agent.choose_move_with_belief(&state, &belief, limits).expect("No move found");
+            assert!(state.try_move(report.game_move).is_ok(), "{:?} is not legal", report.game_move);
+        }
+    }
+}
+
+#[test]
+fn lazy_smp_takes_the_fastest_win() {
+    let limits = SearchLimits {
+        depth: Some(4),
+        ..SearchLimits::default()
+    };
+    let mut agent = LazySmpAgent::new(AlphabetaConfig::default(), THREADS);
+    let mut winning_states = 0;
+    for state in utils::generate_test_states() {
+        let board = state.board();
+        let can_win = board
+            .legal_moves()
+            .into_iter()
+            .any(|game_move| state.try_move(game_move).unwrap().finished());
+        if !can_win {
+            continue;
+        }
+        winning_states += 1;
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        let report = agent.choose_move_with_belief(&state, &belief, limits).expect("No move found");
+        let forced_win = ForcedWin {
+            winner: board.turn,
+            plies: 1,
+        };
+        assert_eq!(report.forced_win, Some(forced_win));
+        assert!(state.try_move(report.game_move).unwrap().finished());
+    }
+    assert!(winning_states > 0);
+}
+
+#[test]
+fn lazy_smp_threads_share_the_node_budget() {
+    const NODES: u64 = 2_000;
+    let limits = SearchLimits {
+        nodes: Some(NODES),
+        ..SearchLimits::default()
+    };
+    for state in utils::generate_test_states() {
+        let board = state.board();
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        let mut agent = LazySmpAgent::new(AlphabetaConfig::default(), THREADS);
+        let report = agent.choose_move_with_belief(&state, &belief, limits).expect("No move found");
+        // Reports count every thread's nodes, each may finish the position it was in once the budget runs out
+        assert!(report.nodes <= NODES + NODES / 10, "Searched {} nodes", report.nodes);
+    }
+}
+
+#[test]
+fn lazy_smp_hybrid_ranks_every_move() {
+    let limits = SearchLimits {
+        nodes: Some(2_000),
+        simulations: Some(100),
+        ..SearchLimits::default()
+    };
+    let mut agent = LazySmpHybridAgent::new(HybridMonteCarloConfig::default(), THREADS);
+    for state in utils::generate_test_states().into_iter().take(10) {
+        let board = state.board();
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        let ranking = agent.rank_moves_with_belief(&state, &belief, limits).expect("No move found");
+        let moves: Vec<_> = ranking.ranked_moves.iter().map(|(game_move, _)| *game_move).collect();
+        assert_eq!(moves, board.legal_moves());
+        assert!(moves.contains(&ranking.report.game_move));
+    }
+}
+
+#[test]
+fn root_parallel_mcts_adds_up_every_tree() {
+    let limits = SearchLimits {
+        simulations: Some(200),
+        seed: Some(3),
+        ..SearchLimits::default()
+    };
+    let mut agent = RootParallelMctsAgent::new(MctsConfig::default(), THREADS);
+    let state = utils::generate_test_states()[0];
+    let board = state.board();
+    let belief = NinjaBelief::unknown(&board, board.turn);
+    let ranking = agent.rank_moves_with_belief(&state, &belief, limits).expect("No move found");
+    assert_eq!(ranking.report.simulations, Some(200 * THREADS as u64));
+    assert_eq!(ranking.ranked_moves.len(), board.legal_moves().len());
+}
+
+#[test]
+fn root_parallel_mcts_reports_forced_wins() {
+    let limits = SearchLimits {
+        simulations: Some(200),
+        seed: Some(3),
+        ..SearchLimits::default()
+    };
+    let mut agent = RootParallelMctsAgent::new(MctsConfig::default(), THREADS);
+    let mut winning_states = 0;
+    for state in utils::generate_test_states() {
+        let board = state.board();
+        let can_win = board
+            .legal_moves()
+            .into_iter()
+            .any(|game_move| state.try_move(game_move).unwrap().finished());
+        if !can_win {
+            continue;
+        }
+        winning_states += 1;
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        let ranking = agent.rank_moves_with_belief(&state, &belief, limits).expect("No move found");
+        let forced_win = ForcedWin {
+            winner: board.turn,
+            plies: 1,
+        };
+        assert_eq!(ranking.report.forced_win, Some(forced_win));
+        assert!(state.try_move(ranking.report.game_move).unwrap().finished());
+    }
+    assert!(winning_states > 0);
+}
+
+#[test]
+fn ponderers_hand_back_agents_that_still_play() {
+    let limits = SearchLimits {
+        nodes: Some(2_000),
+        simulations: Some(100),
+        ..SearchLimits::default()
+    };
+    let state = utils::generate_test_states()[0];
+    let board = state.board();
+    for ai in [AiAgent::Alphabeta, AiAgent::HybridMonteCarlo, AiAgent::MonteCarloTreeSearch].iter() {
+        let mut agent = ai.build_parallel(THREADS);
+        agent.new_game(&state, board.turn);
+        let ponderer = Ponderer::start(agent, state);
+        thread::sleep(Duration::from_millis(50));
+        let mut agent = ponderer.stop();
+        let report = agent.choose_move(&state, limits).expect("No move found");
+        assert!(state.try_move(report.game_move).is_ok(), "{:?} is not legal", report.game_move);
+    }
+}
@@ -0,0 +1,136 @@
+use rand::prelude::*;
+
+use crate::record::{GameRecord, RecordError, ReplayError, BLUE_AGENT_TAG, EVENT_TAG, RED_TAG};
+use crate::messages::GameMessage;
+use crate::{Card, GameSettings, GameState, Move, MoveError, Player, Point};
+
+fn random_game(settings: GameSettings, rng: &mut SmallRng) -> GameRecord {
+    let mut state = GameState::new_with_settings(settings.clone());
+    for player in [Player::Red, Player::Blue] {
+        for placement in state.random_ninja_placements(player, rng) {
+            state = state.try_move(placement).expect("Generated illegal placement");
+        }
+    }
+    let mut record = GameRecord::new(settings, state.board());
+    while let GameState::Playing { board } = state {
+        let game_move = board.random_legal_move(rng).expect("No legal moves");
+        state = state.try_move(game_move).expect("Generated illegal move");
+        record.push(game_move, &state);
+    }
+    record
+}
+
+fn expansion_settings(mode: &str) -> GameSettings {
+    GameSettings {
+        number_of_wind_cards: Some(2),
+        force_wind_spirit_inclusion: true,
+        force_light_and_shadow: true,
+        light_and_shadow_mode: Some(mode.to_string()),
+        ..GameSettings::default()
+    }
+}
+
+#[test]
+fn records_round_trip_and_replay() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let seeded = GameSettings {
+        seed: Some(7),
+        ..GameSettings::default()
+    };
+    let settings = [seeded, expansion_settings("Light"), expansion_settings("Shadow")];
+    for settings in settings.iter() {
+        for _ in 0..20 {
+            let mut record = random_game(settings.clone(), &mut rng);
+            record.set_tag(EVENT_TAG, "Test \"quoted\" \\ event");
+            record.set_tag(RED_TAG, "alice");
+            record.set_tag(BLUE_AGENT_TAG, "Random");
+
+            let text = record.to_string();
+            let parsed: GameRecord = text.parse().unwrap_or_else(|err| panic!("{}\n{}", err, text));
+            assert_eq!(parsed.to_string(), text);
+            assert_eq!(parsed.moves, record.moves);
+            assert_eq!(parsed.tags, record.tags);
+            assert_eq!(parsed.result, record.result);
+            assert_eq!(parsed.settings.seed, record.settings.seed);
+
+            let final_state = parsed.replay().unwrap_or_else(|err| panic!("{}\n{}", err, text));
+            assert!(final_state.finished());
+        }
+    }
+}
+
+#[test]
+fn replay_reports_first_illegal_move() {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let mut record = random_game(GameSettings::default(), &mut rng);
+    let illegal = Move::Move {
+        card: Card::Tiger,
+        src: Point { x: 2, y: 2 },
+        dst: Point { x: 2, y: 0 },
+        reveal_ninja: false,
+    };
+    record.moves.insert(1, illegal);
+    match record.replay() {
+        Err(ReplayError::IllegalMove { ply, game_move, error }) => {
+            assert_eq!(ply, 2);
+            assert_eq!(game_move, illegal);
+            assert_ne!(error, MoveError::GameFinished);
+        }
+        other => panic!("Expected an illegal move, got {:?}", other),
+    }
+}
+
+#[test]
+fn replay_checks_result() {
+    let mut rng = SmallRng::seed_from_u64(2);
+    let mut record = random_game(GameSettings::default(), &mut rng);
+    let winner = record.result;
+    record.moves.pop();
+    record.result = winner;
+    assert_eq!(
+        record.replay().unwrap_err(),
+        ReplayError::ResultMismatch { recorded: winner, actual: None }
+    );
+}
+
+#[test]
+fn rejects_invalid_records() {
+    let position = "[Position \"ppkpp/5/5/5/PPKPP Tiger,Dragon Frog,Crab Eel r - -\"]";
+    let settings = "[DisabledCardSets \"\"]\n[NumberOfWindCards \"-\"]\n[ForceWindSpiritInclusion \"false\"]\n\
+        [EnableLightAndShadow \"false\"]\n[ForceLightAndShadow \"false\"]\n[LightAndShadowMode \"-\"]";
+    let valid = format!("[Result \"*\"]\n{}\n{}\n\n1. Tiger c1-c3\n", position, settings);
+    let record: GameRecord = valid.parse().unwrap();
+    assert_eq!(record.moves.len(), 1);
+    assert!(record.replay().is_ok());
+
+    let missing = format!("{}\n{}\n", position, settings);
+    assert_eq!(missing.parse::<GameRecord>().unwrap_err(), RecordError::MissingTag("Result"));
+    let result = format!("[Result \"Green\"]\n{}\n{}\n", position, settings);
+    assert_eq!(
+        result.parse::<GameRecord>().unwrap_err(),
+        RecordError::InvalidResult("Green".to_string())
+    );
+    let tag = format!("[Result *]\n{}\n{}\n", position, settings);
+    assert_eq!(
+        tag.parse::<GameRecord>().unwrap_err(),
+        RecordError::InvalidTag("[Result *]".to_string())
+    );
+    let bad_move = format!("{}\n1. Tiger c1-c9\n", valid);
+    assert!(matches!(
+        bad_move.parse::<GameRecord>().unwrap_err(),
+        RecordError::InvalidMove { line: 12, .. }
+    ));
+}
+
+#[test]
+fn records_survive_the_server_message() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    let record = random_game(expansion_settings("Shadow"), &mut rng);
+    let msg = serde_cbor::to_vec(&GameMessage::Record { record: record.clone() }).expect("Failed to serialize");
+    let received = match serde_cbor::from_slice(&msg).expect("Failed to deserialize") {
+        GameMessage::Record { record } => record,
+        msg => panic!("Unexpected message {:?}", msg),
+    };
+    assert_eq!(received.to_string(), record.to_string());
+    assert!(received.replay().expect("Received record doesn't replay").finished());
+}
@@ -0,0 +1,132 @@
+use rand::prelude::*;
+
+use crate::{Board, GameSettings, GameState, Move, MoveError, Player, Point};
+
+fn settings_with_seed(seed: u64) -> GameSettings {
+    GameSettings {
+        seed: Some(seed),
+        ..GameSettings::default()
+    }
+}
+
+fn mode_settings(mode: &str) -> GameSettings {
+    GameSettings {
+        force_light_and_shadow: true,
+        light_and_shadow_mode: Some(mode.to_string()),
+        ..GameSettings::default()
+    }
+}
+
+#[test]
+fn seeded_setups_are_reproducible() {
+    for seed in 0..100 {
+        let board = Board::new_with_settings(settings_with_seed(seed));
+        assert_eq!(Board::new_with_settings(settings_with_seed(seed)).to_fen(), board.to_fen());
+        let state = GameState::new_with_settings(settings_with_seed(seed));
+        let again = GameState::new_with_settings(settings_with_seed(seed));
+        assert_eq!(state.board().to_fen(), again.board().to_fen());
+        assert_eq!(state.ninjas_to_place(Player::Red), again.ninjas_to_place(Player::Red));
+    }
+    let setups: Vec<String> = (0..10)
+        .map(|seed| Board::new_with_settings(settings_with_seed(seed)).to_fen())
+        .collect();
+    assert!(setups.iter().any(|fen| *fen != setups[0]));
+}
+
+#[test]
+fn setups_follow_rng() {
+    let settings = GameSettings {
+        force_wind_spirit_inclusion: true,
+        force_light_and_shadow: true,
+        ..GameSettings::default()
+    };
+    let mut rng = SmallRng::seed_from_u64(0);
+    let boards: Vec<Board> = (0..50)
+        .map(|_| Board::new_with_settings_rng(settings.clone(), &mut rng))
+        .collect();
+    let mut rng = SmallRng::seed_from_u64(0);
+    for board in boards.iter() {
+        assert_eq!(Board::new_with_settings_rng(settings.clone(), &mut rng).to_fen(), board.to_fen());
+        assert!(board.wind_spirit.is_some());
+        assert!(board.shadow_mode || board.blue_king.is_none(), "{}", board.to_fen());
+        let ninjas = board.red_ninjas.iter().chain(board.blue_ninjas.iter()).flatten().count();
+        assert_eq!(ninjas, 2, "{}", board.to_fen());
+    }
+}
+
+#[test]
+fn base_game_skips_setup() {
+    let settings = GameSettings {
+        enable_light_and_shadow: false,
+        ..GameSettings::default()
+    };
+    assert!(matches!(GameState::new_with_settings(settings), GameState::Playing { .. }));
+}
+
+#[test]
+fn shadow_setup_places_one_ninja_each() {
+    let state = GameState::new_with_settings(mode_settings("Shadow"));
+    assert_eq!(state.ninjas_to_place(Player::Red), 1);
+    assert_eq!(state.ninjas_to_place(Player::Blue), 1);
+    assert_eq!(state.board().ninja_placements(Player::Red), vec![Point { x: 1, y: 4 }, Point { x: 3, y: 4 }]);
+
+    let card = state.board().red_hand[0];
+    let moved = Move::Move { card, src: Point { x: 0, y: 4 }, dst: Point { x: 0, y: 3 }, reveal_ninja: false };
+    assert_eq!(state.try_move(moved).unwrap_err(), MoveError::SetupInProgress);
+    let off_row = Move::PlaceNinja { player: Player::Red, dst: Point { x: 1, y: 3 } };
+    assert_eq!(
+        state.try_move(off_row).unwrap_err(),
+        MoveError::InvalidNinjaPlacement { dst: Point { x: 1, y: 3 } }
+    );
+    let on_pawn = Move::PlaceNinja { player: Player::Red, dst: Point { x: 0, y: 4 } };
+    assert!(state.try_move(on_pawn).is_err());
+
+    // Placement order between the players does not matter
+    let state = state.try_move(Move::PlaceNinja { player: Player::Blue, dst: Point { x: 3, y: 0 } }).unwrap();
+    let again = Move::PlaceNinja { player: Player::Blue, dst: Point { x: 1, y: 0 } };
+    assert_eq!(state.try_move(again).unwrap_err(), MoveError::NoNinjaToPlace { player: Player::Blue });
+    let state = state.try_move(Move::PlaceNinja { player: Player::Red, dst: Point { x: 1, y: 4 } }).unwrap();
+    let board = match state {
+        GameState::Playing { board } => board,
+        _ => panic!("Setup should be finished"),
+    };
+    assert_eq!(board.red_ninjas, [Some((Point { x: 1, y: 4 }, false)), None]);
+    assert_eq!(board.blue_ninjas, [Some((Point { x: 3, y: 0 }, false)), None]);
+    assert_eq!(board.turn, Player::Red);
+    let late = Move::PlaceNinja { player: Player::Red, dst: Point { x: 3, y: 4 } };
+    assert_eq!(board.try_move(late).unwrap_err(), MoveError::NotInSetup);
+}
+
+#[test]
+fn light_setup_places_two_blue_ninjas() {
+    let state = GameState::new_with_settings(mode_settings("Light"));
+    assert_eq!(state.ninjas_to_place(Player::Red), 0);
+    assert_eq!(state.ninjas_to_place(Player::Blue), 2);
+    assert_eq!(state.board().ninja_placements(Player::Blue).len(), 5);
+
+    let red = Move::PlaceNinja { player: Player::Red, dst: Point { x: 1, y: 4 } };
+    assert_eq!(state.try_move(red).unwrap_err(), MoveError::NoNinjaToPlace { player: Player::Red });
+    let first = Move::PlaceNinja { player: Player::Blue, dst: Point { x: 2, y: 0 } };
+    let state = state.try_move(first).unwrap();
+    assert_eq!(state.ninjas_to_place(Player::Blue), 1);
+    assert!(state.try_move(first).is_err());
+    let state = state.try_move(Move::PlaceNinja { player: Player::Blue, dst: Point { x: 4, y: 0 } }).unwrap();
+    assert!(matches!(state, GameState::Playing { .. }));
+}
+
+#[test]
+fn random_ninjas_finish_setup() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    for mode in ["Light", "Shadow"] {
+        let state = GameState::new_with_settings(mode_settings(mode));
+        let state = state.with_random_ninjas(Player::Red, &mut rng);
+        assert_eq!(state.ninjas_to_place(Player::Red), 0);
+        let board = match state.with_random_ninjas(Player::Blue, &mut rng) {
+            GameState::Playing { board } => board,
+            _ => panic!("Setup should be finished"),
+        };
+        let blue_ninjas = board.blue_ninjas.iter().flatten();
+        assert!(blue_ninjas.clone().all(|(pos, revealed)| pos.y == 0 && !revealed));
+        assert_eq!(blue_ninjas.count(), if mode == "Light" { 2 } else { 1 });
+    }
+}
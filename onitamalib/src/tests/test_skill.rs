@@ -0,0 +1,91 @@
+use enum_iterator::IntoEnumIterator;
+use rand::prelude::*;
+
+use super::utils;
+use crate::{AiAgent, NinjaBelief, Player, SearchLimits, SkillLevel, SkillLevelError};
+
+#[test]
+fn skilled_agents_play_legal_moves() {
+    let limits = SearchLimits {
+        nodes: Some(2_000),
+        simulations: Some(200),
+        seed: Some(5),
+        ..SearchLimits::default()
+    };
+    for state in utils::generate_expansion_test_states() {
+        let board = state.board();
+        let belief = NinjaBelief::unknown(&board, board.turn);
+        for agent in AiAgent::into_enum_iter() {
+            for &level in [1, 10, 20].iter() {
+                let report = agent
+                    .play_move_at_skill(&state, &belief, SkillLevel::new(level).unwrap(), limits)
+                    .expect("No move found");
+                assert!(state.try_move(report.game_move).is_ok(), "{:?} is not legal", report.game_move);
+            }
+        }
+    }
+}
+
+#[test]
+fn lower_skill_levels_search_less_and_blunder_more() {
+    let limits = SearchLimits::default();
+    assert_eq!(SkillLevel::MAX.limits(limits), limits);
+    let lowest = SkillLevel::MIN.limits(limits);
+    assert_eq!(lowest.depth, Some(1));
+    for level in SkillLevel::MIN.level()..SkillLevel::MAX.level() {
+        let (weaker, stronger) = (SkillLevel::new(level).unwrap(), SkillLevel::new(level + 1).unwrap());
+        assert!(weaker.limits(limits).nodes < stronger.limits(limits).nodes.or(Some(u64::MAX)));
+        assert!(weaker.noise() > stronger.noise());
+        assert!(weaker.blunder_probability() > stronger.blunder_probability());
+    }
+    assert_eq!(SkillLevel::MAX.noise(), 0.0);
+    assert_eq!(SkillLevel::MAX.blunder_probability(), 0.0);
+}
+
+#[test]
+fn skill_levels_outside_the_range_are_rejected() {
+    assert_eq!(SkillLevel::new(1), Ok(SkillLevel::MIN));
+    assert_eq!(SkillLevel::new(0), Err(SkillLevelError::OutOfRange(0)));
+    assert_eq!(SkillLevel::new(21), Err(SkillLevelError::OutOfRange(21)));
+    assert_eq!("255".parse::<SkillLevel>(), Err(SkillLevelError::OutOfRange(255)));
+    assert_eq!("1000".parse::<SkillLevel>(), Err(SkillLevelError::OutOfRange(1000)));
+    assert_eq!("-3".parse::<SkillLevel>(), Err(SkillLevelError::NotANumber("-3".to_string())));
+    assert!(serde_json::from_str::<SkillLevel>("0").is_err());
+    assert_eq!(serde_json::from_str::<SkillLevel>("20").unwrap(), SkillLevel::MAX);
+}
+
+#[test]
+fn skill_levels_pick_from_ranked_moves() {
+    let state = utils::generate_test_states()[0];
+    let board = state.board();
+    let ranked_moves: Vec<_> = board
+        .legal_moves()
+        .into_iter()
+        .zip(0..)
+        .map(|(game_move, score)| (game_move, score * 100))
+        .collect();
+    let (best, _) = *ranked_moves.last().unwrap();
+    let mut rng = SmallRng::seed_from_u64(0);
+    for _ in 0..100 {
+        let (game_move, _) = SkillLevel::MAX.pick(&ranked_moves, Player::Red, &mut rng).unwrap();
+        assert_eq!(game_move, best);
+    }
+    let weakest_picks = (0..100)
+        .filter(|_| SkillLevel::MIN.pick(&ranked_moves, Player::Red, &mut rng).unwrap().0 != best)
+        .count();
+    assert!(weakest_picks > 0);
+}
+
+#[test]
+fn difficulties_split_into_names_and_skill_levels() {
+    let seven = SkillLevel::new(7).unwrap();
+    assert_eq!(SkillLevel::from_difficulty("hard"), Ok(("hard", SkillLevel::MAX)));
+    assert_eq!(SkillLevel::from_difficulty("hard:7"), Ok(("hard", seven)));
+    assert_eq!(SkillLevel::from_difficulty("7"), Ok(("", seven)));
+    assert_eq!(SkillLevel::from_difficulty("0"), Err(SkillLevelError::OutOfRange(0)));
+    assert_eq!(SkillLevel::from_difficulty("255"), Err(SkillLevelError::OutOfRange(255)));
+    assert_eq!(
+        SkillLevel::from_difficulty("hard:high"),
+        Err(SkillLevelError::NotANumber("high".to_string()))
+    );
+}
@@ -0,0 +1,41 @@
+use super::utils;
+use crate::evaluation::{EvalWeights, Evaluator, Features};
+use crate::tuning::{fit_scale, fit_weights, prediction_error, Sample};
+use crate::{BitBoard, Player};
+
+// Results as if the games were decided by material alone
+fn material_samples() -> Vec<Sample> {
+    let evaluator = Evaluator::new(EvalWeights::material());
+    utils::generate_test_states()
+        .into_iter()
+        .chain(utils::generate_expansion_test_states())
+        .map(|state| {
+            let board = BitBoard::from(&state.board());
+            let winner = match evaluator.evaluate(&board) {
+                value if value > 0 => Some(Player::Red),
+                value if value < 0 => Some(Player::Blue),
+                _ => None,
+            };
+            Sample::new(Features::new(&board), winner)
+        })
+        .collect()
+}
+
+#[test]
+fn scale_is_positive() {
+    let samples = material_samples();
+    let scale = fit_scale(&samples, &EvalWeights::material());
+    assert!(scale.is_finite() && scale > 0.0);
+    // A better scale than an arbitrary one
+    let error = prediction_error(&samples, &EvalWeights::material(), scale);
+    assert!(error <= prediction_error(&samples, &EvalWeights::material(), 0.01));
+}
+
+#[test]
+fn fitting_reduces_error() {
+    let samples = material_samples();
+    let initial = EvalWeights::default();
+    let scale = fit_scale(&samples, &initial);
+    let tuned = fit_weights(&samples, &initial, scale, 200);
+    assert!(prediction_error(&samples, &tuned, scale) < prediction_error(&samples, &initial, scale));
+}
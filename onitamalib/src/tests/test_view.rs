@@ -0,0 +1,90 @@
+use super::utils;
+use crate::bitboard::{bit, side};
+use crate::{BitBoard, Board, GameState, Move, Player};
+
+fn expansion_boards() -> Vec<Board> {
+    utils::generate_expansion_test_states()
+        .into_iter()
+        .map(|state| match state {
+            GameState::Playing { board } => board,
+            _ => panic!("Expected a game in play"),
+        })
+        .collect()
+}
+
+#[test]
+fn views_hide_only_opponent_hidden_ninjas() {
+    for board in expansion_boards() {
+        let full = BitBoard::from(&board);
+        for player in [Player::Red, Player::Blue].iter().copied() {
+            let view = BitBoard::from(&board.view_for(player));
+            let (own, opponent) = (side(player), 1 - side(player));
+            assert_eq!(view.ninjas[own], full.ninjas[own]);
+            assert_eq!(view.revealed_ninjas[own], full.revealed_ninjas[own]);
+            assert_eq!(view.ninjas[opponent], full.revealed_ninjas[opponent]);
+            assert_eq!(view.pawns, full.pawns);
+            assert_eq!(view.kings, full.kings);
+        }
+    }
+}
+
+#[test]
+fn views_accept_the_same_moves() {
+    for board in expansion_boards() {
+        let view = board.view_for(board.turn);
+        for game_move in board.legal_moves() {
+            assert!(view.try_move(game_move).is_ok(), "{:?} rejected in view", game_move);
+        }
+        for game_move in view.legal_moves() {
+            assert!(board.try_move(game_move).is_ok(), "{:?} rejected in game", game_move);
+        }
+    }
+}
+
+#[test]
+fn hidden_ninja_moves_are_not_shown() {
+    let mut hidden_moves = 0;
+    for board in expansion_boards() {
+        let bits = BitBoard::from(&board);
+        let player = side(board.turn);
+        for game_move in board.legal_moves() {
+            assert_eq!(board.move_view_for(game_move, board.turn), Some(game_move));
+            let src = match game_move {
+                Move::Move { src, .. } => src,
+                _ => {
+                    assert_eq!(board.move_view_for(game_move, board.turn.invert()), Some(game_move));
+                    continue;
+                }
+            };
+            if bits.ninjas[player] & bit(src) == 0 {
+                assert_eq!(board.move_view_for(game_move, board.turn.invert()), Some(game_move));
+            } else if bits.revealed_ninjas[player] & bit(src) == 0 {
+                hidden_moves += 1;
+                assert_eq!(board.move_view_for(game_move, board.turn.invert()), None);
+            }
+        }
+    }
+    assert!(hidden_moves > 0);
+}
+
+#[test]
+fn placements_are_not_shown() {
+    let game_move = Move::PlaceNinja {
+        player: Player::Blue,
+        dst: crate::Point { x: 1, y: 0 },
+    };
+    let board = Board::new();
+    assert_eq!(board.move_view_for(game_move, Player::Red), None);
+    assert_eq!(board.move_view_for(game_move, Player::Blue), Some(game_move));
+}
+
+#[test]
+fn finished_games_are_not_redacted() {
+    for board in expansion_boards() {
+        let state = GameState::Finished {
+            winner: Player::Red,
+            board,
+        };
+        assert_eq!(state.view_for(Player::Blue).board().to_fen(), board.to_fen());
+    }
+}
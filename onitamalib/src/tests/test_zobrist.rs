@@ -0,0 +1,61 @@
+use rand::prelude::*;
+
+use super::utils;
+use crate::agents::transposition::{Bound, TableEntry, TranspositionTable};
+use crate::{zobrist, BitBoard, Board, GameState};
+
+fn assert_incremental_hash(board: &Board, rng: &mut SmallRng) {
+    let mut bits = BitBoard::from(board);
+    for _ in 0..200 {
+        assert_eq!(bits.hash, zobrist::hash(&bits));
+        let game_move = match bits.random_legal_move(rng) {
+            Some(game_move) => game_move,
+            None => return,
+        };
+        let (next, winner) = bits.try_move(game_move).expect("Generated illegal move");
+        assert_ne!(next.hash, bits.hash, "{:?} did not change the hash", game_move);
+        if winner.is_some() {
+            return;
+        }
+        bits = next;
+    }
+}
+
+#[test]
+fn incremental_hash_matches_full_hash() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    for state in utils::generate_test_states() {
+        if let GameState::Playing { board } = state {
+            assert_incremental_hash(&board, &mut rng);
+        }
+    }
+}
+
+#[test]
+fn incremental_hash_matches_full_hash_with_expansions() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    for state in utils::generate_expansion_test_states() {
+        if let GameState::Playing { board } = state {
+            assert_incremental_hash(&board, &mut rng);
+        }
+    }
+}
+
+#[test]
+fn transposition_table_prefers_deeper_entries() {
+    let mut table = TranspositionTable::new(4);
+    let entry = |hash, depth| TableEntry {
+        hash,
+        depth,
+        value: depth as i64,
+        bound: Bound::Exact,
+        best_move: None,
+    };
+    table.store(entry(1, 3));
+    table.store(entry(1, 2));
+    assert_eq!(table.probe(1).map(|entry| entry.depth), Some(3));
+    // A different position sharing the slot replaces it
+    table.store(entry(17, 1));
+    assert!(table.probe(1).is_none());
+    assert_eq!(table.probe(17).map(|entry| entry.depth), Some(1));
+}
@@ -1,17 +1,26 @@
-use enum_iterator::IntoEnumIterator;
 use rand::prelude::*;
 
-use crate::{Board, Card, GameState, Player, Point};
+use crate::{Board, GameSettings, GameState};
 
 const SAMPLES: usize = 100;
 pub fn generate_test_states() -> Vec<GameState> {
     let mut states: Vec<GameState> = vec![];
     let mut rng = SmallRng::seed_from_u64(0);
+    // Base game only, the expansions have their own states below
+    let settings = GameSettings {
+        disabled_card_sets: vec!["WayOfTheWind".to_string()],
+        enable_light_and_shadow: false,
+        ..GameSettings::default()
+    };
     while states.len() < SAMPLES {
-        let mut state = GameState::new_rng(&mut rng);
+        let mut state = GameState::Playing {
+            board: Board::new_with_settings_rng(settings.clone(), &mut rng),
+        };
         while let GameState::Playing { board } = state {
             states.push(state);
-            let game_move = board.random_legal_move(&mut rng);
+            let game_move = board
+                .random_legal_move(&mut rng)
+                .expect("No legal moves");
             state = state.try_move(game_move).expect("Generated illegal move");
         }
     }
@@ -19,31 +28,31 @@ pub fn generate_test_states() -> Vec<GameState> {
     return states;
 }
 
-impl GameState {
-    pub fn new_rng<R: Rng>(rng: &mut R) -> GameState {
-        GameState::Playing {
-            board: Board::new_rng(rng),
-        }
-    }
-}
-
-impl Board {
-    pub fn new_rng<R: Rng>(rng: &mut R) -> Board {
-        let mut cards: Vec<Card> = Card::into_enum_iter().collect();
-        cards.shuffle(rng);
-        let mut cards = cards.into_iter();
-        let pawn_xs: [i8; 4] = [0, 1, 3, 4];
-        Board {
-            wind_spirit: Point { x: 2, y: 2 },
-            blue_king: Point { x: 2, y: 0 },
-            blue_pawns: pawn_xs.map(|x| Some(Point { x, y: 0 })),
-
-            blue_hand: [cards.next().unwrap(), cards.next().unwrap()],
-            red_king: Point { x: 2, y: 4 },
-            red_pawns: pawn_xs.map(|x| Some(Point { x, y: 4 })),
-            red_hand: [cards.next().unwrap(), cards.next().unwrap()],
-            spare_card: cards.next().unwrap(),
-            turn: Player::Red,
+/// Positions from games with the Wind Spirit and Light or Shadow rules enabled
+pub fn generate_expansion_test_states() -> Vec<GameState> {
+    let mut states: Vec<GameState> = vec![];
+    let mut rng = SmallRng::seed_from_u64(0);
+    for mode in ["Light", "Shadow"] {
+        let settings = GameSettings {
+            number_of_wind_cards: Some(2),
+            force_wind_spirit_inclusion: true,
+            force_light_and_shadow: true,
+            light_and_shadow_mode: Some(mode.to_string()),
+            ..GameSettings::default()
+        };
+        let mode_states = states.len();
+        while states.len() - mode_states < SAMPLES {
+            let mut state = GameState::Playing {
+                board: Board::new_with_settings_rng(settings.clone(), &mut rng),
+            };
+            while let GameState::Playing { board } = state {
+                states.push(state);
+                let game_move = board
+                    .random_legal_move(&mut rng)
+                    .expect("No legal moves");
+                state = state.try_move(game_move).expect("Generated illegal move");
+            }
         }
     }
+    return states;
 }
@@ -0,0 +1,148 @@
+use std::sync::OnceLock;
+
+use enum_iterator::IntoEnumIterator;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+
+use crate::bitboard::{points, square, BitBoard, SQUARES};
+use crate::models::{Board, Card, Player};
+
+// Fixed seed so that hashes are stable between runs
+const ZOBRIST_SEED: u64 = 0x6f6e_6974_616d_6121;
+
+struct ZobristKeys {
+    kings: [[u64; SQUARES]; 2],
+    pawns: [[u64; SQUARES]; 2],
+    ninjas: [[u64; SQUARES]; 2],
+    revealed_ninjas: [[u64; SQUARES]; 2],
+    wind_spirit: [u64; SQUARES],
+    // Indexed by [side][card], a hand is unordered so each card held is keyed separately
+    hands: [Vec<u64>; 2],
+    spare_card: Vec<u64>,
+    wind_move_card: Vec<u64>,
+    ninja_move_card: Vec<u64>,
+    blue_turn: u64,
+    wind_move_pending: u64,
+    ninja_move_pending: u64,
+    shadow_mode: u64,
+    light_mode: u64,
+}
+
+impl ZobristKeys {
+    fn new() -> ZobristKeys {
+        let mut rng = SmallRng::seed_from_u64(ZOBRIST_SEED);
+        let card_count = Card::into_enum_iter().count();
+        let mut squares = || -> [u64; SQUARES] { std::array::from_fn(|_| rng.gen()) };
+        let kings = [squares(), squares()];
+        let pawns = [squares(), squares()];
+        let ninjas = [squares(), squares()];
+        let revealed_ninjas = [squares(), squares()];
+        let wind_spirit = squares();
+        let mut cards = || -> Vec<u64> { (0..card_count).map(|_| rng.gen()).collect() };
+        let hands = [cards(), cards()];
+        let spare_card = cards();
+        let wind_move_card = cards();
+        let ninja_move_card = cards();
+        ZobristKeys {
+            kings,
+            pawns,
+            ninjas,
+            revealed_ninjas,
+            wind_spirit,
+            hands,
+            spare_card,
+            wind_move_card,
+            ninja_move_card,
+            blue_turn: rng.gen(),
+            wind_move_pending: rng.gen(),
+            ninja_move_pending: rng.gen(),
+            shadow_mode: rng.gen(),
+            light_mode: rng.gen(),
+        }
+    }
+}
+
+fn keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(ZobristKeys::new)
+}
+
+fn mask_hash(keys: &[u64; SQUARES], mask: u32) -> u64 {
+    points(mask).fold(0, |hash, pos| hash ^ keys[square(pos)])
+}
+
+fn pieces_hash(bits: &BitBoard) -> u64 {
+    let keys = keys();
+    let mut hash = mask_hash(&keys.wind_spirit, bits.wind_spirit);
+    for side in 0..2 {
+        hash ^= mask_hash(&keys.kings[side], bits.kings[side]);
+        hash ^= mask_hash(&keys.pawns[side], bits.pawns[side]);
+        hash ^= mask_hash(&keys.ninjas[side], bits.ninjas[side]);
+        hash ^= mask_hash(&keys.revealed_ninjas[side], bits.revealed_ninjas[side]);
+    }
+    hash
+}
+
+/// Everything but the pieces: cards, turn and pending follow up moves
+fn state_hash(bits: &BitBoard) -> u64 {
+    let keys = keys();
+    let card = |keys: &[u64], card: Card| keys[card.index() as usize];
+    let mut hash = card(&keys.spare_card, bits.spare_card);
+    for (side, hand) in bits.hands.iter().enumerate() {
+        for &held in hand {
+            hash ^= card(&keys.hands[side], held);
+        }
+    }
+    if let Some(wind_card) = bits.wind_move_card {
+        hash ^= card(&keys.wind_move_card, wind_card);
+    }
+    if let Some(ninja_card) = bits.ninja_move_card {
+        hash ^= card(&keys.ninja_move_card, ninja_card);
+    }
+    if bits.turn == Player::Blue {
+        hash ^= keys.blue_turn;
+    }
+    if bits.wind_move_pending {
+        hash ^= keys.wind_move_pending;
+    }
+    if bits.ninja_move_pending {
+        hash ^= keys.ninja_move_pending;
+    }
+    if bits.shadow_mode {
+        hash ^= keys.shadow_mode;
+    }
+    if bits.light_mode {
+        hash ^= keys.light_mode;
+    }
+    hash
+}
+
+/// Hash of the whole position, computed from scratch
+pub fn hash(bits: &BitBoard) -> u64 {
+    pieces_hash(bits) ^ state_hash(bits)
+}
+
+/// Value to XOR into the hash of `old` to get the hash of `new`.
+///
+/// Only squares whose occupancy changed are visited, so this is much cheaper than `hash` after a move.
+pub fn delta(old: &BitBoard, new: &BitBoard) -> u64 {
+    let changed = BitBoard {
+        wind_spirit: old.wind_spirit ^ new.wind_spirit,
+        kings: [old.kings[0] ^ new.kings[0], old.kings[1] ^ new.kings[1]],
+        pawns: [old.pawns[0] ^ new.pawns[0], old.pawns[1] ^ new.pawns[1]],
+        ninjas: [old.ninjas[0] ^ new.ninjas[0], old.ninjas[1] ^ new.ninjas[1]],
+        revealed_ninjas: [
+            old.revealed_ninjas[0] ^ new.revealed_ninjas[0],
+            old.revealed_ninjas[1] ^ new.revealed_ninjas[1],
+        ],
+        ..*new
+    };
+    pieces_hash(&changed) ^ state_hash(old) ^ state_hash(new)
+}
+
+impl Board {
+    /// Zobrist hash of the position, see `BitBoard::hash` for the incrementally updated value
+    pub fn zobrist_hash(&self) -> u64 {
+        BitBoard::from(self).hash
+    }
+}
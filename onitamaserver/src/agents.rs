@@ -1,23 +1,88 @@
 use std::time::Duration;
+use std::{env, fs};
 
 use actix::{Actor, Addr, AsyncContext, Handler, StreamHandler, SyncArbiter, SyncContext};
 use actix_web_actors::ws;
+use rand::prelude::*;
 use serde_cbor::ser;
 
-use onitamalib::{AiAgent, GameMessage, GameState, Player};
+use onitamalib::parallel::Ponderer;
+use onitamalib::record::BLUE_AGENT_TAG;
+use onitamalib::{
+    AiAgent, Board, EvalWeights, GameMessage, GameRecord, GameState, Move, Player, SearchLimits, SkillLevel,
+};
 
-use crate::messages::{AgentRequest, AgentResponse};
+use crate::archive;
+use crate::messages::{AgentRequest, AgentResponse, AgentTurn};
 
 pub struct Agent {
     state: GameState,
+    /// Follows the game to keep track of what it has seen of the player's hidden Ninjas,
+    /// taken by `ponderer` while it searches during the player's turn
+    player: Option<Box<dyn onitamalib::Agent>>,
+    ponderer: Option<Ponderer>,
     id: String,
     ai: AiAgent,
+    skill: SkillLevel,
+    /// Record of the game in play, started by its first move after setup
+    record: Option<GameRecord>,
 }
 
 impl Agent {
-    pub fn new(id: String, ai: AiAgent) -> Agent {
-        let state = GameState::new();
-        Agent { id, state, ai }
+    pub fn new(id: String, ai: AiAgent, skill: SkillLevel) -> Agent {
+        let mut player = ai.build_parallel_at_skill_with_weights(search_threads(), skill, search_weights());
+        let state = Agent::new_game(player.as_mut());
+        Agent {
+            id,
+            state,
+            player: Some(player),
+            ponderer: None,
+            ai,
+            skill,
+            record: None,
+        }
+    }
+
+    // The agent plays Blue, both sides' Ninjas are placed at random as soon as the game starts
+    fn new_game(player: &mut dyn onitamalib::Agent) -> GameState {
+        let mut state = GameState::new();
+        player.new_game(&state, Player::Blue);
+        for side in [Player::Red, Player::Blue] {
+            for placement in state.random_ninja_placements(side, &mut thread_rng()) {
+                player.observe_move(&state, placement);
+                state = state
+                    .try_move(placement)
+                    .expect("Random placements are valid");
+            }
+        }
+        state
+    }
+}
+
+/// Threads each AI searches with, set by `AI_THREADS` and otherwise one for each core
+fn search_threads() -> usize {
+    env::var("AI_THREADS")
+        .ok()
+        .and_then(|threads| threads.parse().ok())
+        .unwrap_or_else(onitamalib::parallel::default_threads)
+}
+
+/// Evaluation weights read from the JSON file at `AI_WEIGHTS`, such as one written by `tuneweights`,
+/// otherwise the defaults
+fn search_weights() -> EvalWeights {
+    let path = match env::var("AI_WEIGHTS") {
+        Ok(path) => path,
+        Err(_) => return EvalWeights::default(),
+    };
+    let weights = fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|json| EvalWeights::from_json(&json).map_err(|err| err.to_string()));
+    match weights {
+        Ok(weights) => weights,
+        Err(err) => {
+            error!("Failed to load weights from {}, using the defaults: {}", path, err);
+            EvalWeights::default()
+        }
     }
 }
 
@@ -31,63 +96,124 @@ pub enum AgentException {
 const TIMEOUT: Duration = Duration::from_millis(500);
 
 impl Agent {
-    fn play_move(&mut self, state: GameState) -> Result<GameMessage, AgentException> {
-        // The state is guaranteed to be Playing
-        let (game_move, expected_score) = match self.ai.play_move(&state, TIMEOUT) {
+    fn agent_to_move(&self) -> bool {
+        matches!(self.state, GameState::Playing { board } if board.turn == Player::Blue)
+    }
+    // Stops pondering first, so the agent can use what it found
+    fn player(&mut self) -> &mut dyn onitamalib::Agent {
+        if let Some(ponderer) = self.ponderer.take() {
+            self.player = Some(ponderer.stop());
+        }
+        self.player
+            .as_deref_mut()
+            .expect("Agent is either idle or pondering")
+    }
+    // Searches while the player thinks, until their next message arrives
+    fn ponder(&mut self) {
+        if !matches!(self.state, GameState::Playing { board } if board.turn == Player::Red) {
+            return;
+        }
+        if let Some(player) = self.player.take() {
+            self.ponderer = Some(Ponderer::start(player, self.state));
+        }
+    }
+    // The player only gets to see their own view of the agent's state
+    fn update(&self, board: Board, game_move: Move) -> GameMessage {
+        GameMessage::Update {
+            state: self.state.view_for(Player::Red),
+            last_move: board.move_view_for(game_move, Player::Red),
+        }
+    }
+    fn initialize(&self) -> GameMessage {
+        GameMessage::Initialize {
+            state: self.state.view_for(Player::Red),
+            room_id: "ai".to_string(),
+            player: Player::Red,
+            waiting: false,
+        }
+    }
+    // Once the game is over its record is archived, and the player gets a copy
+    // Placements stay secret, the record starts from the position they lead to
+    fn record_move(&mut self, previous: GameState, game_move: Move) -> Option<GameMessage> {
+        let board = match previous {
+            GameState::Playing { board } => board,
+            GameState::Setup { .. } | GameState::Finished { .. } => return None,
+        };
+        let agent = format!("{:?} at skill level {}", self.ai, self.skill.level());
+        let record = self.record.get_or_insert_with(|| {
+            let mut record = archive::new_record(board, "AI");
+            record.set_tag(BLUE_AGENT_TAG, &agent);
+            record
+        });
+        record.push(game_move, &self.state);
+        if !self.state.finished() {
+            return None;
+        }
+        let record = self.record.take()?;
+        archive::archive(&record);
+        Some(GameMessage::Record { record })
+    }
+    fn play_move(&mut self) -> Result<Vec<GameMessage>, AgentException> {
+        let board = self.state.board();
+        let current = self.state;
+        let report = match self.player().choose_move(&current, SearchLimits::time(TIMEOUT)) {
             None => {
                 error!("No moves available");
                 return Err(AgentException::AgentError);
             }
-            Some((best_move, best_score)) => (best_move, best_score),
+            Some(report) => report,
         };
-        info!(
-            "Expected score: {}, move = {:?}",
-            expected_score, &game_move
-        );
-        self.state = match state.try_move(game_move) {
+        let game_move = report.game_move;
+        info!("Expected score: {}, move = {:?}", report.score, &game_move);
+        let state = match self.state.try_move(game_move) {
             Ok(state) => state,
             Err(err) => {
                 error!("Agent attempted invalid move: {}", err);
                 return Err(AgentException::AgentError);
             }
         };
-        let msg = GameMessage::Move { game_move };
-        Ok(msg)
+        // The player only sees as much of the search as they could of the game
+        let report = report.view_for(&self.state, Player::Red);
+        self.player().observe_move(&current, game_move);
+        self.state = state;
+        let mut msgs = vec![self.update(board, game_move)];
+        msgs.extend(report.map(|report| GameMessage::SearchReport { report }));
+        msgs.extend(self.record_move(current, game_move));
+        Ok(msgs)
     }
-    fn handle_game_message(&mut self, msg: GameMessage) -> Result<GameMessage, AgentException> {
-        match (&self.state, msg) {
+    fn handle_game_message(&mut self, msg: GameMessage) -> Result<Vec<GameMessage>, AgentException> {
+        match (self.state, msg) {
             (GameState::Finished { .. }, GameMessage::RequestRematch) => {
                 info!("Starting rematch");
-                self.state = GameState::new();
-                Ok(GameMessage::Initialize {
-                    state: self.state.clone(),
-                    room_id: "ai".to_string(),
-                    player: Player::Red,
-                    waiting: false,
-                })
+                self.state = Agent::new_game(self.player());
+                self.record = None;
+                Ok(vec![self.initialize()])
             }
             (_, GameMessage::Joined) => {
                 info!("Game started");
-                Ok(GameMessage::Initialize {
-                    state: self.state.clone(),
-                    room_id: "ai".to_string(),
-                    player: Player::Red,
-                    waiting: false,
-                })
+                Ok(vec![self.initialize()])
             }
             (state, GameMessage::Move { game_move }) => {
-                let state = match state.try_move(game_move) {
+                if self.agent_to_move() {
+                    warn!("Player moved during the agent's turn");
+                    return Err(AgentException::InvalidMessageForState);
+                }
+                let board = state.board();
+                let next = match state.try_move(game_move) {
                     Ok(state) => state,
                     Err(err) => {
                         warn!("Invalid Move: {}", err);
                         return Err(AgentException::IllegalMove);
                     }
                 };
-                self.state = state;
-                match state {
-                    GameState::Finished { .. } => Ok(GameMessage::RequestRematch),
-                    state => self.play_move(state),
+                self.player().observe_move(&state, game_move);
+                self.state = next;
+                let mut msgs = vec![self.update(board, game_move)];
+                msgs.extend(self.record_move(state, game_move));
+                if self.state.finished() {
+                    msgs.push(GameMessage::RequestRematch);
                 }
+                Ok(msgs)
             }
             (state, msg) => {
                 warn!("Unexpected transition: state={:?}, msg={:?}", state, msg);
@@ -95,36 +221,68 @@ impl Agent {
             }
         }
     }
+    fn log_result(&self) {
+        if let GameState::Finished { winner, .. } = &self.state {
+            let won = match winner {
+                Player::Red => "won",
+                Player::Blue => "lost",
+            };
+            info!(
+                "Game finished, player {} against {:?} at skill level {}: {}",
+                won, self.ai, self.skill.level(), self.id
+            );
+        }
+    }
+    // The agent moves in its own message, so the player sees their move before the agent is done thinking
+    // Otherwise the agent ponders until the player's next message
+    fn schedule_turn(&mut self, addr: Addr<AgentWs>, ctx: &mut SyncContext<Self>) {
+        if self.agent_to_move() {
+            ctx.address().do_send(AgentTurn { addr });
+        } else {
+            self.ponder();
+        }
+    }
 }
 
 impl Actor for Agent {
     type Context = SyncContext<Self>;
     fn stopped(&mut self, _ctx: &mut Self::Context) {
         log::info!("Agent stopped");
+        // Games the player walked away from are kept too
+        if let Some(record) = self.record.take() {
+            archive::archive(&record);
+        }
     }
 }
 
 impl Handler<AgentRequest> for Agent {
     type Result = ();
 
-    fn handle(&mut self, msg: AgentRequest, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: AgentRequest, ctx: &mut Self::Context) -> Self::Result {
         let AgentRequest { msg, addr } = msg;
         let resp = self.handle_game_message(msg);
-        match &self.state {
-            GameState::Finished { winner, .. } => {
-                let won = match winner {
-                    Player::Red => "won",
-                    Player::Blue => "lost",
-                };
-                info!(
-                    "Game finished, player {} against {:?}: {}",
-                    won, self.ai, self.id
-                );
-            }
-            _ => {}
-        };
-        let resp = AgentResponse { resp };
-        addr.do_send(resp);
+        self.log_result();
+        addr.do_send(AgentResponse { resp });
+        self.schedule_turn(addr, ctx);
+    }
+}
+
+impl Handler<AgentTurn> for Agent {
+    type Result = ();
+
+    fn handle(&mut self, msg: AgentTurn, ctx: &mut Self::Context) -> Self::Result {
+        let AgentTurn { addr } = msg;
+        if !self.agent_to_move() {
+            return;
+        }
+        let resp = self.play_move();
+        self.log_result();
+        let failed = resp.is_err();
+        addr.do_send(AgentResponse { resp });
+        // A pending Ninja follow up is another turn for the agent
+        if !failed {
+            self.schedule_turn(addr, ctx);
+        }
     }
 }
 
@@ -133,8 +291,8 @@ pub struct AgentWs {
 }
 
 impl AgentWs {
-    pub fn new(id: String, ai: AiAgent) -> AgentWs {
-        let agent = SyncArbiter::start(1, move || Agent::new(id.clone(), ai));
+    pub fn new(id: String, ai: AiAgent, skill: SkillLevel) -> AgentWs {
+        let agent = SyncArbiter::start(1, move || Agent::new(id.clone(), ai, skill));
         AgentWs { agent }
     }
 }
@@ -188,8 +346,8 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for AgentWs {
 impl Handler<AgentResponse> for AgentWs {
     type Result = ();
     fn handle(&mut self, msg: AgentResponse, ctx: &mut Self::Context) {
-        let msg = match msg.resp {
-            Ok(msg) => msg,
+        let msgs = match msg.resp {
+            Ok(msgs) => msgs,
             Err(err) => {
                 let msg = format!("Error: {:?}", err);
                 ctx.text(msg);
@@ -197,7 +355,9 @@ impl Handler<AgentResponse> for AgentWs {
                 // ctx.close(None);
             }
         };
-        let msg = ser::to_vec(&msg).expect("failed to serialize message");
-        ctx.binary(msg);
+        for msg in msgs {
+            let msg = ser::to_vec(&msg).expect("failed to serialize message");
+            ctx.binary(msg);
+        }
     }
 }
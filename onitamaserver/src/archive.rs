@@ -0,0 +1,53 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+use uuid::Uuid;
+
+use onitamalib::record::{DATE_TAG, EVENT_TAG};
+use onitamalib::{Board, GameRecord, GameSettings};
+
+/// Starts the record of a game on the server from its first position after setup
+pub fn new_record(board: Board, event: &str) -> GameRecord {
+    let mut record = GameRecord::new(GameSettings::default(), board);
+    record.set_tag(EVENT_TAG, event);
+    record.set_tag(DATE_TAG, &today());
+    record
+}
+
+/// Keeps a record in its own file in `GAME_ARCHIVE_DIR`, or in the log when that isn't set
+pub fn archive(record: &GameRecord) {
+    let dir = match env::var("GAME_ARCHIVE_DIR") {
+        Ok(dir) => dir,
+        Err(_) => {
+            info!("Game record:\n{}", record);
+            return;
+        }
+    };
+    let path = Path::new(&dir).join(format!("{}.txt", Uuid::new_v4()));
+    match fs::create_dir_all(&dir).and_then(|_| fs::write(&path, record.to_string())) {
+        Ok(_) => info!("Archived game record: {}", path.display()),
+        Err(err) => error!("Failed to archive game record {}: {}", path.display(), err),
+    };
+}
+
+/// The date in UTC as YYYY.MM.DD, like PGN dates
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86400) as i64;
+    // Days since 1970-01-01 to a civil date, from Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -20,6 +20,7 @@ use crate::routes::{create_room, event_receive, join_room, ServerData};
 
 #[cfg(feature = "agent")]
 mod agents;
+mod archive;
 mod messages;
 mod rooms;
 mod routes;
@@ -61,10 +61,17 @@ cfg_if::cfg_if! {
             pub addr: Addr<AgentWs>,
         }
 
+        /// Asks the agent to move if it is its turn
+        #[derive(Message)]
+        #[rtype(result = "()")]
+        pub struct AgentTurn {
+            pub addr: Addr<AgentWs>,
+        }
+
         #[derive(Message)]
         #[rtype(result = "()")]
         pub struct AgentResponse {
-            pub resp: Result<GameMessage,AgentException>,
+            pub resp: Result<Vec<GameMessage>,AgentException>,
         }
     }
 }
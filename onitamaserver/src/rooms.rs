@@ -8,7 +8,9 @@ use tokio::task::JoinHandle;
 use tokio::time;
 use uuid::Uuid;
 
-use onitamalib::{GameMessage, GameState, Move, Player};
+use onitamalib::{Board, GameMessage, GameRecord, GameState, Move, Player};
+
+use crate::archive;
 
 use crate::messages::{
     AddressedGameMessage, CloseRoom, CreateRoom, JoinRoom, JoinedRoom, LeftRoom, SocketGameMessage,
@@ -145,21 +147,32 @@ pub struct OnitamaRoom {
     key: Uuid,
     requested_rematch: Option<Player>,
     close_room_handle: Option<JoinHandle<()>>,
+    /// Record of the game in play, started by its first move after setup
+    record: Option<GameRecord>,
 }
 
 impl OnitamaRoom {
     pub fn new() -> OnitamaRoom {
         OnitamaRoom {
-            game_state: GameState::new(),
+            game_state: new_game(),
             red: None,
             blue: None,
             key: Uuid::new_v4(),
             requested_rematch: None,
             close_room_handle: None,
+            record: None,
         }
     }
 }
 
+/// A fresh game with both sides' Ninjas placed at random, players can't place their own yet
+fn new_game() -> GameState {
+    let mut rng = thread_rng();
+    GameState::new()
+        .with_random_ninjas(Player::Red, &mut rng)
+        .with_random_ninjas(Player::Blue, &mut rng)
+}
+
 impl Actor for OnitamaRoom {
     type Context = Context<Self>;
 }
@@ -245,7 +258,7 @@ impl Handler<JoinRoom> for OnitamaRoom {
             room_key,
             player,
             waiting,
-            state: self.game_state,
+            state: self.game_state.view_for(player),
         };
         socket.do_send(msg);
         // Send join message
@@ -289,6 +302,10 @@ impl Handler<CloseRoom> for OnitamaRoom {
         match (&self.blue, &self.red) {
             (None, None) => {
                 info!("Room Closing: {}", self.key.clone());
+                // Games the players walked away from are kept too
+                if let Some(record) = self.record.take() {
+                    archive::archive(&record);
+                }
                 ctx.stop();
             }
             _ => {
@@ -299,9 +316,26 @@ impl Handler<CloseRoom> for OnitamaRoom {
 }
 
 impl OnitamaRoom {
+    // The room keeps the full state, each player only gets their own view of it
+    fn send_update(&self, board: Board, game_move: Move) {
+        for player in [Player::Red, Player::Blue].iter().copied() {
+            let msg = GameMessage::Update {
+                state: self.game_state.view_for(player),
+                last_move: board.move_view_for(game_move, player),
+            };
+            self.send_to_player(player, msg);
+        }
+    }
     fn handle_move(&mut self, game_move: Move, player: Player) {
+        if let Move::PlaceNinja { player: owner, .. } = game_move {
+            return self.handle_placement(game_move, owner, player);
+        }
         let board = match self.game_state {
             GameState::Playing { board } => board,
+            GameState::Setup { .. } => {
+                info!("Attempted move during setup");
+                return;
+            }
             GameState::Finished { .. } => {
                 info!("Attempted move on finished game");
                 return;
@@ -319,9 +353,38 @@ impl OnitamaRoom {
             }
         };
         self.game_state = new_state;
-        let next_player = player.invert();
-        let msg = GameMessage::Move { game_move };
-        self.send_to_player(next_player, msg);
+        self.send_update(board, game_move);
+        self.record_move(board, game_move);
+    }
+    // Once the game is over its record is archived, and both players get a copy
+    fn record_move(&mut self, board: Board, game_move: Move) {
+        let event = format!("Online room {}", self.key);
+        let record = self
+            .record
+            .get_or_insert_with(|| archive::new_record(board, &event));
+        record.push(game_move, &self.game_state);
+        if !self.game_state.finished() {
+            return;
+        }
+        if let Some(record) = self.record.take() {
+            archive::archive(&record);
+            self.broadcast(GameMessage::Record { record });
+        }
+    }
+    fn handle_placement(&mut self, game_move: Move, owner: Player, player: Player) {
+        if owner != player {
+            error!("Player attempted to place opponent's Ninja");
+            return;
+        }
+        let board = self.game_state.board();
+        self.game_state = match self.game_state.try_move(game_move) {
+            Ok(new_state) => new_state,
+            Err(err) => {
+                error!("Player placed Ninja illegally: {:?}", err);
+                return;
+            }
+        };
+        self.send_update(board, game_move);
     }
     fn handle_rematch_request(&mut self, player: Player) {
         let requested_player = match self.requested_rematch {
@@ -338,12 +401,13 @@ impl OnitamaRoom {
             info!("Player requsted rematch multiple times");
         } else {
             self.requested_rematch = None;
-            let state = GameState::new();
+            let state = new_game();
             self.game_state = state;
+            self.record = None;
             self.send_to_player(
                 Player::Red,
                 GameMessage::Initialize {
-                    state,
+                    state: state.view_for(Player::Red),
                     room_id: self.key.to_string(),
                     player: Player::Red,
                     waiting: false,
@@ -352,7 +416,7 @@ impl OnitamaRoom {
             self.send_to_player(
                 Player::Blue,
                 GameMessage::Initialize {
-                    state,
+                    state: state.view_for(Player::Blue),
                     room_id: self.key.to_string(),
                     player: Player::Blue,
                     waiting: false,
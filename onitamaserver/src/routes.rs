@@ -49,7 +49,7 @@ pub struct ServerData {
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "agent")] {
-        use onitamalib::AiAgent;
+        use onitamalib::{AiAgent, SkillLevel};
 
         use crate::agents::AgentWs;
         pub async fn ai_room(
@@ -58,15 +58,21 @@ cfg_if::cfg_if! {
             stream: web::Payload,
         ) -> Result<HttpResponse, Error> {
             let id = get_identifier(&req);
-            let difficulty = difficulty.as_str();
-            let ai = match difficulty {
+            let (name, skill) = match SkillLevel::from_difficulty(difficulty.as_str()) {
+                Ok(difficulty) => difficulty,
+                Err(err) => {
+                    return Err(error::ErrorBadRequest(err));
+                }
+            };
+            let ai = match name {
                 "easy" => AiAgent::Greedy,
                 "medium" => AiAgent::PureMonteCarlo,
                 "hard" => AiAgent::HybridMonteCarlo,
+                "expert" => AiAgent::InformationSetMonteCarlo,
                 _ => AiAgent::PureMonteCarlo,
             };
-            info!("AI Game Start: {}, ({:?})", &id, ai);
-            let actor = AgentWs::new(id, ai);
+            info!("AI Game Start: {}, ({:?} at skill level {})", &id, ai, skill.level());
+            let actor = AgentWs::new(id, ai, skill);
             let resp = ws::start(actor, &req, stream);
             resp
         }
@@ -45,13 +45,16 @@ const useSingleplayer = (difficulty, trainingMode) => {
       onEvent,
     );
 
-    worker.onmessage = (m) => game.move(m.data, false);
+    worker.onmessage = (m) => {
+      game.move(m.data.gameMove, false);
+    };
     if (trainer) {
       trainer.onmessage = (m) => {
         const ranksByCardSrc = {};
-        const max = m.data.length > 0 ? Math.max(...m.data.map(([, ranking]) => ranking)) : 0;
-        const min = m.data.length > 0 ? Math.min(...m.data.map(([, ranking]) => ranking)) : 0;
-        m.data.forEach(([{ src, dst, card }, ranking]) => {
+        const rankedMoves = m.data ? m.data.rankedMoves : [];
+        const max = rankedMoves.length > 0 ? Math.max(...rankedMoves.map(([, ranking]) => ranking)) : 0;
+        const min = rankedMoves.length > 0 ? Math.min(...rankedMoves.map(([, ranking]) => ranking)) : 0;
+        rankedMoves.forEach(([{ src, dst, card }, ranking]) => {
           const cardSrc = `${card},${src.x},${src.y}`;
           if (!ranksByCardSrc[cardSrc]) {
             ranksByCardSrc[cardSrc] = {};